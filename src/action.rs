@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
+use crate::{
+    app::Mode,
+    executor::{body::Progress, HttpResponse},
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
 pub enum Action {
     Tick,
@@ -12,4 +17,26 @@ pub enum Action {
     ClearScreen,
     Error(String),
     Help,
+    /// A response for the request sent from the tab with the given id.
+    ResponseReceived(usize, Box<HttpResponse>),
+    /// How far along the body of the response for the given tab is.
+    ResponseProgress(usize, Progress),
+    RequestFailed(usize, String),
+    SwitchMode(Mode),
+    Search,
+    SearchNext,
+    SearchPrevious,
+    ToggleSearchCase,
+    ToggleSearchRegex,
+    SaveBody,
+    OpenCookies,
+    CancelRequest,
+    /// Fetch the GraphQL schema, or the gRPC services, of the current tab's endpoint.
+    FetchSchema,
+    /// Replace the URL of the current tab.
+    SetUrl(String),
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
 }
@@ -30,19 +30,29 @@ pub enum Mode {
     #[default]
     Url,
     Request,
+    Response,
+    Search,
+    Filter,
+    Prompt,
+    Cookies,
+    Confirm,
     Home,
 }
 
 impl App {
-    pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
+    pub fn new(tick_rate: f64, frame_rate: f64, workspace: Option<String>) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
+        let mut config = Config::new()?;
+        if let Some(workspace) = workspace {
+            config.config.workspace = workspace;
+        }
         Ok(Self {
             tick_rate,
             frame_rate,
             components: vec![Box::new(Home::new())],
             should_quit: false,
             should_suspend: false,
-            config: Config::new()?,
+            config,
             mode: Mode::Home,
             last_tick_key_events: Vec::new(),
             action_tx,
@@ -109,13 +119,10 @@ impl App {
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         let action_tx = self.action_tx.clone();
-        let Some(keymap) = self.config.keybindings.get(&self.mode) else {
-            return Ok(());
-        };
-        match keymap.get(&vec![key]) {
+        match self.keymap_action(&[key]) {
             Some(action) => {
                 info!("Got action: {action:?}");
-                action_tx.send(action.clone())?;
+                action_tx.send(action)?;
             }
             _ => {
                 // If the key was not handled as a single key action,
@@ -123,15 +130,25 @@ impl App {
                 self.last_tick_key_events.push(key);
 
                 // Check for multi-key combinations
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
+                if let Some(action) = self.keymap_action(&self.last_tick_key_events) {
                     info!("Got action: {action:?}");
-                    action_tx.send(action.clone())?;
+                    action_tx.send(action)?;
                 }
             }
         }
         Ok(())
     }
 
+    /// Looks up a key sequence in the bindings of the current mode, falling back to the global
+    /// bindings under `Home`.
+    fn keymap_action(&self, keys: &[KeyEvent]) -> Option<Action> {
+        [self.mode, Mode::Home]
+            .iter()
+            .filter_map(|mode| self.config.keybindings.get(mode))
+            .find_map(|keymap| keymap.get(keys))
+            .cloned()
+    }
+
     fn handle_actions(&mut self, tui: &mut Tui) -> Result<()> {
         while let Ok(action) = self.action_rx.try_recv() {
             if action != Action::Tick && action != Action::Render {
@@ -141,11 +158,18 @@ impl App {
                 Action::Tick => {
                     self.last_tick_key_events.drain(..);
                 }
-                Action::Quit => self.should_quit = true,
+                Action::Quit => {
+                    let mut confirmed = true;
+                    for component in self.components.iter_mut() {
+                        confirmed &= component.confirm_quit()?;
+                    }
+                    self.should_quit = confirmed;
+                }
                 Action::Suspend => self.should_suspend = true,
                 Action::Resume => self.should_suspend = false,
                 Action::ClearScreen => tui.terminal.clear()?,
                 Action::Resize(w, h) => self.handle_resize(tui, w, h)?,
+                Action::SwitchMode(mode) => self.mode = mode,
                 Action::Render => self.render(tui)?,
                 _ => {}
             }
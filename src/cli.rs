@@ -12,6 +12,10 @@ pub struct Cli {
     /// Frame rate, i.e. number of frames per second
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
+
+    /// Workspace to open, overriding the one in the configuration file
+    #[arg(short, long, value_name = "NAME")]
+    pub workspace: Option<String>,
 }
 
 const VERSION_MESSAGE: &str = concat!(
@@ -8,8 +8,14 @@ use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{action::Action, config::Config, tui::Event};
 
+pub mod confirm;
+pub mod cookies;
+pub mod events;
+pub mod grpc;
 pub mod home;
 pub mod request;
+pub mod response;
+pub mod websocket;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
@@ -118,6 +124,18 @@ pub trait Component {
         Ok(None)
     }
 
+    /// Check whether the application may quit, e.g. with no unsaved work that would be lost.
+    ///
+    /// A component returning `false` is expected to ask the user, and to send [`Action::Quit`]
+    /// again once they agree.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool>` - Whether to quit now, or an error.
+    fn confirm_quit(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
     /// Render the component on the screen. (REQUIRED)
     ///
     /// # Arguments
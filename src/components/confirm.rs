@@ -0,0 +1,108 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Clear, Padding, Paragraph, Wrap};
+
+use crate::app::Mode;
+
+/// A yes or no question shown over the other panes, about something the caller does once the
+/// answer is yes.
+pub struct Confirm<T> {
+    question: Option<Question<T>>,
+}
+
+struct Question<T> {
+    text: String,
+    subject: T,
+    previous_mode: Mode,
+}
+
+impl<T> Default for Confirm<T> {
+    fn default() -> Self {
+        Self { question: None }
+    }
+}
+
+impl<T> Confirm<T> {
+    /// Asks a question about `subject`, going back to `previous_mode` once it's answered.
+    pub fn ask(&mut self, text: impl Into<String>, subject: T, previous_mode: Mode) {
+        self.question = Some(Question {
+            text: text.into(),
+            subject,
+            previous_mode,
+        });
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.question.is_some()
+    }
+
+    /// Handles a key, returning the mode to go back to once the question is answered, along with
+    /// the subject if the answer was yes.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<(Mode, Option<T>)> {
+        let yes = match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => true,
+            KeyCode::Char('n') | KeyCode::Esc => false,
+            _ => return None,
+        };
+        let question = self.question.take()?;
+        Some((question.previous_mode, yes.then_some(question.subject)))
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let Some(question) = &self.question else {
+            return;
+        };
+        let [_, area, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Max(60),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let block = Block::bordered()
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Confirm ")
+            .title_bottom(Line::raw(" <y> yes  <n> no ").right_aligned())
+            .padding(Padding::horizontal(1));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(question.text.clone())
+                .wrap(Wrap { trim: true })
+                .block(block),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_answer() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut confirm = Confirm::default();
+        confirm.ask("Close?", 7, Mode::Request);
+        assert_eq!(confirm.handle_key(key(KeyCode::Char('x'))), None);
+        assert!(confirm.is_open());
+        assert_eq!(
+            confirm.handle_key(key(KeyCode::Char('y'))),
+            Some((Mode::Request, Some(7)))
+        );
+        assert!(!confirm.is_open());
+
+        confirm.ask("Close?", 7, Mode::Url);
+        assert_eq!(
+            confirm.handle_key(key(KeyCode::Esc)),
+            Some((Mode::Url, None))
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::sync::{Arc, Mutex};
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Cell, Clear, Row, Table, TableState};
+use tracing::error;
+use tui_textarea::TextArea;
+use url::Url;
+
+use crate::action::Action;
+use crate::app::Mode;
+use crate::components::Component;
+use crate::executor::cookies::{format_date, now, Cookie, CookieJar};
+
+/// A cookie being added or edited, typed in `Set-Cookie` syntax.
+struct Editor {
+    /// The cookie being replaced, or `None` when adding one.
+    original: Option<Cookie>,
+    input: TextArea<'static>,
+    error: Option<String>,
+}
+
+/// A popup listing the cookies in the jar, where they can be edited, added and deleted.
+#[derive(Default)]
+pub struct CookieManager {
+    jar: Arc<Mutex<CookieJar>>,
+    /// Mode to switch back to on closing, or `None` while closed.
+    previous_mode: Option<Mode>,
+    table: TableState,
+    editor: Option<Editor>,
+}
+
+impl Editor {
+    fn new(original: Option<Cookie>) -> Self {
+        let text = original.as_ref().map(Cookie::to_set_cookie);
+        let mut input = TextArea::new(text.into_iter().collect());
+        input.set_placeholder_text("name=value; Domain=example.com; Path=/; Secure");
+        input.set_cursor_line_style(Style::default());
+        input.move_cursor(tui_textarea::CursorMove::End);
+        Self {
+            original,
+            input,
+            error: None,
+        }
+    }
+
+    /// Parses the input as if it had been set by the cookie's own domain.
+    fn parse(&self) -> Result<Cookie, String> {
+        let text = self.input.lines().join("");
+        let domain = text
+            .split(';')
+            .skip(1)
+            .filter_map(|attribute| attribute.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("domain"))
+            .map(|(_, value)| value.trim().trim_start_matches('.').to_string())
+            .or_else(|| self.original.as_ref().map(|c| c.domain.clone()))
+            .ok_or("Add a Domain attribute")?;
+        let origin = Url::parse(&format!("https://{domain}/")).map_err(|err| err.to_string())?;
+        Cookie::parse(&text, &origin, now()).ok_or_else(|| "Not a valid cookie".into())
+    }
+}
+
+impl CookieManager {
+    pub fn open(&mut self, jar: Arc<Mutex<CookieJar>>, previous_mode: Mode) {
+        self.jar = jar;
+        self.previous_mode = Some(previous_mode);
+        self.table.select(Some(0));
+        self.editor = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.previous_mode.is_some()
+    }
+
+    fn close(&mut self) -> Option<Action> {
+        self.previous_mode.take().map(Action::SwitchMode)
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let len = self.jar.lock().unwrap().cookies().len();
+        self.table.selected().filter(|&i| i < len)
+    }
+
+    fn save(jar: &CookieJar) {
+        if let Err(err) = jar.save() {
+            error!("Failed to save cookies: {err}");
+        }
+    }
+
+    fn handle_editor_key(&mut self, key: KeyEvent) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => match editor.parse() {
+                Ok(cookie) => {
+                    let mut jar = self.jar.lock().unwrap();
+                    if let Some(i) = self.table.selected().filter(|_| editor.original.is_some()) {
+                        jar.remove(i);
+                    }
+                    jar.insert(cookie);
+                    Self::save(&jar);
+                    self.editor = None;
+                }
+                Err(err) => editor.error = Some(err),
+            },
+            KeyCode::Esc => self.editor = None,
+            _ => {
+                editor.input.input(key);
+                editor.error = None;
+            }
+        }
+    }
+
+    fn render_editor(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+        let title = if editor.original.is_some() {
+            " Edit cookie "
+        } else {
+            " Add cookie "
+        };
+        let mut block = Block::bordered()
+            .border_style(Style::default().fg(Color::White))
+            .title(title)
+            .title_bottom(Line::raw(" <Enter> save  <Esc> cancel ").right_aligned());
+        if let Some(err) = &editor.error {
+            block = block.title_bottom(Line::raw(format!(" {err} ")).red());
+        }
+        editor.input.set_block(block);
+        Clear.render(area, buf);
+        editor.input.render(area, buf);
+    }
+}
+
+impl Component for CookieManager {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if self.editor.is_some() {
+            self.handle_editor_key(key);
+            return Ok(None);
+        }
+        let len = self.jar.lock().unwrap().cookies().len();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = self.table.selected().map_or(0, |i| i + 1);
+                self.table.select(Some(next.min(len.saturating_sub(1))));
+            }
+            KeyCode::Char('a') => self.editor = Some(Editor::new(None)),
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(i) = self.selected() {
+                    let cookie = self.jar.lock().unwrap().cookies()[i].clone();
+                    self.editor = Some(Editor::new(Some(cookie)));
+                }
+            }
+            KeyCode::Delete | KeyCode::Char('d') => {
+                if let Some(i) = self.selected() {
+                    let mut jar = self.jar.lock().unwrap();
+                    jar.remove(i);
+                    Self::save(&jar);
+                    if i + 1 >= len {
+                        self.table.select(Some(i.saturating_sub(1)));
+                    }
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(self.close()),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.is_open() {
+            return Ok(());
+        }
+        let [_, area, _] = Layout::vertical([
+            Constraint::Percentage(10),
+            Constraint::Percentage(80),
+            Constraint::Percentage(10),
+        ])
+        .areas(area);
+        let area = area.inner(Margin::new(4, 0));
+        let [table_area, editor_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(if self.editor.is_some() { 3 } else { 0 }),
+        ])
+        .areas(area);
+
+        let rows: Vec<Row> = self
+            .jar
+            .lock()
+            .unwrap()
+            .cookies()
+            .iter()
+            .map(|cookie| {
+                let domain = if cookie.host_only {
+                    cookie.domain.clone()
+                } else {
+                    format!(".{}", cookie.domain)
+                };
+                let flags = [
+                    cookie.secure.then_some("Secure"),
+                    cookie.http_only.then_some("HttpOnly"),
+                    cookie.same_site.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+                Row::new([
+                    Cell::from(cookie.name.clone()),
+                    Cell::from(cookie.value.clone()),
+                    Cell::from(domain),
+                    Cell::from(cookie.path.clone()),
+                    Cell::from(cookie.expires.map_or_else(|| "Session".into(), format_date)),
+                    Cell::from(flags),
+                ])
+            })
+            .collect();
+        let empty = rows.is_empty();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(29),
+                Constraint::Length(20),
+            ],
+        )
+        .header(
+            Row::new(["Name", "Value", "Domain", "Path", "Expires", "Flags"])
+                .bold()
+                .underlined(),
+        )
+        .row_highlight_style(Style::default().reversed())
+        .block(
+            Block::bordered()
+                .border_style(Style::default().fg(Color::White))
+                .title(" Cookies ")
+                .title_bottom(
+                    Line::raw(" <a> add  <e> edit  <d> delete  <Esc> close ").right_aligned(),
+                ),
+        );
+
+        frame.render_widget(Clear, table_area);
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+        if empty {
+            let hint = table_area.inner(Margin::new(2, 2));
+            frame.render_widget(Line::raw("No cookies stored yet").dark_gray(), hint);
+        }
+        self.render_editor(editor_area, frame.buffer_mut());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_editor_parse() {
+        let mut editor = Editor::new(None);
+        editor.input.insert_str("a=1; Path=/x");
+        assert_eq!(editor.parse(), Err("Add a Domain attribute".into()));
+
+        editor.input.insert_str("; Domain=.example.com");
+        let cookie = editor.parse().unwrap();
+        assert_eq!(cookie.domain, "example.com");
+        assert!(!cookie.host_only);
+
+        let mut editor = Editor::new(Some(cookie.clone()));
+        editor.input.insert_str("; Secure");
+        assert_eq!(
+            editor.parse(),
+            Ok(Cookie {
+                secure: true,
+                ..cookie
+            })
+        );
+    }
+}
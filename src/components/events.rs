@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Padding, Paragraph};
+use ratatui::Frame;
+
+use crate::action::Action;
+use crate::components::websocket::format_time;
+use crate::components::Component;
+use crate::executor::sse::{Event, EventStream, SseEvent};
+use crate::executor::HttpResponse;
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    Connecting,
+    Open(Box<HttpResponse>),
+    Reconnecting(Duration, String),
+    Stopped,
+    Failed(String),
+}
+
+/// A server-sent event stream opened from a tab, shown as a live list of its events.
+#[derive(Default)]
+pub struct EventStreamPane {
+    focused: bool,
+    state: State,
+    stream: Option<EventStream>,
+    events: Vec<Event>,
+    /// Event types in the order they were first seen, to filter by.
+    types: Vec<String>,
+    /// Only events of this type are shown.
+    filter: Option<String>,
+    /// Number of events shown while the view is paused, the rest being buffered.
+    paused: Option<usize>,
+    /// First line of the list on screen.
+    offset: usize,
+    /// Keeps the newest events in view as they arrive, until the list is scrolled up.
+    follow: bool,
+    height: usize,
+}
+
+impl EventStreamPane {
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Whether a stream has been opened since the pane was last reset.
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, State::Idle)
+    }
+
+    /// Whether the stream is open or will be reopened.
+    pub fn is_running(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Starts showing a new stream, dropping the previous one and its events.
+    pub fn connect(&mut self, stream: EventStream) {
+        *self = Self {
+            focused: self.focused,
+            state: State::Connecting,
+            stream: Some(stream),
+            follow: true,
+            ..Self::default()
+        };
+    }
+
+    /// Closes the stream for good, keeping the events received so far.
+    pub fn stop(&mut self) {
+        if self.stream.take().is_some() {
+            self.state = State::Stopped;
+        }
+    }
+
+    /// Drops the stream and goes back to showing nothing, for when another request is sent.
+    pub fn reset(&mut self) {
+        *self = Self {
+            focused: self.focused,
+            ..Self::default()
+        };
+    }
+
+    /// Picks up whatever happened on the stream since the last call.
+    pub fn poll(&mut self) {
+        while let Some(event) = self.stream.as_mut().and_then(EventStream::poll) {
+            match event {
+                SseEvent::Connected(head) => self.state = State::Open(head),
+                SseEvent::Event(event) => self.push(event),
+                SseEvent::Reconnecting(delay, reason) => {
+                    self.state = State::Reconnecting(delay, reason)
+                }
+                SseEvent::Failed(err) => {
+                    self.state = State::Failed(err);
+                    self.stream = None;
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        if !self.types.contains(&event.event) {
+            self.types.push(event.event.clone());
+        }
+        self.events.push(event);
+    }
+
+    /// The events in view, leaving out those buffered while paused or of other types.
+    fn visible(&self) -> Vec<&Event> {
+        let shown = self.paused.unwrap_or(self.events.len());
+        self.events[..shown]
+            .iter()
+            .filter(|event| self.filter.as_ref().is_none_or(|kind| &event.event == kind))
+            .collect()
+    }
+
+    /// Shows only the next event type seen, or all of them after the last.
+    fn cycle_filter(&mut self) {
+        let next = match &self.filter {
+            None => 0,
+            Some(kind) => self
+                .types
+                .iter()
+                .position(|t| t == kind)
+                .map_or(0, |n| n + 1),
+        };
+        self.filter = self.types.get(next).cloned();
+        self.follow = true;
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = match self.paused {
+            Some(_) => None,
+            None => Some(self.events.len()),
+        };
+    }
+
+    fn scroll(&mut self, delta: isize) {
+        let max = self.visible().len().saturating_sub(self.height);
+        self.offset = self.offset.saturating_add_signed(delta).min(max);
+        self.follow = self.offset == max;
+    }
+
+    fn block(&self, title: String) -> Block<'static> {
+        let border_style = if self.focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered()
+            .border_style(border_style)
+            .title(title)
+            .padding(Padding::horizontal(1))
+    }
+
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        let mut line = match &self.state {
+            State::Idle => Line::raw("Not connected").dark_gray(),
+            State::Connecting => Line::from(vec![
+                "Connecting…".yellow(),
+                "  <Esc> to cancel".dark_gray(),
+            ]),
+            State::Open(head) => Line::from(vec![
+                format!("{} {}", head.status, head.reason).green().bold(),
+                "  streaming".dark_gray(),
+            ]),
+            State::Reconnecting(delay, reason) => Line::from(vec![
+                format!("Reconnecting in {} ms", delay.as_millis()).yellow(),
+                format!("  {reason}").dark_gray(),
+            ]),
+            State::Stopped => Line::raw("Stopped").yellow(),
+            State::Failed(err) => Line::raw(err.clone()).red(),
+        };
+        if let Some(shown) = self.paused {
+            let buffered = self.events.len() - shown;
+            line.push_span(format!("  paused, {buffered} new").cyan());
+        }
+        line.render(area, buf);
+    }
+
+    fn render_events(&mut self, area: Rect, buf: &mut Buffer) {
+        let visible = self.visible();
+        let title = match &self.filter {
+            Some(kind) => format!(" {} {kind} events of {} ", visible.len(), self.events.len()),
+            None => format!(" {} events ", visible.len()),
+        };
+        let block = self.block(title);
+        let height = block.inner(area).height as usize;
+        let offset = if self.follow {
+            visible.len().saturating_sub(height)
+        } else {
+            self.offset
+        };
+        let lines: Vec<Line> = visible
+            .into_iter()
+            .skip(offset)
+            .take(height)
+            .map(event_line)
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+        self.height = height;
+        self.offset = offset;
+    }
+
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        let filter = self.filter.as_deref().unwrap_or("all");
+        let pause = if self.paused.is_some() {
+            "resume"
+        } else {
+            "pause"
+        };
+        let mut hint = format!("<t> type: {filter}  <Space> {pause}");
+        if self.is_running() {
+            hint.push_str("  <Esc> stop");
+        }
+        Line::raw(hint).centered().render(area, buf);
+    }
+}
+
+impl Widget for &mut EventStreamPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let vertical = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]);
+        let [status_area, events_area, footer_area] = vertical.areas(area);
+
+        self.render_status(status_area, buf);
+        self.render_events(events_area, buf);
+        self.render_footer(footer_area, buf);
+    }
+}
+
+impl Component for EventStreamPane {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        let page = self.height.max(1) as isize;
+        match key.code {
+            KeyCode::Char('t') => self.cycle_filter(),
+            KeyCode::Char(' ') => self.toggle_pause(),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.scroll(isize::MAX),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Render {
+            self.poll();
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        frame.render_widget(self, area);
+
+        Ok(())
+    }
+}
+
+/// One line of the list: time, id, type and as much of the data as fits.
+fn event_line(event: &Event) -> Line<'static> {
+    let mut line = Line::from(format_time(event.time).dark_gray());
+    if let Some(id) = &event.id {
+        line.push_span(format!(" #{id}").dark_gray());
+    }
+    line.push_span(format!(" {} ", event.event).cyan().bold());
+    line.push_span(event.data.replace('\n', "⏎"));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn event(kind: &str, data: &str) -> Event {
+        Event {
+            id: None,
+            event: kind.into(),
+            data: data.into(),
+            time: SystemTime::now(),
+        }
+    }
+
+    fn visible_data(pane: &EventStreamPane) -> Vec<&str> {
+        pane.visible()
+            .into_iter()
+            .map(|event| event.data.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_and_pause() {
+        let mut pane = EventStreamPane::default();
+        pane.push(event("message", "a"));
+        pane.push(event("tick", "b"));
+        pane.push(event("message", "c"));
+        assert_eq!(pane.types, ["message", "tick"]);
+
+        pane.cycle_filter();
+        assert_eq!(visible_data(&pane), ["a", "c"]);
+        pane.cycle_filter();
+        assert_eq!(visible_data(&pane), ["b"]);
+        pane.cycle_filter();
+        assert_eq!(visible_data(&pane), ["a", "b", "c"]);
+
+        pane.toggle_pause();
+        pane.push(event("tick", "d"));
+        assert_eq!(visible_data(&pane), ["a", "b", "c"]);
+        pane.toggle_pause();
+        assert_eq!(visible_data(&pane), ["a", "b", "c", "d"]);
+    }
+}
@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Padding, Paragraph};
+use ratatui::Frame;
+
+use crate::action::Action;
+use crate::components::websocket::format_time;
+use crate::components::Component;
+use crate::executor::grpc::{code_name, GrpcCall, GrpcEvent, GrpcMessage, Status};
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    Calling,
+    Open,
+    Finished(Status, Duration),
+    Stopped,
+    Failed(String),
+}
+
+/// A gRPC call made from a tab, shown as the messages of its response as they arrive.
+#[derive(Default)]
+pub struct GrpcPane {
+    focused: bool,
+    state: State,
+    call: Option<GrpcCall>,
+    headers: Vec<(String, String)>,
+    messages: Vec<GrpcMessage>,
+    /// First line of the response on screen.
+    offset: usize,
+    /// Keeps the newest messages in view as they arrive, until the response is scrolled up.
+    follow: bool,
+    height: usize,
+}
+
+impl GrpcPane {
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Whether a call has been made since the pane was last reset.
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, State::Idle)
+    }
+
+    /// Whether the call is still waiting for messages.
+    pub fn is_running(&self) -> bool {
+        self.call.is_some()
+    }
+
+    /// Starts showing a new call, dropping the previous one.
+    pub fn connect(&mut self, call: GrpcCall) {
+        *self = Self {
+            focused: self.focused,
+            state: State::Calling,
+            call: Some(call),
+            follow: true,
+            ..Self::default()
+        };
+    }
+
+    /// Cancels the call, keeping the messages received so far.
+    pub fn stop(&mut self) {
+        if self.call.take().is_some() {
+            self.state = State::Stopped;
+        }
+    }
+
+    /// Drops the call and goes back to showing nothing, for when another request is sent.
+    pub fn reset(&mut self) {
+        *self = Self {
+            focused: self.focused,
+            ..Self::default()
+        };
+    }
+
+    /// Picks up whatever happened during the call since the last call.
+    pub fn poll(&mut self) {
+        while let Some(event) = self.call.as_mut().and_then(GrpcCall::poll) {
+            match event {
+                GrpcEvent::Headers(headers) => {
+                    self.headers = headers;
+                    self.state = State::Open;
+                }
+                GrpcEvent::Message(message) => self.messages.push(message),
+                GrpcEvent::Finished(status, elapsed) => {
+                    self.state = State::Finished(status, elapsed);
+                    self.call = None;
+                }
+                GrpcEvent::Failed(err) => {
+                    self.state = State::Failed(err);
+                    self.call = None;
+                }
+            }
+        }
+    }
+
+    /// The headers, each message and then the trailers, one after the other.
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let section = |lines: &mut Vec<Line<'static>>, title: &str, pairs: &[(String, String)]| {
+            if pairs.is_empty() {
+                return;
+            }
+            lines.push(Line::raw(title.to_string()).bold());
+            for (name, value) in pairs {
+                lines.push(Line::from(vec![
+                    format!("{name}: ").dark_gray(),
+                    value.clone().into(),
+                ]));
+            }
+            lines.push(Line::default());
+        };
+        section(&mut lines, "Headers", &self.headers);
+        for (n, message) in self.messages.iter().enumerate() {
+            lines.push(Line::from(vec![
+                format!("Message {}", n + 1).cyan().bold(),
+                format!("  {}", format_time(message.time)).dark_gray(),
+            ]));
+            lines.extend(message.json.lines().map(|line| Line::raw(line.to_string())));
+            lines.push(Line::default());
+        }
+        if let State::Finished(status, _) = &self.state {
+            section(&mut lines, "Trailers", &status.trailers);
+        }
+        lines
+    }
+
+    fn scroll(&mut self, delta: isize) {
+        let max = self.lines().len().saturating_sub(self.height);
+        self.offset = self.offset.saturating_add_signed(delta).min(max);
+        self.follow = self.offset == max;
+    }
+
+    fn block(&self, title: String) -> Block<'static> {
+        let border_style = if self.focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered()
+            .border_style(border_style)
+            .title(title)
+            .padding(Padding::horizontal(1))
+    }
+
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        let line = match &self.state {
+            State::Idle => Line::raw("No call made").dark_gray(),
+            State::Calling => {
+                Line::from(vec!["Calling…".yellow(), "  <Esc> to cancel".dark_gray()])
+            }
+            State::Open => Line::from(vec![
+                "Receiving".green().bold(),
+                "  <Esc> to cancel".dark_gray(),
+            ]),
+            State::Finished(status, elapsed) => {
+                let text = format!("{} {}", status.code, code_name(status.code));
+                let mut line = Line::from(if status.code == 0 {
+                    text.green().bold()
+                } else {
+                    text.red().bold()
+                });
+                if !status.message.is_empty() {
+                    line.push_span(format!("  {}", status.message));
+                }
+                line.push_span(format!("  {} ms", elapsed.as_millis()).dark_gray());
+                line
+            }
+            State::Stopped => Line::raw("Cancelled").yellow(),
+            State::Failed(err) => Line::raw(err.clone()).red(),
+        };
+        line.render(area, buf);
+    }
+
+    fn render_response(&mut self, area: Rect, buf: &mut Buffer) {
+        let lines = self.lines();
+        let title = match self.messages.len() {
+            1 => " 1 message ".to_string(),
+            n => format!(" {n} messages "),
+        };
+        let block = self.block(title);
+        let height = block.inner(area).height as usize;
+        let offset = if self.follow {
+            lines.len().saturating_sub(height)
+        } else {
+            self.offset
+        };
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((offset as u16, 0))
+            .render(area, buf);
+        self.height = height;
+        self.offset = offset;
+    }
+}
+
+impl Widget for &mut GrpcPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let vertical = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+        let [status_area, response_area] = vertical.areas(area);
+
+        self.render_status(status_area, buf);
+        self.render_response(response_area, buf);
+    }
+}
+
+impl Component for GrpcPane {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        let page = self.height.max(1) as isize;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.scroll(isize::MAX),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Render {
+            self.poll();
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        frame.render_widget(self, area);
+
+        Ok(())
+    }
+}
@@ -1,25 +1,58 @@
+use std::collections::HashMap;
+
 use color_eyre::Result;
-use crossterm::event::{KeyEvent, MouseEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::prelude::*;
 use ratatui::style::Styled;
 use ratatui::widgets::{Block, Borders};
-use std::collections::HashMap;
-use tokio::sync::mpsc::UnboundedSender;
-use tui_textarea::TextArea;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio_util::sync::CancellationToken;
+use tui_textarea::{CursorMove, TextArea};
 
+use self::tab::Tab;
 use super::Component;
 use crate::app::Mode;
-use crate::components::request::Request;
+use crate::components::confirm::Confirm;
+use crate::components::cookies::CookieManager;
+use crate::components::response::Shared;
+use crate::executor::grpc::{is_grpc, GrpcCall};
+use crate::executor::sse::{is_event_stream, EventStream};
+use crate::executor::websocket::{is_websocket, WebSocket};
+use crate::executor::{cookies::CookieJar, Client};
+use crate::session::{Autosave, Session};
 use crate::{action::Action, config::Config, PKG_NAME};
 
+pub mod tab;
+
+/// What the user is asked to confirm before it happens.
+enum Pending {
+    /// Closing the tab with this id.
+    CloseTab(usize),
+    Quit,
+}
+
 #[derive(Default)]
 pub struct Home {
     command_tx: Option<UnboundedSender<Action>>,
     config: Config,
     mode: Mode,
+    /// The application-wide mode, which differs from `mode` while a popup or input has focus.
+    app_mode: Mode,
     clickable: HashMap<Mode, Rect>,
-    url_input: TextArea<'static>,
-    request: Request,
+    tabs: Vec<Tab>,
+    current: usize,
+    /// Id for the next tab to be opened.
+    next_id: usize,
+    /// Where each tab's title was drawn in the tab bar, for clicks.
+    tab_areas: Vec<Rect>,
+    cookies: CookieManager,
+    confirm: Confirm<Pending>,
+    /// Set once quitting with unsent changes has been confirmed.
+    quit_confirmed: bool,
+    client: Client,
+    autosave: Autosave,
+    /// Saved filters and run history, which every tab's response pane works on.
+    shared: Shared,
 }
 
 impl Home {
@@ -27,6 +60,78 @@ impl Home {
         Self::default()
     }
 
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.current]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current]
+    }
+
+    fn open_tab(&mut self) -> Result<()> {
+        let tab = Tab::new(
+            self.next_id,
+            self.command_tx.clone(),
+            &self.config,
+            &self.shared,
+        )?;
+        self.next_id += 1;
+        self.tabs.push(tab);
+        self.select_tab(self.tabs.len() - 1);
+        Ok(())
+    }
+
+    /// Reopens the tabs of the last session, returning the mode it was left in.
+    fn restore_session(&mut self, session: Session) -> Result<Mode> {
+        for state in session.tabs {
+            let tab = Tab::restore(
+                self.next_id,
+                self.command_tx.clone(),
+                &self.config,
+                &self.shared,
+                state,
+            )?;
+            self.next_id += 1;
+            self.tabs.push(tab);
+        }
+        self.current = session.current.min(self.tabs.len().saturating_sub(1));
+        Ok(session.mode)
+    }
+
+    fn session(&self) -> Session {
+        Session {
+            tabs: self.tabs.iter().map(Tab::snapshot).collect(),
+            current: self.current,
+            mode: self.mode,
+        }
+    }
+
+    fn select_tab(&mut self, n: usize) {
+        if n < self.tabs.len() && n != self.current {
+            self.tab_mut().response.blur();
+            self.current = n;
+        }
+    }
+
+    /// Closes a tab, cancelling its request, and opens an empty one if it was the last.
+    fn close_tab(&mut self, id: usize) -> Result<()> {
+        let Some(n) = self.tabs.iter().position(|tab| tab.id == id) else {
+            return Ok(());
+        };
+        self.tabs.remove(n).cancellation_token.cancel();
+        if n < self.current || self.current == self.tabs.len() {
+            self.current = self.current.saturating_sub(1);
+        }
+        if self.tabs.is_empty() {
+            self.open_tab()?;
+        }
+        Ok(())
+    }
+
+    fn dirty_tabs(&self) -> usize {
+        self.tabs.iter().filter(|tab| tab.is_dirty()).count()
+    }
+
     fn render_title(&self, frame: &mut Frame, area: Rect) {
         let app_name = Span::styled(
             format!(" {} ", PKG_NAME.to_uppercase()),
@@ -34,31 +139,71 @@ impl Home {
                 .fg(Color::LightGreen)
                 .add_modifier(Modifier::BOLD),
         );
-        let title = Block::bordered()
+        let mut title = Block::bordered()
             .borders(Borders::TOP)
             .title(app_name)
             .title_alignment(Alignment::Center);
 
+        // Skipping verification is easy to forget about, so it stays on screen while configured.
+        let mut insecure: Vec<&str> = self
+            .config
+            .tls
+            .iter()
+            .filter(|(_, settings)| settings.insecure)
+            .map(|(pattern, _)| pattern.as_str())
+            .collect();
+        if !insecure.is_empty() {
+            insecure.sort_unstable();
+            let warning = format!(" ⚠ TLS verification disabled for {} ", insecure.join(", "));
+            title = title.title_bottom(
+                Line::styled(
+                    warning,
+                    Style::default().fg(Color::White).bg(Color::Red).bold(),
+                )
+                .right_aligned(),
+            );
+        }
+
         frame.render_widget(title, area);
     }
 
+    fn render_tab_bar(&mut self, frame: &mut Frame, area: Rect) {
+        self.tab_areas.clear();
+        let mut x = area.x;
+        for (n, tab) in self.tabs.iter().enumerate() {
+            let marker = if tab.is_dirty() { " ●" } else { "" };
+            let title = format!(" {}{marker} ", tab.title());
+            let width = (title.chars().count() as u16).min(area.right().saturating_sub(x));
+            let tab_area = Rect::new(x, area.y, width, 1);
+            let style = if n == self.current {
+                Style::default().fg(Color::Black).bg(Color::White).bold()
+            } else {
+                Style::default().fg(Color::Gray).bg(Color::DarkGray)
+            };
+            frame.render_widget(Line::styled(title, style), tab_area);
+            self.tab_areas.push(tab_area);
+            x = (x + width + 1).min(area.right());
+        }
+    }
+
     fn render_url_input(&mut self, frame: &mut Frame, area: Rect) {
         self.clickable.insert(Mode::Url, area);
 
-        self.url_input.set_placeholder_text("Enter a URL...");
-        self.url_input
-            .set_cursor_line_style(Style::default().fg(Color::White));
+        let focused = self.mode == Mode::Url;
+        let url_input = &mut self.tabs[self.current].url_input;
+        url_input.set_placeholder_text("Enter a URL...");
+        url_input.set_cursor_line_style(Style::default().fg(Color::White));
 
         let info = "Press <Enter> to send request";
-        if self.mode == Mode::Url {
-            self.url_input.set_block(
+        if focused {
+            url_input.set_block(
                 Block::default()
                     .borders(Borders::ALL)
                     .set_style(Color::White)
                     .title(info),
             );
         } else {
-            self.url_input.set_block(
+            url_input.set_block(
                 Block::default()
                     .borders(Borders::ALL)
                     .set_style(Color::DarkGray)
@@ -66,13 +211,118 @@ impl Home {
             );
         }
 
-        frame.render_widget(&self.url_input, area);
+        frame.render_widget(&*url_input, area);
     }
 
     fn render_tabs(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         self.clickable.insert(Mode::Request, area);
 
-        self.request.draw(frame, area)
+        let tab = self.tab_mut();
+        let url = tab.url_input.lines().join("");
+        tab.request.set_endpoint(&url);
+        tab.request.draw(frame, area)
+    }
+
+    fn render_response(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        self.clickable.insert(Mode::Response, area);
+
+        let focused = self.mode == Mode::Response;
+        let tab = self.tab_mut();
+        if tab.shows_websocket() {
+            tab.socket.set_focused(focused);
+            return tab.socket.draw(frame, area);
+        }
+        if tab.events.is_active() {
+            tab.events.set_focused(focused);
+            return tab.events.draw(frame, area);
+        }
+        if tab.grpc.is_active() {
+            tab.grpc.set_focused(focused);
+            return tab.grpc.draw(frame, area);
+        }
+        tab.response.set_focused(focused);
+        tab.response.draw(frame, area)
+    }
+
+    fn send_request(&mut self) {
+        let Some(tx) = self.command_tx.clone() else {
+            return;
+        };
+        let client = self.client.clone();
+        let variables = self.shared.variables.clone();
+        let tab = self.tab_mut();
+        let composed = tab.composed();
+        let request = variables.borrow().apply(composed.clone());
+        if request.url.trim().is_empty() {
+            return;
+        }
+        if let Some(err) = tab.request.error(&request.url) {
+            tab.grpc.reset();
+            let _ = tx.send(Action::RequestFailed(tab.id, err));
+            return;
+        }
+
+        tab.mark_sent(composed);
+        if is_websocket(&request.url) {
+            tab.events.reset();
+            tab.grpc.reset();
+            tab.socket.connect(WebSocket::open(client, request));
+            return;
+        }
+        tab.socket.reset();
+        if is_grpc(&request.url) {
+            tab.events.reset();
+            if let Ok((method, message)) = tab.request.grpc_call(&request.url) {
+                tab.grpc
+                    .connect(GrpcCall::open(client, request, method, message));
+            }
+            return;
+        }
+        tab.grpc.reset();
+        if is_event_stream(&request) {
+            tab.events.connect(EventStream::open(client, request));
+            return;
+        }
+        tab.events.reset();
+        tab.response.set_request(&request);
+        tab.response.set_pending();
+        tab.cancellation_token.cancel(); // Cancel any request still in flight
+        tab.cancellation_token = CancellationToken::new();
+        let cancellation_token = tab.cancellation_token.clone();
+        let id = tab.id;
+        tokio::spawn(async move {
+            let (progress_tx, mut progress) = mpsc::unbounded_channel();
+            let sending = client.send_reporting(request, progress_tx);
+            tokio::pin!(sending);
+            let action = loop {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => return,
+                    Some(progress) = progress.recv() => {
+                        let _ = tx.send(Action::ResponseProgress(id, progress));
+                    }
+                    result = &mut sending => break match result {
+                        Ok(response) => Action::ResponseReceived(id, Box::new(response)),
+                        Err(err) => Action::RequestFailed(id, err.to_string()),
+                    },
+                }
+            };
+            let _ = tx.send(action);
+        });
+    }
+
+    fn handle_confirm_key(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some((mode, confirmed)) = self.confirm.handle_key(key) else {
+            return Ok(None);
+        };
+        match confirmed {
+            Some(Pending::CloseTab(id)) => self.close_tab(id)?,
+            Some(Pending::Quit) => {
+                self.quit_confirmed = true;
+                return Ok(Some(Action::Quit));
+            }
+            None => {}
+        }
+        Ok(Some(Action::SwitchMode(mode)))
     }
 }
 
@@ -83,73 +333,230 @@ impl Component for Home {
     }
 
     fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        let cookies = config.config.workspace_dir().join("cookies.json");
+        self.client = Client::new(CookieJar::load(cookies))
+            .tls(config.tls.clone())
+            .proxy(config.proxy.for_workspace(&config.config.workspace))
+            .dns(config.dns.for_workspace(&config.config.workspace))
+            .downloads(config.downloads.clone());
+        self.autosave = Autosave::new(config.config.workspace_dir());
+        self.shared = Shared::load(
+            config.config.data_dir.clone(),
+            config.config.workspace_dir(),
+        );
         self.config = config;
+        if let Some(session) = self.autosave.load() {
+            self.mode = self.restore_session(session)?;
+            if let Some(tx) = &self.command_tx {
+                tx.send(Action::SwitchMode(self.mode))?;
+            }
+        }
+        if self.tabs.is_empty() {
+            self.open_tab()?;
+        }
         Ok(())
     }
 
+    fn confirm_quit(&mut self) -> Result<bool> {
+        let dirty = self.dirty_tabs();
+        if dirty == 0 || self.quit_confirmed {
+            // Saved here rather than on every quit, which may yet be cancelled.
+            self.autosave.save(self.session());
+            return Ok(true);
+        }
+        if !self.confirm.is_open() {
+            let question = match dirty {
+                1 => "A tab has changes that were never sent. Quit anyway?".into(),
+                n => format!("{n} tabs have changes that were never sent. Quit anyway?"),
+            };
+            self.confirm.ask(question, Pending::Quit, self.app_mode);
+            if let Some(tx) = &self.command_tx {
+                tx.send(Action::SwitchMode(Mode::Confirm))?;
+            }
+        }
+        Ok(false)
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if self.confirm.is_open() {
+            return self.handle_confirm_key(key);
+        }
+        if self.cookies.is_open() {
+            return self.cookies.handle_key_event(key);
+        }
         match self.mode {
             Mode::Url => {
                 // FIXME: Discard global key events when the URL input is focused
-                // FIXME: Disallow multiple lines in the URL input
-                self.url_input.input(key);
+                if key.code == KeyCode::Enter {
+                    self.send_request();
+                } else {
+                    self.tab_mut().url_input.input(key);
+                }
                 Ok(None)
             }
-            Mode::Request => self.request.handle_key_event(key),
+            Mode::Request => self.tab_mut().request.handle_key_event(key),
+            Mode::Response if self.tab().shows_websocket() => {
+                self.tab_mut().socket.handle_key_event(key)
+            }
+            Mode::Response if self.tab().events.is_active() => {
+                self.tab_mut().events.handle_key_event(key)
+            }
+            Mode::Response if self.tab().grpc.is_active() => {
+                self.tab_mut().grpc.handle_key_event(key)
+            }
+            Mode::Response => self.tab_mut().response.handle_key_event(key),
             _ => Ok(None),
         }
     }
 
     fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
-        match mouse {
-            MouseEvent {
-                kind: MouseEventKind::Down(_),
-                column,
-                row,
-                ..
-            } => {
-                for (mode, rect) in self.clickable.iter() {
-                    if rect.contains(Position { x: column, y: row }) {
-                        self.mode = *mode;
-                        break;
-                    }
+        if self.cookies.is_open() || self.confirm.is_open() {
+            return Ok(None);
+        }
+        let position = Position {
+            x: mouse.column,
+            y: mouse.row,
+        };
+        if let Some(n) = self
+            .tab_areas
+            .iter()
+            .position(|area| area.contains(position))
+        {
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => self.select_tab(n),
+                MouseEventKind::Down(MouseButton::Middle) => {
+                    self.select_tab(n);
+                    return Ok(Some(Action::CloseTab));
                 }
+                _ => {}
             }
-            _ => {}
+            return Ok(None);
         }
+        let target = self
+            .clickable
+            .iter()
+            .find(|(_, rect)| rect.contains(position))
+            .map(|(mode, _)| *mode);
 
-        Ok(None)
+        if let (MouseEventKind::Down(_), Some(mode)) = (mouse.kind, target) {
+            self.mode = mode;
+            self.tab_mut().response.blur();
+            if let Some(tx) = &self.command_tx {
+                tx.send(Action::SwitchMode(mode))?;
+            }
+        }
+        match target {
+            Some(Mode::Response) if !self.tab().shows_live_pane() => {
+                self.tab_mut().response.handle_mouse_event(mouse)
+            }
+            _ => Ok(None),
+        }
     }
 
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
-            Action::Tick => {
-                // add any logic here that should run on every tick
-            }
+            Action::Tick if self.autosave.is_due() => self.autosave.update(self.session()),
             Action::Render => {
-                // add any logic here that should run on every render
+                for tab in &mut self.tabs {
+                    tab.request.update(Action::Render)?;
+                    tab.socket.update(Action::Render)?;
+                    tab.events.update(Action::Render)?;
+                    tab.grpc.update(Action::Render)?;
+                }
+            }
+            Action::SwitchMode(mode) => self.app_mode = mode,
+            Action::OpenCookies if !self.cookies.is_open() => {
+                self.cookies.open(self.client.cookies(), self.app_mode);
+                return Ok(Some(Action::SwitchMode(Mode::Cookies)));
+            }
+            Action::CancelRequest if self.tab().socket.is_connecting() => {
+                self.tab_mut().socket.disconnect("Connection cancelled");
+                return Ok(None);
+            }
+            Action::CancelRequest if self.tab().events.is_running() => {
+                self.tab_mut().events.stop();
+                return Ok(None);
+            }
+            Action::CancelRequest if self.tab().grpc.is_running() => {
+                self.tab_mut().grpc.stop();
+                return Ok(None);
+            }
+            Action::CancelRequest if self.tab().response.is_pending() => {
+                let tab = self.tab();
+                tab.cancellation_token.cancel();
+                return Ok(Some(Action::RequestFailed(
+                    tab.id,
+                    "Request cancelled".into(),
+                )));
+            }
+            Action::FetchSchema => {
+                let client = self.client.clone();
+                let variables = self.shared.variables.clone();
+                let tab = self.tab_mut();
+                let request = variables.borrow().apply(tab.composed());
+                tab.request.fetch_schema(client, request);
+                return Ok(None);
+            }
+            Action::SetUrl(url) => {
+                let tab = self.tab_mut();
+                tab.url_input = TextArea::from([url]);
+                tab.url_input.move_cursor(CursorMove::End);
+                return Ok(None);
+            }
+            Action::NewTab => {
+                self.open_tab()?;
+                self.mode = Mode::Url;
+                return Ok(Some(Action::SwitchMode(Mode::Url)));
+            }
+            Action::CloseTab if self.tab().is_dirty() => {
+                let id = self.tab().id;
+                let question = "This tab has changes that were never sent. Close it anyway?";
+                self.confirm
+                    .ask(question, Pending::CloseTab(id), self.app_mode);
+                return Ok(Some(Action::SwitchMode(Mode::Confirm)));
+            }
+            Action::CloseTab => self.close_tab(self.tab().id)?,
+            Action::NextTab => self.select_tab((self.current + 1) % self.tabs.len()),
+            Action::PreviousTab => {
+                self.select_tab((self.current + self.tabs.len() - 1) % self.tabs.len())
+            }
+            Action::ResponseReceived(id, _)
+            | Action::ResponseProgress(id, _)
+            | Action::RequestFailed(id, _) => {
+                return match self.tabs.iter_mut().find(|tab| tab.id == id) {
+                    Some(tab) => tab.response.update(action),
+                    None => Ok(None),
+                };
             }
+            // The response's bindings, e.g. search, don't apply to a live pane.
+            _ if self.tab().shows_live_pane() => return Ok(None),
             _ => {}
         }
-        Ok(None)
+        self.tab_mut().response.update(action)
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
         let main_area = Layout::vertical([
             Constraint::Length(2),
+            Constraint::Length(1),
             Constraint::Length(3),
             Constraint::Percentage(100),
         ])
-            .split(area);
+        .split(area);
         let title_area = main_area[0];
-        let url_area = main_area[1];
-        let [req_area, _resp_area] =
+        let tab_bar_area = main_area[1];
+        let url_area = main_area[2];
+        let [req_area, resp_area] =
             Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .areas(main_area[2]);
+                .areas(main_area[3]);
 
         self.render_title(frame, title_area);
+        self.render_tab_bar(frame, tab_bar_area);
         self.render_url_input(frame, url_area);
         self.render_tabs(frame, req_area)?;
+        self.render_response(frame, resp_area)?;
+        self.cookies.draw(frame, area)?;
+        self.confirm.draw(frame, area);
 
         Ok(())
     }
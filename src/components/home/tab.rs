@@ -0,0 +1,162 @@
+use color_eyre::Result;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::action::Action;
+use crate::components::events::EventStreamPane;
+use crate::components::grpc::GrpcPane;
+use crate::components::request::Request;
+use crate::components::response::{Response, Shared};
+use crate::components::websocket::WebSocketPane;
+use crate::components::Component;
+use crate::config::Config;
+use crate::executor::websocket::is_websocket;
+use crate::executor::HttpRequest;
+use crate::session::TabState;
+
+/// Longest title shown in the tab bar before it is cut short.
+const MAX_TITLE_WIDTH: usize = 28;
+
+/// A request open in the tab bar, along with the response it last got.
+pub struct Tab {
+    /// Tells the responses of requests sent from different tabs apart.
+    pub id: usize,
+    pub url_input: TextArea<'static>,
+    pub request: Request,
+    pub response: Response,
+    /// Shown instead of the response for `ws://` and `wss://` URLs.
+    pub socket: WebSocketPane,
+    /// Shown instead of the response once a request for a server-sent event stream is sent.
+    pub events: EventStreamPane,
+    /// Shown instead of the response once a gRPC call is made.
+    pub grpc: GrpcPane,
+    /// Cancels the request in flight, if any.
+    pub cancellation_token: CancellationToken,
+    /// The request as last sent, to tell whether it has been edited since.
+    sent: HttpRequest,
+}
+
+impl Tab {
+    pub fn new(
+        id: usize,
+        tx: Option<UnboundedSender<Action>>,
+        config: &Config,
+        shared: &Shared,
+    ) -> Result<Self> {
+        let mut request = Request::new();
+        let mut response = Response::new(shared.clone());
+        if let Some(tx) = tx {
+            request.register_action_handler(tx.clone())?;
+            response.register_action_handler(tx)?;
+        }
+        request.register_config_handler(config.clone())?;
+        response.register_config_handler(config.clone())?;
+        let mut tab = Self {
+            id,
+            url_input: TextArea::default(),
+            request,
+            response,
+            socket: WebSocketPane::default(),
+            events: EventStreamPane::default(),
+            grpc: GrpcPane::default(),
+            cancellation_token: CancellationToken::new(),
+            sent: HttpRequest::default(),
+        };
+        tab.sent = tab.composed();
+        Ok(tab)
+    }
+
+    /// Reopens a tab saved with the session.
+    pub fn restore(
+        id: usize,
+        tx: Option<UnboundedSender<Action>>,
+        config: &Config,
+        shared: &Shared,
+        state: TabState,
+    ) -> Result<Self> {
+        let mut tab = Self::new(id, tx, config, shared)?;
+        tab.url_input = TextArea::from([state.request.url.clone()]);
+        tab.url_input.move_cursor(CursorMove::End);
+        tab.request.restore(&state.request);
+        tab.response.set_request(&state.sent);
+        tab.sent = state.sent;
+        if let Some(response) = state.response {
+            tab.response.restore(response);
+        }
+        Ok(tab)
+    }
+
+    pub fn snapshot(&self) -> TabState {
+        TabState {
+            request: self.composed(),
+            sent: self.sent.clone(),
+            response: self.response.snapshot(),
+        }
+    }
+
+    /// The request as composed in the tab.
+    pub fn composed(&self) -> HttpRequest {
+        self.request.compose(&self.url_input.lines().join(""))
+    }
+
+    /// Whether the response pane shows a WebSocket connection rather than a response.
+    pub fn shows_websocket(&self) -> bool {
+        self.socket.is_active() || is_websocket(&self.url_input.lines().join(""))
+    }
+
+    /// Whether the response pane shows a WebSocket, an event stream or a gRPC call rather than a
+    /// response.
+    pub fn shows_live_pane(&self) -> bool {
+        self.shows_websocket() || self.events.is_active() || self.grpc.is_active()
+    }
+
+    pub fn mark_sent(&mut self, request: HttpRequest) {
+        self.sent = request;
+    }
+
+    /// Whether the request has been edited since it was last sent, or since the tab was opened.
+    pub fn is_dirty(&self) -> bool {
+        self.composed() != self.sent
+    }
+
+    /// The method and URL without its scheme, cut short to fit the tab bar.
+    pub fn title(&self) -> String {
+        let request = self.composed();
+        let url = request.url.trim();
+        if url.is_empty() {
+            return "New request".into();
+        }
+        let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let title = format!("{} {url}", request.method);
+        if title.chars().count() <= MAX_TITLE_WIDTH {
+            return title;
+        }
+        let mut title: String = title.chars().take(MAX_TITLE_WIDTH - 1).collect();
+        title.push('…');
+        title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_title_and_dirty() -> Result<()> {
+        let mut tab = Tab::new(0, None, &Config::default(), &Shared::default())?;
+        assert_eq!(tab.title(), "New request");
+        assert!(!tab.is_dirty());
+
+        tab.url_input
+            .insert_str("https://api.example.com/v1/items?page=2");
+        assert_eq!(tab.title(), "GET api.example.com/v1/item…");
+        assert!(tab.is_dirty());
+
+        tab.mark_sent(tab.composed());
+        assert!(!tab.is_dirty());
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent};
+use prost_reflect::MethodDescriptor;
 use ratatui::layout::Rect;
 use ratatui::prelude::*;
 use ratatui::style::{palette::tailwind, Stylize};
@@ -8,9 +9,26 @@ use ratatui::Frame;
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 use tokio::sync::mpsc::UnboundedSender;
 
+use self::graphql::GraphqlEditor;
+use self::grpc::GrpcEditor;
+use self::headers::HeadersTab;
+use self::jsonrpc::JsonRpcEditor;
+use self::options::OptionsTab;
 use crate::action::Action;
+use crate::app::Mode;
 use crate::components::Component;
 use crate::config::Config;
+use crate::executor::grpc::{is_grpc, DescriptorCache};
+use crate::executor::{Client, HttpRequest, RequestOptions};
+use crate::graphql::parse_request_body;
+use crate::graphql::schema::SchemaCache;
+
+pub mod completion;
+pub mod graphql;
+pub mod grpc;
+pub mod headers;
+pub mod jsonrpc;
+pub mod options;
 
 #[derive(Default, Display, FromRepr, EnumIter, Clone, Copy)]
 pub enum SelectedTab {
@@ -23,6 +41,20 @@ pub enum SelectedTab {
     Headers,
     #[strum(to_string = "Auth")]
     Auth,
+    #[strum(to_string = "Options")]
+    Options,
+}
+
+/// What the body of the request is made of.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+pub enum BodyMode {
+    #[default]
+    #[strum(to_string = "None")]
+    None,
+    #[strum(to_string = "GraphQL")]
+    Graphql,
+    #[strum(to_string = "JSON-RPC")]
+    JsonRpc,
 }
 
 #[derive(Default)]
@@ -30,6 +62,15 @@ pub struct Request {
     command_tx: Option<UnboundedSender<Action>>,
     config: Config,
     selected_tab: SelectedTab,
+    body_mode: BodyMode,
+    graphql: GraphqlEditor,
+    jsonrpc: JsonRpcEditor,
+    /// Takes the place of the body for `grpc://` and `grpcs://` URLs.
+    grpc: GrpcEditor,
+    /// The URL the request goes to, as last drawn.
+    endpoint: String,
+    headers: HeadersTab,
+    options: OptionsTab,
 }
 
 impl SelectedTab {
@@ -53,6 +94,7 @@ impl SelectedTab {
             Self::Body => tailwind::GREEN,
             Self::Headers => tailwind::YELLOW,
             Self::Auth => tailwind::RED,
+            Self::Options => tailwind::INDIGO,
         }
     }
 
@@ -74,11 +116,7 @@ impl SelectedTab {
     }
 
     fn render_tab_body(self, area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Body").block(self.block()).render(area, buf);
-    }
-
-    fn render_tab_headers(self, area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Headers")
+        Paragraph::new("No body".dark_gray())
             .block(self.block())
             .render(area, buf);
     }
@@ -86,6 +124,17 @@ impl SelectedTab {
     fn render_tab_auth(self, area: Rect, buf: &mut Buffer) {
         Paragraph::new("Auth").block(self.block()).render(area, buf);
     }
+
+    fn render_tab_options(self, area: Rect, buf: &mut Buffer, options: &OptionsTab) {
+        let block = self.block();
+        // Keep the selected setting in view when they don't all fit.
+        let height = block.inner(area).height.max(1) as usize;
+        let offset = (options.selected() + 1).saturating_sub(height) as u16;
+        Paragraph::new(options.lines())
+            .block(block)
+            .scroll((offset, 0))
+            .render(area, buf);
+    }
 }
 
 impl Request {
@@ -93,8 +142,131 @@ impl Request {
         Self::default()
     }
 
+    pub fn options(&self) -> &RequestOptions {
+        &self.options.options
+    }
+
+    pub fn set_options(&mut self, options: RequestOptions) {
+        self.options.options = options;
+    }
+
+    pub fn headers(&self) -> Vec<(String, String)> {
+        self.headers.headers()
+    }
+
+    pub fn set_headers(&mut self, headers: &[(String, String)]) {
+        self.headers.set_headers(headers);
+    }
+
+    /// The request to send to the URL, with a body made from the editors of the Body tab.
+    pub fn compose(&self, url: &str) -> HttpRequest {
+        let mut request = HttpRequest {
+            headers: self.headers(),
+            options: self.options().clone(),
+            ..HttpRequest::get(url)
+        };
+        if is_grpc(url) {
+            self.grpc.apply(&mut request);
+        } else {
+            match self.body_mode {
+                BodyMode::Graphql => self.graphql.apply(&mut request),
+                BodyMode::JsonRpc => self.jsonrpc.apply(&mut request),
+                BodyMode::None => {}
+            }
+        }
+        request
+    }
+
+    /// Fills the tab back in from a request composed earlier.
+    pub fn restore(&mut self, request: &HttpRequest) {
+        let mut headers = request.headers.clone();
+        let post = request.method == "POST";
+        if is_grpc(&request.url) {
+            self.grpc.restore(&request.body);
+        } else if let Some((query, variables, operation)) =
+            parse_request_body(&request.body).filter(|_| post)
+        {
+            self.body_mode = BodyMode::Graphql;
+            self.graphql.restore(query, variables, operation);
+        } else if let Some(calls) =
+            crate::jsonrpc::parse_request_body(&request.body).filter(|_| post)
+        {
+            self.body_mode = BodyMode::JsonRpc;
+            self.jsonrpc.restore(calls);
+        } else {
+            self.body_mode = BodyMode::None;
+        }
+        if self.body_mode != BodyMode::None {
+            // Added back when the request is composed.
+            if let Some(n) = headers
+                .iter()
+                .position(|(name, value)| name == "Content-Type" && value == "application/json")
+            {
+                headers.remove(n);
+            }
+        }
+        self.set_headers(&headers);
+        self.set_options(request.options.clone());
+    }
+
+    /// Why the request can't be sent to the URL as it is.
+    pub fn error(&self, url: &str) -> Option<String> {
+        if is_grpc(url) {
+            return self.grpc.call(url).err();
+        }
+        match self.body_mode {
+            BodyMode::Graphql => self.graphql.error(),
+            BodyMode::JsonRpc => self.jsonrpc.error(),
+            BodyMode::None => None,
+        }
+    }
+
+    /// The method a gRPC URL calls and the message to send it.
+    pub fn grpc_call(&self, url: &str) -> Result<(MethodDescriptor, Vec<u8>), String> {
+        self.grpc.call(url)
+    }
+
+    pub fn set_endpoint(&mut self, url: &str) {
+        self.endpoint = url.to_string();
+        if is_grpc(url) {
+            self.grpc.set_endpoint(url);
+        } else {
+            self.graphql.set_endpoint(url);
+        }
+    }
+
+    /// Fetches the GraphQL schema, or the gRPC service definitions, of the endpoint.
+    pub fn fetch_schema(&mut self, client: Client, request: HttpRequest) {
+        if is_grpc(&request.url) {
+            self.grpc.fetch_descriptors(client, request);
+        } else {
+            self.graphql.fetch_schema(client, request);
+        }
+    }
+
+    fn cycle_body_mode(&mut self) {
+        self.body_mode = match self.body_mode {
+            BodyMode::None => BodyMode::Graphql,
+            BodyMode::Graphql => BodyMode::JsonRpc,
+            BodyMode::JsonRpc => BodyMode::None,
+        };
+    }
+
     fn render_footer(&self, area: Rect, buf: &mut Buffer) {
-        Line::raw("◄ ► to change tab").centered().render(area, buf);
+        let hint = match self.selected_tab {
+            SelectedTab::Body if is_grpc(&self.endpoint) => {
+                format!("◄ ► to change tab  {}", self.grpc.hint())
+            }
+            SelectedTab::Body => match self.body_mode {
+                BodyMode::Graphql => format!("◄ ► to change tab  {}", self.graphql.hint()),
+                BodyMode::JsonRpc => format!("◄ ► to change tab  {}", self.jsonrpc.hint()),
+                BodyMode::None => "◄ ► to change tab  <m> body mode".into(),
+            },
+            SelectedTab::Headers => format!("◄ ► to change tab  {}", self.headers.hint()),
+            SelectedTab::Options => format!("◄ ► to change tab  {}", self.options.hint()),
+            _ => "◄ ► to change tab".into(),
+        };
+        Line::raw(hint).centered().render(area, buf);
     }
 
     fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
@@ -129,7 +301,35 @@ impl Widget for &mut Request {
         let [header_area, inner_area, footer_area] = vertical.areas(area);
 
         self.render_tabs(header_area, buf);
-        self.selected_tab.render(inner_area, buf);
+        match self.selected_tab {
+            SelectedTab::Body if is_grpc(&self.endpoint) => {
+                let block = self.selected_tab.block().title(" gRPC ");
+                let inner = block.inner(inner_area);
+                block.render(inner_area, buf);
+                self.grpc.render(inner, buf);
+            }
+            SelectedTab::Body if self.body_mode == BodyMode::Graphql => {
+                let block = self.selected_tab.block().title(" GraphQL ");
+                let inner = block.inner(inner_area);
+                block.render(inner_area, buf);
+                self.graphql.render(inner, buf);
+            }
+            SelectedTab::Body if self.body_mode == BodyMode::JsonRpc => {
+                let block = self.selected_tab.block().title(" JSON-RPC ");
+                let inner = block.inner(inner_area);
+                block.render(inner_area, buf);
+                self.jsonrpc.render(inner, buf);
+            }
+            SelectedTab::Headers => {
+                let block = self.selected_tab.block();
+                self.headers.render(inner_area, buf, block)
+            }
+            SelectedTab::Options => {
+                self.selected_tab
+                    .render_tab_options(inner_area, buf, &self.options)
+            }
+            tab => tab.render(inner_area, buf),
+        }
         self.render_footer(footer_area, buf);
     }
 }
@@ -139,8 +339,9 @@ impl Widget for SelectedTab {
         match self {
             Self::Query => self.render_tab_query(area, buf),
             Self::Body => self.render_tab_body(area, buf),
-            Self::Headers => self.render_tab_headers(area, buf),
             Self::Auth => self.render_tab_auth(area, buf),
+            // Rendered by the request, which owns their state.
+            Self::Headers | Self::Options => {}
         }
     }
 }
@@ -152,11 +353,36 @@ impl Component for Request {
     }
 
     fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.graphql = GraphqlEditor::new(SchemaCache::new(config.config.data_dir.clone()));
+        self.grpc = GrpcEditor::new(DescriptorCache::new(config.config.data_dir.clone()));
         self.config = config;
         Ok(())
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if self.headers.is_editing() {
+            if !self.headers.handle_key(key) {
+                return Ok(Some(Action::SwitchMode(Mode::Request)));
+            }
+            return Ok(None);
+        }
+        if self.options.is_editing() {
+            self.options.handle_key(key);
+            return Ok(None);
+        }
+        let body = matches!(self.selected_tab, SelectedTab::Body);
+        let grpc = body && is_grpc(&self.endpoint);
+        let graphql = body && !grpc && self.body_mode == BodyMode::Graphql;
+        let jsonrpc = body && !grpc && self.body_mode == BodyMode::JsonRpc;
+        if grpc && self.grpc.is_capturing() {
+            return Ok(self.grpc.handle_key(key));
+        }
+        if graphql && self.graphql.is_capturing() {
+            return Ok(self.graphql.handle_key(key));
+        }
+        if jsonrpc && self.jsonrpc.is_capturing() {
+            return Ok(self.jsonrpc.handle_key(key));
+        }
         match key {
             KeyEvent {
                 kind: KeyEventKind::Press,
@@ -172,12 +398,60 @@ impl Component for Request {
             } => {
                 self.next_tab();
             }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                code: KeyCode::Enter,
+                ..
+            } if matches!(self.selected_tab, SelectedTab::Headers) => {
+                self.headers.start_editing();
+                // Typing shouldn't trigger the bindings of the request pane.
+                return Ok(Some(Action::SwitchMode(Mode::Prompt)));
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                code: KeyCode::Char('m'),
+                ..
+            } if body && !grpc => {
+                self.cycle_body_mode();
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if grpc => {
+                return Ok(self.grpc.handle_key(key));
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if graphql => {
+                return Ok(self.graphql.handle_key(key));
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if jsonrpc => {
+                return Ok(self.jsonrpc.handle_key(key));
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if matches!(self.selected_tab, SelectedTab::Options) => {
+                self.options.handle_key(key);
+            }
             _ => {}
         }
 
         Ok(None)
     }
 
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Render {
+            self.graphql.poll();
+            self.grpc.poll();
+        }
+        Ok(None)
+    }
+
     #[allow(unused_variables)]
     fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
         // TODO: allow clicking to change tabs
@@ -0,0 +1,102 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Clear, Paragraph};
+use tui_textarea::{CursorMove, TextArea};
+
+/// Most completions shown at once.
+const MAX_COMPLETIONS: usize = 8;
+
+/// Something that can be typed at the cursor, with a hint about it such as its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub detail: String,
+}
+
+/// Completions offered at the cursor of an editor.
+#[derive(Debug)]
+pub struct Popup {
+    items: Vec<Completion>,
+    selected: usize,
+}
+
+impl Popup {
+    /// A popup for the completions, unless there are none.
+    pub fn new(items: Vec<Completion>) -> Option<Self> {
+        (!items.is_empty()).then_some(Self { items, selected: 0 })
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1).min(self.items.len() - 1);
+    }
+
+    /// Replaces the word before the cursor with the selected completion.
+    pub fn accept(&self, textarea: &mut TextArea) {
+        let Some(item) = self.items.get(self.selected) else {
+            return;
+        };
+        let before = before_cursor(textarea);
+        let mut typed = before
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .count();
+        // Quoted completions, like JSON keys, take the opening quote along.
+        if item.label.starts_with('"') && before.chars().rev().nth(typed) == Some('"') {
+            typed += 1;
+        }
+        for _ in 0..typed {
+            textarea.move_cursor(CursorMove::Back);
+        }
+        textarea.delete_str(typed);
+        textarea.insert_str(&item.label);
+    }
+
+    /// Lists the completions at the bottom of the area.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let offset = (self.selected + 1).saturating_sub(MAX_COMPLETIONS);
+        let lines: Vec<Line> = self
+            .items
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(MAX_COMPLETIONS)
+            .map(|(n, item)| {
+                let line = Line::from(vec![
+                    item.label.clone().cyan(),
+                    item.detail.clone().dark_gray(),
+                ]);
+                if n == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.bottom() - height,
+            height,
+            ..area
+        };
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Completions "))
+            .render(area, buf);
+    }
+}
+
+/// The text of the editor up to its cursor.
+pub fn before_cursor(textarea: &TextArea) -> String {
+    let (row, col) = textarea.cursor();
+    let lines = textarea.lines();
+    let mut text = lines[..row].join("\n");
+    if row > 0 {
+        text.push('\n');
+    }
+    text.extend(lines[row].chars().take(col));
+    text
+}
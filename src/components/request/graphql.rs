@@ -0,0 +1,464 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Paragraph};
+use tokio::sync::oneshot;
+use tracing::error;
+use tui_textarea::TextArea;
+
+use self::docs::DocsExplorer;
+use crate::action::Action;
+use crate::app::Mode;
+use crate::components::request::completion::{before_cursor, Popup};
+use crate::executor::{Client, HttpRequest};
+use crate::graphql::schema::{Schema, SchemaCache, INTROSPECTION_QUERY};
+use crate::graphql::{self, Operation};
+
+pub mod docs;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Editor {
+    #[default]
+    Query,
+    Variables,
+}
+
+/// What the keys go to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    #[default]
+    Browsing,
+    Editing,
+    Docs,
+}
+
+/// A GraphQL body: a query document, its variables and the operation to run.
+#[derive(Default)]
+pub struct GraphqlEditor {
+    query: TextArea<'static>,
+    variables: TextArea<'static>,
+    editor: Editor,
+    focus: Focus,
+    /// The operation picked when the document defines several.
+    operation: Option<String>,
+    completions: Option<Popup>,
+    docs: DocsExplorer,
+    cache: SchemaCache,
+    /// The URL the schema was looked up for.
+    endpoint: Option<String>,
+    schema: Option<Schema>,
+    fetching: Option<oneshot::Receiver<Result<Schema, String>>>,
+    /// Why the last fetch of the schema failed.
+    fetch_error: Option<String>,
+}
+
+impl GraphqlEditor {
+    pub fn new(cache: SchemaCache) -> Self {
+        Self {
+            cache,
+            ..Self::default()
+        }
+    }
+
+    /// Whether all keys go to the editor, rather than some changing tabs.
+    pub fn is_capturing(&self) -> bool {
+        self.focus != Focus::Browsing
+    }
+
+    /// Looks up the schema cached for the URL the request goes to, when it changes.
+    pub fn set_endpoint(&mut self, url: &str) {
+        let url = url.trim();
+        if self.endpoint.as_deref() != Some(url) {
+            self.schema = self.cache.load(url);
+            self.endpoint = Some(url.to_string());
+            self.fetch_error = None;
+        }
+    }
+
+    fn query_text(&self) -> String {
+        self.query.lines().join("\n")
+    }
+
+    fn variables_text(&self) -> String {
+        self.variables.lines().join("\n")
+    }
+
+    fn operations(&self) -> Vec<Operation> {
+        graphql::operations(&self.query_text())
+    }
+
+    /// The operation to run, which only needs naming when there is a choice.
+    fn operation(&self) -> Option<String> {
+        let operations = self.operations();
+        if operations.len() < 2 {
+            return None;
+        }
+        self.operation
+            .clone()
+            .filter(|name| operations.contains(&Some(name.clone())))
+            .or_else(|| operations.into_iter().flatten().next())
+    }
+
+    fn cycle_operation(&mut self) {
+        let names: Vec<String> = self.operations().into_iter().flatten().collect();
+        let next = match self.operation() {
+            Some(current) => names
+                .iter()
+                .position(|name| *name == current)
+                .map_or(0, |n| (n + 1) % names.len()),
+            None => 0,
+        };
+        self.operation = names.get(next).cloned();
+    }
+
+    /// Turns the request into a GraphQL one, leaving out the variables if they don't parse.
+    pub fn apply(&self, request: &mut HttpRequest) {
+        let query = self.query_text();
+        let operation = self.operation();
+        request.method = "POST".into();
+        request.body = graphql::request_body(&query, &self.variables_text(), operation.as_deref())
+            .or_else(|_| graphql::request_body(&query, "", operation.as_deref()))
+            .unwrap_or_default();
+        let has_content_type = request
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        if !has_content_type {
+            request
+                .headers
+                .push(("Content-Type".into(), "application/json".into()));
+        }
+    }
+
+    /// Why the request can't be sent as it is.
+    pub fn error(&self) -> Option<String> {
+        let operations = self.operations();
+        if operations.len() > 1 && operations.contains(&None) {
+            return Some("Operations must all be named when there are several".into());
+        }
+        graphql::request_body("", &self.variables_text(), None).err()
+    }
+
+    /// Fills the editors from a request body sent earlier.
+    pub fn restore(&mut self, query: String, variables: String, operation: Operation) {
+        self.query = TextArea::from(query.lines());
+        self.variables = TextArea::from(variables.lines());
+        self.operation = operation;
+    }
+
+    /// Sends the introspection query to the endpoint in the background.
+    pub fn fetch_schema(&mut self, client: Client, mut request: HttpRequest) {
+        request.method = "POST".into();
+        request.body = graphql::request_body(INTROSPECTION_QUERY, "", None).unwrap_or_default();
+        request
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("content-type"));
+        request
+            .headers
+            .push(("Content-Type".into(), "application/json".into()));
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let schema = async {
+                let response = client.send(request).await?;
+                let body = serde_json::from_slice(&response.body)?;
+                Schema::from_introspection(&body)
+            };
+            let _ = tx.send(schema.await.map_err(|err| err.to_string()));
+        });
+        self.fetching = Some(rx);
+        self.fetch_error = None;
+    }
+
+    /// Picks up the schema once it has been fetched, keeping it for next time.
+    pub fn poll(&mut self) {
+        let Some(fetching) = self.fetching.as_mut() else {
+            return;
+        };
+        let Ok(result) = fetching.try_recv() else {
+            return;
+        };
+        self.fetching = None;
+        match result {
+            Ok(schema) => {
+                if let Some(endpoint) = &self.endpoint {
+                    if let Err(err) = self.cache.save(endpoint, &schema) {
+                        error!("Failed to save the schema: {err}");
+                    }
+                }
+                self.schema = Some(schema);
+            }
+            Err(err) => self.fetch_error = Some(err),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match self.focus {
+            Focus::Editing => self.handle_editing_key(key),
+            Focus::Docs => {
+                self.handle_docs_key(key);
+                None
+            }
+            Focus::Browsing => self.handle_browsing_key(key),
+        }
+    }
+
+    fn handle_browsing_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('i') => {
+                self.focus = Focus::Editing;
+                // Typing shouldn't trigger the bindings of the request pane.
+                return Some(Action::SwitchMode(Mode::Prompt));
+            }
+            KeyCode::Char('r') if self.fetching.is_none() => return Some(Action::FetchSchema),
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.editor = match self.editor {
+                    Editor::Query => Editor::Variables,
+                    Editor::Variables => Editor::Query,
+                };
+            }
+            KeyCode::Char('o') => self.cycle_operation(),
+            KeyCode::Char('d') if self.schema.is_some() => self.focus = Focus::Docs,
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_docs_key(&mut self, key: KeyEvent) {
+        let Some(schema) = &self.schema else {
+            self.focus = Focus::Browsing;
+            return;
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.docs.move_selection(schema, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.docs.move_selection(schema, 1),
+            KeyCode::PageUp => self.docs.move_selection(schema, -10),
+            KeyCode::PageDown => self.docs.move_selection(schema, 10),
+            KeyCode::Enter => self.docs.open(schema),
+            KeyCode::Backspace => {
+                self.docs.back();
+            }
+            KeyCode::Esc | KeyCode::Char('d') => self.focus = Focus::Browsing,
+            _ => {}
+        }
+    }
+
+    fn handle_editing_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if let Some(popup) = &mut self.completions {
+            match key.code {
+                KeyCode::Up => {
+                    popup.previous();
+                    return None;
+                }
+                KeyCode::Down => {
+                    popup.next();
+                    return None;
+                }
+                KeyCode::Enter | KeyCode::Tab => {
+                    popup.accept(&mut self.query);
+                    self.completions = None;
+                    return None;
+                }
+                KeyCode::Esc => {
+                    self.completions = None;
+                    return None;
+                }
+                _ => {}
+            }
+        }
+        let editor = match self.editor {
+            Editor::Query => &mut self.query,
+            Editor::Variables => &mut self.variables,
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.focus = Focus::Browsing;
+                return Some(Action::SwitchMode(Mode::Request));
+            }
+            KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.complete();
+                return None;
+            }
+            _ => {
+                editor.input(key);
+            }
+        }
+        if self.completions.is_some() {
+            self.complete();
+        }
+        None
+    }
+
+    /// Offers completions for the word at the cursor, if the schema is known.
+    fn complete(&mut self) {
+        let Some(schema) = &self.schema else {
+            return;
+        };
+        if self.editor != Editor::Query {
+            return;
+        }
+        self.completions = Popup::new(graphql::completions(&before_cursor(&self.query), schema));
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self.focus {
+            Focus::Editing if self.completions.is_some() => "<↑/↓> choose  <Enter> insert",
+            Focus::Editing => "<Ctrl-Space> complete  <Esc> done",
+            Focus::Docs => "<Enter> open  <Backspace> back  <Esc> close",
+            Focus::Browsing => "<Enter> edit  <Tab> variables  <o> operation  <r> schema  <d> docs",
+        }
+    }
+
+    fn block(&self, title: Line<'static>, focused: bool) -> Block<'static> {
+        let border_style = if focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered().border_style(border_style).title(title)
+    }
+
+    fn query_title(&self) -> Line<'static> {
+        let mut title = Line::raw(" Query ");
+        match graphql::validate(&self.query_text(), self.schema.as_ref()) {
+            _ if self.query.is_empty() => {}
+            Ok(()) if self.schema.is_some() => title.push_span("✓ ".green()),
+            Ok(()) => {}
+            Err(err) => title.push_span(format!("{err} ").red()),
+        }
+        title
+    }
+
+    fn variables_title(&self) -> Line<'static> {
+        match graphql::request_body("", &self.variables_text(), None) {
+            Ok(_) => Line::raw(" Variables "),
+            Err(err) => Line::from(vec![" Variables ".into(), format!("{err} ").red()]),
+        }
+    }
+
+    fn status_line(&self) -> Line<'static> {
+        let mut line = Line::default();
+        let operations = self.operations();
+        if operations.len() > 1 {
+            let name = self.operation().unwrap_or_default();
+            let n = operations
+                .iter()
+                .position(|operation| operation.as_deref() == Some(name.as_str()))
+                .map_or(0, |n| n + 1);
+            line.push_span(format!("Operation {name} ({n} of {})  ", operations.len()).cyan());
+        }
+        let schema = match (&self.fetching, &self.fetch_error, &self.schema) {
+            (Some(_), _, _) => "Fetching the schema…".yellow(),
+            (_, Some(err), _) => err.clone().red(),
+            (_, _, Some(schema)) => format!("Schema with {} types", schema.types.len()).dark_gray(),
+            (_, _, None) => "No schema, <r> to fetch it".dark_gray(),
+        };
+        line.push_span(schema);
+        line
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let [status_area, area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        self.status_line().render(status_area, buf);
+
+        if self.focus == Focus::Docs {
+            if let Some(schema) = &self.schema {
+                let block = self.block(Line::raw(" Docs "), true);
+                let height = block.inner(area).height as usize;
+                Paragraph::new(self.docs.lines(schema, height))
+                    .block(block)
+                    .render(area, buf);
+                return;
+            }
+        }
+
+        let [query_area, variables_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(6)]).areas(area);
+        let editing = self.focus == Focus::Editing;
+        for editor in [Editor::Query, Editor::Variables] {
+            let focused = self.editor == editor;
+            let title = match editor {
+                Editor::Query => self.query_title(),
+                Editor::Variables => self.variables_title(),
+            };
+            let block = self.block(title, focused);
+            let textarea = match editor {
+                Editor::Query => &mut self.query,
+                Editor::Variables => &mut self.variables,
+            };
+            textarea.set_block(block);
+            textarea.set_cursor_line_style(Style::default());
+            let cursor_style = if editing && focused {
+                Style::default().reversed()
+            } else {
+                Style::default()
+            };
+            textarea.set_cursor_style(cursor_style);
+            let placeholder = match editor {
+                Editor::Query => "query { ... }",
+                Editor::Variables => "{\"id\": 1}",
+            };
+            textarea.set_placeholder_text(placeholder);
+        }
+        self.query.render(query_area, buf);
+        self.variables.render(variables_area, buf);
+        // Over the variables, which aren't being typed in meanwhile.
+        if let Some(popup) = &self.completions {
+            popup.render(variables_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::graphql::schema::tests::schema;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_complete_and_apply() {
+        let mut editor = GraphqlEditor {
+            schema: Some(schema()),
+            ..GraphqlEditor::default()
+        };
+        assert_eq!(
+            editor.handle_key(key(KeyCode::Enter)),
+            Some(Action::SwitchMode(Mode::Prompt))
+        );
+        editor.query.insert_str("query A { us");
+        editor.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::CONTROL));
+        editor.handle_key(key(KeyCode::Down));
+        editor.handle_key(key(KeyCode::Enter));
+        editor
+            .query
+            .insert_str(" { id } } query B { users { id } }");
+        assert_eq!(
+            editor.query_text(),
+            "query A { users { id } } query B { users { id } }"
+        );
+
+        assert_eq!(
+            editor.handle_key(key(KeyCode::Esc)),
+            Some(Action::SwitchMode(Mode::Request))
+        );
+        editor.handle_key(key(KeyCode::Char('o')));
+        editor.handle_key(key(KeyCode::Char('o')));
+        assert_eq!(editor.operation(), Some("A".into()));
+
+        let mut request = HttpRequest::get("https://api.example.com/graphql");
+        editor.apply(&mut request);
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            graphql::parse_request_body(&request.body),
+            Some((editor.query_text(), String::new(), Some("A".into())))
+        );
+        assert_eq!(
+            request.headers,
+            [("Content-Type".to_string(), "application/json".to_string())]
+        );
+    }
+}
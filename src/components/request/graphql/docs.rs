@@ -0,0 +1,201 @@
+use ratatui::prelude::*;
+
+use crate::graphql::schema::Schema;
+
+/// An entry of the docs explorer, leading to the type named by `target` if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub label: String,
+    pub detail: String,
+    pub description: Option<String>,
+    pub target: Option<String>,
+}
+
+/// Browses the types of a schema, starting from its root types.
+#[derive(Debug, Default)]
+pub struct DocsExplorer {
+    /// The types opened so far, the one shown last.
+    path: Vec<String>,
+    selected: usize,
+}
+
+impl DocsExplorer {
+    /// The entries of the type being shown, or the root types and then every other one.
+    pub fn items(&self, schema: &Schema) -> Vec<Item> {
+        let Some(name) = self.path.last() else {
+            let roots = [
+                ("query", &schema.query_type),
+                ("mutation", &schema.mutation_type),
+                ("subscription", &schema.subscription_type),
+            ];
+            let roots = roots.into_iter().filter_map(|(label, name)| {
+                name.as_ref().map(|name| Item {
+                    label: label.into(),
+                    detail: name.clone(),
+                    description: None,
+                    target: Some(name.clone()),
+                })
+            });
+            let types = schema
+                .types
+                .iter()
+                .filter(|ty| !ty.name.starts_with("__"))
+                .map(|ty| Item {
+                    label: ty.name.clone(),
+                    detail: ty.kind.to_lowercase(),
+                    description: ty.description.clone(),
+                    target: Some(ty.name.clone()),
+                });
+            return roots.chain(types).collect();
+        };
+        let Some(ty) = schema.get(name) else {
+            return vec![];
+        };
+        let fields = ty.fields.iter().map(|field| {
+            let args: Vec<_> = field
+                .args
+                .iter()
+                .map(|arg| match &arg.default_value {
+                    Some(default) => format!("{}: {} = {default}", arg.name, arg.ty),
+                    None => format!("{}: {}", arg.name, arg.ty),
+                })
+                .collect();
+            let args = if args.is_empty() {
+                String::new()
+            } else {
+                format!("({})", args.join(", "))
+            };
+            Item {
+                label: field.name.clone(),
+                detail: format!("{args}: {}", field.ty),
+                description: field.description.clone(),
+                target: Some(field.ty.named().to_string()),
+            }
+        });
+        let input_fields = ty.input_fields.iter().map(|field| Item {
+            label: field.name.clone(),
+            detail: match &field.default_value {
+                Some(default) => format!(": {} = {default}", field.ty),
+                None => format!(": {}", field.ty),
+            },
+            description: field.description.clone(),
+            target: Some(field.ty.named().to_string()),
+        });
+        let values = ty.enum_values.iter().map(|value| Item {
+            label: value.clone(),
+            detail: String::new(),
+            description: None,
+            target: None,
+        });
+        let possible = ty.possible_types.iter().map(|name| Item {
+            label: name.clone(),
+            detail: "possible type".into(),
+            description: None,
+            target: Some(name.clone()),
+        });
+        fields
+            .chain(input_fields)
+            .chain(values)
+            .chain(possible)
+            .collect()
+    }
+
+    pub fn move_selection(&mut self, schema: &Schema, delta: isize) {
+        let len = self.items(schema).len();
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    /// Opens the type the selected entry leads to.
+    pub fn open(&mut self, schema: &Schema) {
+        let target = self
+            .items(schema)
+            .into_iter()
+            .nth(self.selected)
+            .and_then(|item| item.target);
+        // Scalars have nothing more to show.
+        let opens = |target: &String| schema.get(target).is_some_and(|ty| ty.kind != "SCALAR");
+        if let Some(target) = target.filter(opens) {
+            self.path.push(target);
+            self.selected = 0;
+        }
+    }
+
+    /// Goes back to the previous type, returning false if there was none.
+    pub fn back(&mut self) -> bool {
+        self.selected = 0;
+        self.path.pop().is_some()
+    }
+
+    pub fn lines(&self, schema: &Schema, height: usize) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(
+            std::iter::once("Schema".to_string())
+                .chain(self.path.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(" › "),
+        )
+        .bold()];
+        let description = match self.path.last() {
+            Some(name) => schema.get(name).and_then(|ty| ty.description.clone()),
+            None => None,
+        };
+        if let Some(description) = description {
+            lines.push(Line::raw(description).italic().dark_gray());
+        }
+
+        let items = self.items(schema);
+        let height = height.saturating_sub(lines.len()).max(1);
+        let offset = (self.selected + 1).saturating_sub(height);
+        for (n, item) in items.iter().enumerate().skip(offset).take(height) {
+            let mut line = Line::from(vec![
+                Span::raw(item.label.clone()).cyan(),
+                Span::raw(item.detail.clone()),
+            ]);
+            if let Some(description) = &item.description {
+                let first = description.lines().next().unwrap_or_default();
+                line.push_span(format!("  {first}").dark_gray());
+            }
+            if n == self.selected {
+                line = line.reversed();
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::graphql::schema::tests::schema;
+
+    #[test]
+    fn test_explore() {
+        let schema = schema();
+        let mut docs = DocsExplorer::default();
+        let labels = |docs: &DocsExplorer| -> Vec<String> {
+            docs.items(&schema)
+                .into_iter()
+                .map(|item| item.label)
+                .collect()
+        };
+        assert_eq!(labels(&docs)[..3], ["query", "Query", "User"]);
+
+        docs.open(&schema);
+        assert_eq!(labels(&docs), ["user", "users"]);
+        docs.move_selection(&schema, 5);
+        docs.open(&schema);
+        assert_eq!(labels(&docs), ["id", "name", "posts"]);
+        assert_eq!(docs.items(&schema)[2].detail, "(first: Int = 10): [Post]");
+
+        docs.open(&schema);
+        assert_eq!(labels(&docs), ["id", "name", "posts"]);
+        assert!(docs.back());
+        assert!(docs.back());
+        assert!(!docs.back());
+    }
+}
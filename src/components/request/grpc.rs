@@ -0,0 +1,752 @@
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use prost_reflect::{DescriptorPool, FieldDescriptor, Kind, MessageDescriptor, MethodDescriptor};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Clear, Paragraph};
+use tokio::sync::oneshot;
+use tracing::error;
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::action::Action;
+use crate::app::Mode;
+use crate::components::request::completion::{before_cursor, Completion, Popup};
+use crate::executor::grpc::{
+    encode_message,
+    find_method,
+    load_protos,
+    method_path,
+    methods,
+    reflect,
+    template,
+    DescriptorCache,
+};
+use crate::executor::{Client, HttpRequest};
+
+/// What the keys go to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    #[default]
+    Browsing,
+    Message,
+    Protos,
+    /// The list of methods, with the one highlighted.
+    Methods(usize),
+}
+
+/// Where the cursor is in the JSON of a message, as far as completion goes.
+#[derive(Debug, PartialEq, Eq)]
+enum JsonPosition {
+    /// Where a key of the object at the path goes.
+    Key(Vec<String>),
+    /// Where the value of the field at the path goes.
+    Value(Vec<String>),
+}
+
+/// Where the service definitions came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    Protos,
+    Reflection,
+    Cache,
+}
+
+/// A gRPC call: the service definitions to use and the request message to send.
+#[derive(Default)]
+pub struct GrpcEditor {
+    /// Paths of `.proto` files, separated by spaces.
+    protos: TextArea<'static>,
+    message: TextArea<'static>,
+    focus: Focus,
+    completions: Option<Popup>,
+    cache: DescriptorCache,
+    /// The URL the call goes to, which names the method in its path.
+    url: String,
+    /// The server the definitions were looked up for.
+    server: Option<String>,
+    pool: Option<(DescriptorPool, Source)>,
+    loading: Option<oneshot::Receiver<Result<DescriptorPool, String>>>,
+    /// Why the definitions couldn't be loaded.
+    load_error: Option<String>,
+}
+
+impl GrpcEditor {
+    pub fn new(cache: DescriptorCache) -> Self {
+        Self {
+            cache,
+            ..Self::default()
+        }
+    }
+
+    /// Whether all keys go to the editor, rather than some changing tabs.
+    pub fn is_capturing(&self) -> bool {
+        self.focus != Focus::Browsing
+    }
+
+    /// Follows the URL, looking up the definitions cached for its server when that changes.
+    pub fn set_endpoint(&mut self, url: &str) {
+        let url = url.trim();
+        self.url = url.to_string();
+        let server = match url.split_once("://") {
+            Some((scheme, rest)) => format!("{scheme}://{}", rest.split('/').next().unwrap_or("")),
+            None => url.to_string(),
+        };
+        if self.server.as_deref() != Some(server.as_str()) {
+            self.pool = self.cache.load(&server).map(|pool| (pool, Source::Cache));
+            self.server = Some(server);
+            self.load_error = None;
+        }
+    }
+
+    fn message_text(&self) -> String {
+        self.message.lines().join("\n")
+    }
+
+    fn method(&self) -> Option<MethodDescriptor> {
+        find_method(&self.pool.as_ref()?.0, &self.url)
+    }
+
+    /// Keeps the message as the body, so that it is saved and restored with the tab.
+    pub fn apply(&self, request: &mut HttpRequest) {
+        request.method = "POST".into();
+        request.body = self.message_text().into_bytes();
+    }
+
+    pub fn restore(&mut self, body: &[u8]) {
+        self.message = TextArea::from(String::from_utf8_lossy(body).lines());
+    }
+
+    /// The method to call and the encoded message to send it, or why the call can't be made.
+    pub fn call(&self, url: &str) -> Result<(MethodDescriptor, Vec<u8>), String> {
+        let Some((pool, _)) = &self.pool else {
+            return Err("Load .proto files or use server reflection to call a method".into());
+        };
+        let Some(path) = method_path(url) else {
+            return Err(
+                "Pick a method to call, e.g. grpc://host:port/package.Service/Method".into(),
+            );
+        };
+        let method = find_method(pool, url).ok_or_else(|| format!("No method {path}"))?;
+        let message = encode_message(&method, &self.message_text())
+            .map_err(|err| format!("Invalid message: {err}"))?;
+        Ok((method, message))
+    }
+
+    /// Asks the server for its definitions through reflection, in the background.
+    pub fn fetch_descriptors(&mut self, client: Client, request: HttpRequest) {
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let pool = reflect(&client, &request).await;
+            let _ = tx.send(pool.map_err(|err| err.to_string()));
+        });
+        self.loading = Some(rx);
+        self.load_error = None;
+    }
+
+    fn load_protos(&mut self) {
+        let files: Vec<PathBuf> = self
+            .protos
+            .lines()
+            .join(" ")
+            .split_whitespace()
+            .map(PathBuf::from)
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+        match load_protos(&files) {
+            Ok(pool) => self.loaded(pool, Source::Protos),
+            Err(err) => self.load_error = Some(err.to_string()),
+        }
+    }
+
+    fn loaded(&mut self, pool: DescriptorPool, source: Source) {
+        if let Some(server) = &self.server {
+            if let Err(err) = self.cache.save(server, &pool) {
+                error!("Failed to save the service definitions: {err}");
+            }
+        }
+        self.pool = Some((pool, source));
+        self.load_error = None;
+    }
+
+    /// Picks up the definitions once the server has sent them.
+    pub fn poll(&mut self) {
+        let Some(loading) = self.loading.as_mut() else {
+            return;
+        };
+        let Ok(result) = loading.try_recv() else {
+            return;
+        };
+        self.loading = None;
+        match result {
+            Ok(pool) => self.loaded(pool, Source::Reflection),
+            Err(err) => self.load_error = Some(err),
+        }
+    }
+
+    fn methods(&self) -> Vec<MethodDescriptor> {
+        self.pool
+            .as_ref()
+            .map(|(pool, _)| methods(pool))
+            .unwrap_or_default()
+    }
+
+    /// Calls the method instead, starting its message over if nothing was typed yet.
+    fn select_method(&mut self, method: &MethodDescriptor) -> Action {
+        if self.message_text().trim().is_empty() {
+            self.message = TextArea::from(template(method).lines());
+        }
+        let server = self.server.clone().unwrap_or_default();
+        let path = format!("{}/{}", method.parent_service().full_name(), method.name());
+        Action::SetUrl(format!("{server}/{path}"))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match self.focus {
+            Focus::Browsing => self.handle_browsing_key(key),
+            Focus::Message => self.handle_message_key(key),
+            Focus::Protos => match key.code {
+                KeyCode::Enter | KeyCode::Esc => {
+                    self.focus = Focus::Browsing;
+                    self.load_protos();
+                    Some(Action::SwitchMode(Mode::Request))
+                }
+                _ => {
+                    self.protos.input(key);
+                    None
+                }
+            },
+            Focus::Methods(selected) => {
+                let methods = self.methods();
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.focus = Focus::Methods(selected.saturating_sub(1));
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let last = methods.len().saturating_sub(1);
+                        self.focus = Focus::Methods((selected + 1).min(last));
+                    }
+                    KeyCode::Enter => {
+                        self.focus = Focus::Browsing;
+                        return methods
+                            .get(selected)
+                            .map(|method| self.select_method(method));
+                    }
+                    KeyCode::Esc | KeyCode::Char('s') => self.focus = Focus::Browsing,
+                    _ => {}
+                }
+                None
+            }
+        }
+    }
+
+    fn handle_browsing_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            // Typing shouldn't trigger the bindings of the request pane.
+            KeyCode::Enter | KeyCode::Char('i') => {
+                self.focus = Focus::Message;
+                return Some(Action::SwitchMode(Mode::Prompt));
+            }
+            KeyCode::Char('p') => {
+                self.focus = Focus::Protos;
+                self.protos.move_cursor(CursorMove::End);
+                return Some(Action::SwitchMode(Mode::Prompt));
+            }
+            KeyCode::Char('r') if self.loading.is_none() => return Some(Action::FetchSchema),
+            KeyCode::Char('s') if self.pool.is_some() => {
+                let methods = self.methods();
+                let current = self.method();
+                let selected = methods
+                    .iter()
+                    .position(|method| Some(method) == current.as_ref())
+                    .unwrap_or(0);
+                self.focus = Focus::Methods(selected);
+            }
+            KeyCode::Char('t') => {
+                if let Some(method) = self.method() {
+                    self.message = TextArea::from(template(&method).lines());
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_message_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if let Some(popup) = &mut self.completions {
+            match key.code {
+                KeyCode::Up => {
+                    popup.previous();
+                    return None;
+                }
+                KeyCode::Down => {
+                    popup.next();
+                    return None;
+                }
+                KeyCode::Enter | KeyCode::Tab => {
+                    popup.accept(&mut self.message);
+                    self.completions = None;
+                    return None;
+                }
+                KeyCode::Esc => {
+                    self.completions = None;
+                    return None;
+                }
+                _ => {}
+            }
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.focus = Focus::Browsing;
+                return Some(Action::SwitchMode(Mode::Request));
+            }
+            KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.complete();
+                return None;
+            }
+            _ => {
+                self.message.input(key);
+            }
+        }
+        if self.completions.is_some() {
+            self.complete();
+        }
+        None
+    }
+
+    /// Offers the fields, or values, that fit at the cursor, if the method is known.
+    fn complete(&mut self) {
+        let Some(method) = self.method() else {
+            return;
+        };
+        self.completions = Popup::new(completions(&method.input(), &before_cursor(&self.message)));
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self.focus {
+            Focus::Message if self.completions.is_some() => "<↑/↓> choose  <Enter> insert",
+            Focus::Message => "<Ctrl-Space> complete  <Esc> done",
+            Focus::Protos => "<Enter> load",
+            Focus::Methods(_) => "<Enter> call  <Esc> close",
+            Focus::Browsing => "<Enter> edit  <s> method  <p> protos  <r> reflect  <t> template",
+        }
+    }
+
+    fn block(title: Line<'static>, focused: bool) -> Block<'static> {
+        let border_style = if focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered().border_style(border_style).title(title)
+    }
+
+    fn status_line(&self) -> Line<'static> {
+        let mut line = Line::default();
+        match self.method() {
+            Some(method) => {
+                let kind = match (method.is_client_streaming(), method.is_server_streaming()) {
+                    (false, false) => "unary",
+                    (false, true) => "server streaming",
+                    (true, false) => "client streaming, one message",
+                    (true, true) => "bidirectional, one message",
+                };
+                line.push_span(format!("{} ", method.full_name()).cyan());
+                line.push_span(format!("({kind})  ").dark_gray());
+            }
+            None if self.pool.is_some() => line.push_span("No method, <s> to pick one  ".yellow()),
+            None => {}
+        }
+        let source = match (&self.loading, &self.load_error, &self.pool) {
+            (Some(_), _, _) => "Asking the server for its services…".yellow(),
+            (_, Some(err), _) => err.clone().red(),
+            (_, _, Some((pool, source))) => {
+                let source = match source {
+                    Source::Protos => "the .proto files",
+                    Source::Reflection => "reflection",
+                    Source::Cache => "the last session",
+                };
+                format!("{} methods from {source}", methods(pool).len()).dark_gray()
+            }
+            (_, _, None) => "No services, <p> to load .proto files or <r> to reflect".dark_gray(),
+        };
+        line.push_span(source);
+        line
+    }
+
+    fn message_title(&self) -> Line<'static> {
+        let mut title = Line::raw(" Message ");
+        if let Some(method) = self.method() {
+            match encode_message(&method, &self.message_text()) {
+                Ok(_) => title.push_span(format!("{} ✓ ", method.input().name()).green()),
+                Err(err) => title.push_span(format!("{err} ").red()),
+            }
+        }
+        title
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let [status_area, protos_area, message_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Min(3),
+        ])
+        .areas(area);
+        self.status_line().render(status_area, buf);
+
+        let editing_protos = self.focus == Focus::Protos;
+        self.protos
+            .set_block(Self::block(Line::raw(" Proto files "), editing_protos));
+        self.protos.set_cursor_line_style(Style::default());
+        self.protos.set_cursor_style(if editing_protos {
+            Style::default().reversed()
+        } else {
+            Style::default()
+        });
+        self.protos
+            .set_placeholder_text("greeter.proto protos/other.proto");
+        self.protos.render(protos_area, buf);
+
+        let editing = self.focus == Focus::Message;
+        self.message
+            .set_block(Self::block(self.message_title(), editing));
+        self.message.set_cursor_line_style(Style::default());
+        self.message.set_cursor_style(if editing {
+            Style::default().reversed()
+        } else {
+            Style::default()
+        });
+        self.message.set_placeholder_text("{\"name\": \"World\"}");
+        self.message.render(message_area, buf);
+
+        if let Some(popup) = &self.completions {
+            popup.render(message_area, buf);
+        }
+        if let Focus::Methods(selected) = self.focus {
+            self.render_methods(message_area, buf, selected);
+        }
+    }
+
+    fn render_methods(&self, area: Rect, buf: &mut Buffer, selected: usize) {
+        let block = Self::block(Line::raw(" Methods "), true);
+        let height = block.inner(area).height as usize;
+        let offset = (selected + 1).saturating_sub(height);
+        let lines: Vec<Line> = self
+            .methods()
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(n, method)| {
+                let mut line = Line::from(vec![
+                    format!("{}/", method.parent_service().full_name()).dark_gray(),
+                    method.name().to_string().cyan(),
+                ]);
+                if method.is_server_streaming() {
+                    line.push_span("  stream".dark_gray());
+                }
+                if n == selected {
+                    line = line.reversed();
+                }
+                line
+            })
+            .collect();
+        Clear.render(area, buf);
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+/// The fields of a message, or the values of one, that can be typed at the cursor.
+fn completions(message: &MessageDescriptor, before_cursor: &str) -> Vec<Completion> {
+    let prefix: String = before_cursor
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    match json_position(before_cursor) {
+        Some(JsonPosition::Key(path)) => {
+            let Some(message) = message_at(message, &path) else {
+                return vec![];
+            };
+            message
+                .fields()
+                .filter(|field| field.json_name().starts_with(&prefix))
+                .map(|field| Completion {
+                    label: format!("\"{}\"", field.json_name()),
+                    detail: format!(" {}", type_name(&field)),
+                })
+                .collect()
+        }
+        Some(JsonPosition::Value(path)) => {
+            let Some((last, parents)) = path.split_last() else {
+                return vec![];
+            };
+            let field =
+                message_at(message, parents).and_then(|message| field_named(&message, last));
+            match field.map(|field| field.kind()) {
+                Some(Kind::Enum(values)) => values
+                    .values()
+                    .filter(|value| value.name().starts_with(&prefix))
+                    .map(|value| Completion {
+                        label: format!("\"{}\"", value.name()),
+                        detail: format!(" = {}", value.number()),
+                    })
+                    .collect(),
+                Some(Kind::Bool) => ["true", "false"]
+                    .into_iter()
+                    .filter(|value| value.starts_with(&prefix))
+                    .map(|value| Completion {
+                        label: value.into(),
+                        detail: String::new(),
+                    })
+                    .collect(),
+                _ => vec![],
+            }
+        }
+        None => vec![],
+    }
+}
+
+/// Follows the keys of the objects the cursor is in, ignoring arrays, which repeat a field.
+fn json_position(before_cursor: &str) -> Option<JsonPosition> {
+    struct Frame {
+        /// The key this object or array is the value of.
+        name: Option<String>,
+        is_object: bool,
+        /// The key last read in this object.
+        key: Option<String>,
+        expects_key: bool,
+    }
+
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut string: Option<String> = None;
+    let mut escaped = false;
+    for c in before_cursor.chars() {
+        if let Some(s) = &mut string {
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                    s.push(c);
+                }
+                '\\' => escaped = true,
+                '"' => {
+                    let s = string.take().unwrap_or_default();
+                    if let Some(frame) = stack.last_mut().filter(|frame| frame.is_object) {
+                        if frame.expects_key {
+                            frame.key = Some(s);
+                        }
+                    }
+                }
+                _ => s.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => string = Some(String::new()),
+            '{' | '[' => {
+                let name = match stack.last() {
+                    Some(frame) if frame.is_object => frame.key.clone(),
+                    Some(frame) => frame.name.clone(),
+                    None => None,
+                };
+                stack.push(Frame {
+                    name,
+                    is_object: c == '{',
+                    key: None,
+                    expects_key: c == '{',
+                });
+            }
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.expects_key = false;
+                }
+            }
+            ',' => {
+                if let Some(frame) = stack.last_mut().filter(|frame| frame.is_object) {
+                    frame.expects_key = true;
+                    frame.key = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // An object in an array is the value of the same field as the array.
+    let mut path: Vec<String> = stack
+        .windows(2)
+        .filter(|pair| pair[0].is_object)
+        .filter_map(|pair| pair[1].name.clone())
+        .collect();
+    match stack.last()? {
+        Frame {
+            is_object: true,
+            expects_key: true,
+            ..
+        } => Some(JsonPosition::Key(path)),
+        Frame {
+            is_object: true,
+            key: Some(key),
+            ..
+        } => {
+            path.push(key.clone());
+            Some(JsonPosition::Value(path))
+        }
+        Frame {
+            is_object: false, ..
+        } => Some(JsonPosition::Value(path)),
+        _ => None,
+    }
+}
+
+/// The message the fields on the path lead to, the keys of maps being skipped.
+fn message_at(message: &MessageDescriptor, path: &[String]) -> Option<MessageDescriptor> {
+    let mut message = message.clone();
+    let mut names = path.iter();
+    while let Some(name) = names.next() {
+        let mut field = field_named(&message, name)?;
+        if field.is_map() {
+            // The keys of a map are for the user to pick.
+            names.next()?;
+            field = field.kind().as_message()?.map_entry_value_field();
+        }
+        message = field.kind().as_message()?.clone();
+    }
+    Some(message)
+}
+
+/// A field by the name it has in JSON, or in the `.proto` file.
+fn field_named(message: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    message
+        .get_field_by_json_name(name)
+        .or_else(|| message.get_field_by_name(name))
+}
+
+/// The type of a field as written in a `.proto` file, e.g. `repeated string`.
+fn type_name(field: &FieldDescriptor) -> String {
+    fn kind_name(kind: Kind) -> String {
+        match kind {
+            Kind::Message(message) => message.name().to_string(),
+            Kind::Enum(values) => values.name().to_string(),
+            kind => format!("{kind:?}").to_lowercase(),
+        }
+    }
+
+    if field.is_map() {
+        if let Some(entry) = field.kind().as_message() {
+            let key = kind_name(entry.map_entry_key_field().kind());
+            let value = kind_name(entry.map_entry_value_field().kind());
+            return format!("map<{key}, {value}>");
+        }
+    }
+    let name = kind_name(field.kind());
+    if field.is_list() {
+        format!("repeated {name}")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto,
+        EnumDescriptorProto,
+        EnumValueDescriptorProto,
+        FieldDescriptorProto,
+        FileDescriptorProto,
+    };
+
+    use super::*;
+
+    fn field(name: &str, number: i32, ty: Type, type_name: Option<&str>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.into()),
+            json_name: None,
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(ty as i32),
+            type_name: type_name.map(str::to_string),
+            ..FieldDescriptorProto::default()
+        }
+    }
+
+    /// `Order { string order_id; repeated Item items; Status status; }` with
+    /// `Item { string sku; bool gift; }`.
+    fn order() -> MessageDescriptor {
+        let mut items = field("items", 2, Type::Message, Some(".shop.Item"));
+        items.label = Some(Label::Repeated as i32);
+        let file = FileDescriptorProto {
+            name: Some("shop.proto".into()),
+            package: Some("shop".into()),
+            syntax: Some("proto3".into()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("Order".into()),
+                    field: vec![
+                        field("order_id", 1, Type::String, None),
+                        items,
+                        field("status", 3, Type::Enum, Some(".shop.Status")),
+                    ],
+                    ..DescriptorProto::default()
+                },
+                DescriptorProto {
+                    name: Some("Item".into()),
+                    field: vec![
+                        field("sku", 1, Type::String, None),
+                        field("gift", 2, Type::Bool, None),
+                    ],
+                    ..DescriptorProto::default()
+                },
+            ],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Status".into()),
+                value: ["PENDING", "SHIPPED"]
+                    .iter()
+                    .enumerate()
+                    .map(|(n, name)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(n as i32),
+                        options: None,
+                    })
+                    .collect(),
+                ..EnumDescriptorProto::default()
+            }],
+            ..FileDescriptorProto::default()
+        };
+        let pool = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+            file: vec![file],
+        })
+        .unwrap();
+        pool.get_message_by_name("shop.Order").unwrap()
+    }
+
+    fn labels(before_cursor: &str) -> Vec<String> {
+        completions(&order(), before_cursor)
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_completions() {
+        assert_eq!(labels("{"), ["\"orderId\"", "\"items\"", "\"status\""]);
+        assert_eq!(labels("{\"or"), ["\"orderId\""]);
+        assert_eq!(
+            labels("{\"orderId\": \"1\", \"items\": [{\"sku\": \"a\"}, {"),
+            ["\"sku\"", "\"gift\""]
+        );
+        assert_eq!(labels("{\"items\": [{\"gift\": t"), ["true"]);
+        assert_eq!(labels("{\"status\": \"S"), ["\"SHIPPED\""]);
+        assert_eq!(labels("{\"orderId\": \""), Vec::<String>::new());
+        assert_eq!(completions(&order(), "{\"it")[0].detail, " repeated Item");
+    }
+}
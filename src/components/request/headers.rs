@@ -0,0 +1,136 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use tui_textarea::{CursorMove, TextArea};
+
+/// Headers typed one per line as `Name: value`. Blank lines and lines starting with `#` are
+/// skipped, so headers can be switched off without losing them.
+#[derive(Default)]
+pub struct HeadersTab {
+    input: TextArea<'static>,
+    editing: bool,
+}
+
+impl HeadersTab {
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    pub fn start_editing(&mut self) {
+        self.editing = true;
+    }
+
+    /// Handles a key while editing, returning whether editing carries on.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Esc {
+            self.editing = false;
+        } else {
+            self.input.input(key);
+        }
+        self.editing
+    }
+
+    pub fn headers(&self) -> Vec<(String, String)> {
+        self.input
+            .lines()
+            .iter()
+            .filter_map(|line| parse_line(line).ok().flatten())
+            .collect()
+    }
+
+    pub fn set_headers(&mut self, headers: &[(String, String)]) {
+        self.input = TextArea::new(
+            headers
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}"))
+                .collect(),
+        );
+        self.input.move_cursor(CursorMove::Bottom);
+        self.input.move_cursor(CursorMove::End);
+    }
+
+    /// The first line that isn't a header, numbered from 1.
+    fn invalid_line(&self) -> Option<usize> {
+        self.input
+            .lines()
+            .iter()
+            .position(|line| parse_line(line).is_err())
+            .map(|n| n + 1)
+    }
+
+    pub fn hint(&self) -> String {
+        match (self.invalid_line(), self.editing) {
+            (Some(n), _) => format!("line {n} is not `Name: value`"),
+            (None, true) => "<Esc> done".into(),
+            (None, false) => "<Enter> edit".into(),
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, block: Block<'static>) {
+        self.input.set_placeholder_text("Name: value, one per line");
+        self.input.set_cursor_line_style(Style::default());
+        let cursor_style = if self.editing {
+            Style::default().reversed()
+        } else {
+            Style::default()
+        };
+        self.input.set_cursor_style(cursor_style);
+        self.input.set_block(block);
+        self.input.render(area, buf);
+    }
+}
+
+/// Parses a line into a header, or `None` for a blank or commented-out line.
+fn parse_line(line: &str) -> Result<Option<(String, String)>, ()> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (name, value) = line.split_once(':').ok_or(())?;
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !valid {
+        return Err(());
+    }
+    Ok(Some((name.to_string(), value.trim().to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_headers() {
+        let mut tab = HeadersTab {
+            input: TextArea::from([
+                "Accept: application/json",
+                "",
+                "# X-Debug: 1",
+                "Sec-WebSocket-Protocol: chat, superchat",
+            ]),
+            editing: true,
+        };
+        assert_eq!(
+            tab.headers(),
+            [
+                ("Accept".to_string(), "application/json".to_string()),
+                (
+                    "Sec-WebSocket-Protocol".to_string(),
+                    "chat, superchat".to_string()
+                ),
+            ]
+        );
+        assert_eq!(tab.invalid_line(), None);
+
+        tab.input.move_cursor(CursorMove::Bottom);
+        tab.input.move_cursor(CursorMove::End);
+        tab.input.insert_newline();
+        tab.input.insert_str("not a header");
+        assert_eq!(tab.invalid_line(), Some(5));
+    }
+}
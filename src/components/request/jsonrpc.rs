@@ -0,0 +1,357 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use serde_json::Value;
+use tui_textarea::TextArea;
+
+use crate::action::Action;
+use crate::app::Mode;
+use crate::executor::HttpRequest;
+use crate::jsonrpc::{self, Call};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Editor {
+    #[default]
+    Method,
+    Params,
+}
+
+/// A call being written, with the id it was given when added.
+struct CallEditor {
+    method: TextArea<'static>,
+    params: TextArea<'static>,
+    /// `None` for a notification.
+    id: Option<Value>,
+}
+
+/// A JSON-RPC 2.0 body: one call, or a batch of them.
+pub struct JsonRpcEditor {
+    calls: Vec<CallEditor>,
+    selected: usize,
+    editor: Editor,
+    editing: bool,
+    /// Id given to the next call added, or turned back from a notification.
+    next_id: u64,
+}
+
+impl Default for JsonRpcEditor {
+    fn default() -> Self {
+        let mut editor = Self {
+            calls: vec![],
+            selected: 0,
+            editor: Editor::default(),
+            editing: false,
+            next_id: 1,
+        };
+        editor.add_call();
+        editor
+    }
+}
+
+impl CallEditor {
+    fn call(&self) -> Call {
+        Call {
+            method: self.method.lines().join(""),
+            params: self.params.lines().join("\n"),
+            id: self.id.clone(),
+        }
+    }
+}
+
+impl JsonRpcEditor {
+    /// Whether all keys go to the editor, rather than some changing tabs.
+    pub fn is_capturing(&self) -> bool {
+        self.editing
+    }
+
+    fn calls(&self) -> Vec<Call> {
+        self.calls.iter().map(CallEditor::call).collect()
+    }
+
+    fn generate_id(&mut self) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        Value::from(id)
+    }
+
+    /// Adds a call after the selected one, turning the request into a batch.
+    fn add_call(&mut self) {
+        let call = CallEditor {
+            method: TextArea::default(),
+            params: TextArea::default(),
+            id: Some(self.generate_id()),
+        };
+        let n = if self.calls.is_empty() {
+            0
+        } else {
+            self.selected + 1
+        };
+        self.calls.insert(n, call);
+        self.selected = n;
+        self.editor = Editor::Method;
+    }
+
+    fn remove_call(&mut self) {
+        if self.calls.len() > 1 {
+            self.calls.remove(self.selected);
+            self.selected = self.selected.min(self.calls.len() - 1);
+        }
+    }
+
+    fn toggle_notification(&mut self) {
+        let id = match self.calls[self.selected].id {
+            Some(_) => None,
+            None => Some(self.generate_id()),
+        };
+        self.calls[self.selected].id = id;
+    }
+
+    /// Turns the request into a JSON-RPC one, sending an empty body if a call is incomplete.
+    pub fn apply(&self, request: &mut HttpRequest) {
+        request.method = "POST".into();
+        request.body = jsonrpc::request_body(&self.calls()).unwrap_or_default();
+        let has_content_type = request
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        if !has_content_type {
+            request
+                .headers
+                .push(("Content-Type".into(), "application/json".into()));
+        }
+    }
+
+    /// Why the request can't be sent as it is.
+    pub fn error(&self) -> Option<String> {
+        jsonrpc::request_body(&self.calls()).err()
+    }
+
+    /// Fills the editor from the calls of a request sent earlier, carrying on from their ids.
+    pub fn restore(&mut self, calls: Vec<Call>) {
+        self.next_id = calls
+            .iter()
+            .filter_map(|call| call.id.as_ref()?.as_u64())
+            .max()
+            .map_or(1, |id| id + 1);
+        self.calls = calls
+            .into_iter()
+            .map(|call| CallEditor {
+                method: TextArea::from([call.method]),
+                params: TextArea::from(call.params.lines()),
+                id: call.id,
+            })
+            .collect();
+        if self.calls.is_empty() {
+            self.add_call();
+        }
+        self.selected = 0;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if self.editing {
+            return self.handle_editing_key(key);
+        }
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('i') => {
+                self.editing = true;
+                // Typing shouldn't trigger the bindings of the request pane.
+                return Some(Action::SwitchMode(Mode::Prompt));
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.editor = match self.editor {
+                    Editor::Method => Editor::Params,
+                    Editor::Params => Editor::Method,
+                };
+            }
+            KeyCode::Char('a') => self.add_call(),
+            KeyCode::Char('x') => self.remove_call(),
+            KeyCode::Char('n') => self.toggle_notification(),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.calls.len() - 1)
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_editing_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let call = &mut self.calls[self.selected];
+        match (key.code, self.editor) {
+            (KeyCode::Esc, _) => {
+                self.editing = false;
+                return Some(Action::SwitchMode(Mode::Request));
+            }
+            // The method is a single line, so Enter moves on to the params.
+            (KeyCode::Enter | KeyCode::Tab, Editor::Method) => self.editor = Editor::Params,
+            (_, Editor::Method) => {
+                call.method.input(key);
+            }
+            (_, Editor::Params) => {
+                call.params.input(key);
+            }
+        }
+        None
+    }
+
+    pub fn hint(&self) -> &'static str {
+        if self.editing {
+            "<Esc> done"
+        } else {
+            "<Enter> edit  <Tab> params  <↑/↓> call  <a> add  <x> remove  <n> notification"
+        }
+    }
+
+    fn block(&self, title: Line<'static>, focused: bool) -> Block<'static> {
+        let border_style = if focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered().border_style(border_style).title(title)
+    }
+
+    /// A line per call, the selected one highlighted.
+    fn call_lines(&self) -> Vec<Line<'static>> {
+        self.calls
+            .iter()
+            .enumerate()
+            .map(|(n, call)| {
+                let id = match &call.id {
+                    Some(id) => format!("#{:<4}", id.to_string()),
+                    None => "notif".into(),
+                };
+                let method = call.method.lines().join("");
+                let mut line = Line::from(vec![
+                    format!("{id} ").dark_gray(),
+                    if method.is_empty() {
+                        "(no method)".dark_gray()
+                    } else {
+                        method.cyan()
+                    },
+                ]);
+                if let Err(err) = call_error(&call.call()) {
+                    line.push_span(format!("  {err}").red());
+                }
+                if n == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect()
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let batch = self.calls.len() > 1;
+        let calls_height = if batch {
+            self.calls.len().min(5) as u16 + 1
+        } else {
+            0
+        };
+        let [calls_area, method_area, params_area] = Layout::vertical([
+            Constraint::Length(calls_height),
+            Constraint::Length(3),
+            Constraint::Min(3),
+        ])
+        .areas(area);
+
+        if batch {
+            let [title_area, list_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(calls_area);
+            Line::raw(format!("Batch of {} calls", self.calls.len()))
+                .cyan()
+                .render(title_area, buf);
+            let offset = (self.selected + 1).saturating_sub(list_area.height as usize);
+            for (n, line) in self
+                .call_lines()
+                .into_iter()
+                .skip(offset)
+                .take(list_area.height as usize)
+                .enumerate()
+            {
+                let row = Rect {
+                    y: list_area.y + n as u16,
+                    height: 1,
+                    ..list_area
+                };
+                line.render(row, buf);
+            }
+        }
+
+        let id = match &self.calls[self.selected].id {
+            Some(id) => format!(" Method · id {id} "),
+            None => " Method · notification ".into(),
+        };
+        let params_title = match jsonrpc::parse_params(&self.calls[self.selected].call().params) {
+            Ok(_) => Line::raw(" Params "),
+            Err(err) => Line::from(vec![" Params ".into(), format!("{err} ").red()]),
+        };
+        let method_block = self.block(Line::raw(id), self.editor == Editor::Method);
+        let params_block = self.block(params_title, self.editor == Editor::Params);
+        let editing = self.editing;
+        let editor = self.editor;
+        let call = &mut self.calls[self.selected];
+        for (textarea, block, kind, placeholder) in [
+            (&mut call.method, method_block, Editor::Method, "subtract"),
+            (&mut call.params, params_block, Editor::Params, "[42, 23]"),
+        ] {
+            textarea.set_block(block);
+            textarea.set_cursor_line_style(Style::default());
+            let cursor_style = if editing && editor == kind {
+                Style::default().reversed()
+            } else {
+                Style::default()
+            };
+            textarea.set_cursor_style(cursor_style);
+            textarea.set_placeholder_text(placeholder);
+        }
+        call.method.render(method_area, buf);
+        call.params.render(params_area, buf);
+    }
+}
+
+/// Why a single call can't be made as it is.
+fn call_error(call: &Call) -> Result<(), String> {
+    jsonrpc::request_body(std::slice::from_ref(call)).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_batch_and_apply() {
+        let mut editor = JsonRpcEditor::default();
+        editor.calls[0].method.insert_str("sum");
+        editor.calls[0].params.insert_str("[1, 2]");
+        editor.handle_key(key(KeyCode::Char('a')));
+        editor.calls[1].method.insert_str("ping");
+        editor.handle_key(key(KeyCode::Char('n')));
+        editor.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(editor.error(), Some("Every call needs a method".into()));
+        editor.handle_key(key(KeyCode::Char('x')));
+        assert_eq!(editor.error(), None);
+
+        let mut request = HttpRequest::get("https://api.example.com/rpc");
+        editor.apply(&mut request);
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            String::from_utf8(request.body.clone()).unwrap(),
+            "[{\"jsonrpc\":\"2.0\",\"method\":\"sum\",\"params\":[1,2],\"id\":1},{\"jsonrpc\":\"2.\
+             0\",\"method\":\"ping\"}]"
+        );
+
+        let mut restored = JsonRpcEditor::default();
+        restored.restore(jsonrpc::parse_request_body(&request.body).unwrap());
+        restored.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(restored.calls[1].id, Some(Value::from(2)));
+    }
+}
@@ -0,0 +1,282 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+
+use crate::executor::RequestOptions;
+
+/// A row of the Options tab.
+struct Setting {
+    label: &'static str,
+    kind: Kind,
+}
+
+enum Kind {
+    Toggle(fn(&mut RequestOptions) -> &mut bool),
+    Number(fn(&mut RequestOptions) -> &mut u32),
+    Statuses(fn(&mut RequestOptions) -> &mut Vec<u16>),
+    Text(fn(&mut RequestOptions) -> &mut String),
+    /// One of a fixed set of values, shown by the first function and moved on by the second.
+    Choice(fn(&RequestOptions) -> String, fn(&mut RequestOptions)),
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        label: "Send and store cookies",
+        kind: Kind::Toggle(|o| &mut o.cookies),
+    },
+    Setting {
+        label: "Follow redirects",
+        kind: Kind::Toggle(|o| &mut o.follow_redirects),
+    },
+    Setting {
+        label: "Maximum redirects",
+        kind: Kind::Number(|o| &mut o.max_redirects),
+    },
+    Setting {
+        label: "Keep method and body on 301, 302 and 303",
+        kind: Kind::Toggle(|o| &mut o.keep_method),
+    },
+    Setting {
+        label: "Send Authorization to other hosts on redirect",
+        kind: Kind::Toggle(|o| &mut o.forward_authorization),
+    },
+    Setting {
+        label: "Connect timeout in ms, 0 for none",
+        kind: Kind::Number(|o| &mut o.connect_timeout),
+    },
+    Setting {
+        label: "Read timeout in ms, 0 for none",
+        kind: Kind::Number(|o| &mut o.read_timeout),
+    },
+    Setting {
+        label: "Total timeout in ms, 0 for none",
+        kind: Kind::Number(|o| &mut o.total_timeout),
+    },
+    Setting {
+        label: "Attempts, 1 for no retries",
+        kind: Kind::Number(|o| &mut o.max_attempts),
+    },
+    Setting {
+        label: "First retry after ms",
+        kind: Kind::Number(|o| &mut o.retry_backoff),
+    },
+    Setting {
+        label: "Retry when the connection fails",
+        kind: Kind::Toggle(|o| &mut o.retry_on_connection_error),
+    },
+    Setting {
+        label: "Retry on statuses",
+        kind: Kind::Statuses(|o| &mut o.retry_statuses),
+    },
+    Setting {
+        label: "Unix socket, empty to connect to the host",
+        kind: Kind::Text(|o| &mut o.unix_socket),
+    },
+    Setting {
+        label: "HTTP version",
+        kind: Kind::Choice(
+            |o| o.http_version.to_string(),
+            |o| o.http_version = o.http_version.next(),
+        ),
+    },
+    Setting {
+        label: "Compress the body with",
+        kind: Kind::Choice(
+            |o| o.compress_body.to_string(),
+            |o| o.compress_body = o.compress_body.next(),
+        ),
+    },
+];
+
+/// The settings of the request being composed, where switches are flipped and numbers typed in
+/// place.
+#[derive(Default)]
+pub struct OptionsTab {
+    pub options: RequestOptions,
+    selected: usize,
+    /// What has been typed into the selected number while it is being edited.
+    editing: Option<String>,
+}
+
+impl OptionsTab {
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// Handles a key, returning whether it was used.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let setting = &SETTINGS[self.selected];
+        if let Some(input) = self.editing.as_mut() {
+            match (key.code, &setting.kind) {
+                (KeyCode::Char(c), Kind::Text(_)) => input.push(c),
+                (KeyCode::Char(c), _) if c.is_ascii_digit() => input.push(c),
+                (KeyCode::Char(c @ (',' | ' ')), Kind::Statuses(_)) => input.push(c),
+                (KeyCode::Backspace, _) => {
+                    input.pop();
+                }
+                (KeyCode::Enter, Kind::Number(value)) => {
+                    if let Ok(number) = input.parse() {
+                        *value(&mut self.options) = number;
+                    }
+                    self.editing = None;
+                }
+                (KeyCode::Enter, Kind::Statuses(value)) => {
+                    let statuses = input
+                        .split([',', ' '])
+                        .filter(|status| !status.is_empty())
+                        .map(str::parse)
+                        .collect();
+                    if let Ok(statuses) = statuses {
+                        *value(&mut self.options) = statuses;
+                    }
+                    self.editing = None;
+                }
+                (KeyCode::Enter, Kind::Text(value)) => {
+                    *value(&mut self.options) = input.trim().to_string();
+                    self.editing = None;
+                }
+                (KeyCode::Esc, _) => self.editing = None,
+                _ => {}
+            }
+            return true;
+        }
+        match (key.code, &setting.kind) {
+            (KeyCode::Up, _) => self.selected = self.selected.saturating_sub(1),
+            (KeyCode::Down, _) => self.selected = (self.selected + 1).min(SETTINGS.len() - 1),
+            (KeyCode::Char(' ') | KeyCode::Enter, Kind::Toggle(value)) => {
+                let value = value(&mut self.options);
+                *value = !*value;
+            }
+            (KeyCode::Char(' ') | KeyCode::Enter, Kind::Choice(_, next)) => next(&mut self.options),
+            (KeyCode::Enter, Kind::Number(_) | Kind::Statuses(_)) => {
+                self.editing = Some(String::new())
+            }
+            // Text starts from its value, as paths are long to type again.
+            (KeyCode::Enter, Kind::Text(value)) => {
+                self.editing = Some(value(&mut self.options).clone())
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let mut options = self.options.clone();
+        SETTINGS
+            .iter()
+            .enumerate()
+            .map(|(i, setting)| {
+                let selected = i == self.selected;
+                let line = match &setting.kind {
+                    Kind::Toggle(value) => {
+                        let mark = if *value(&mut options) { "x" } else { " " };
+                        Line::raw(format!("[{mark}] {}", setting.label))
+                    }
+                    Kind::Number(value) => {
+                        let value = value(&mut options).to_string();
+                        self.value_line(setting, selected, value)
+                    }
+                    Kind::Statuses(value) => {
+                        let value = value(&mut options)
+                            .iter()
+                            .map(u16::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        self.value_line(setting, selected, value)
+                    }
+                    Kind::Text(value) => {
+                        let value = value(&mut options).clone();
+                        self.value_line(setting, selected, value)
+                    }
+                    Kind::Choice(value, _) => self.value_line(setting, selected, value(&options)),
+                };
+                if selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect()
+    }
+
+    /// A row showing a value, or what is being typed in its place.
+    fn value_line(&self, setting: &Setting, selected: bool, value: String) -> Line<'static> {
+        match &self.editing {
+            Some(input) if selected => Line::raw(format!("    {}: {input}▏", setting.label)),
+            _ => Line::raw(format!("    {}: {value}", setting.label)),
+        }
+    }
+
+    /// What the keys do on the selected row.
+    pub fn hint(&self) -> &'static str {
+        match (&SETTINGS[self.selected].kind, self.is_editing()) {
+            (_, true) => "<Enter> set  <Esc> cancel",
+            (Kind::Toggle(_), false) => "▲ ▼ select  <Space> toggle",
+            (Kind::Choice(..), false) => "▲ ▼ select  <Space> next value",
+            (Kind::Number(_) | Kind::Statuses(_) | Kind::Text(_), false) => {
+                "▲ ▼ select  <Enter> edit"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::executor::compression::BodyCompression;
+    use crate::executor::HttpVersion;
+
+    fn press(tab: &mut OptionsTab, code: KeyCode) {
+        tab.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_edit_settings() {
+        let mut tab = OptionsTab::default();
+        press(&mut tab, KeyCode::Char(' '));
+        assert!(!tab.options.cookies);
+
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Char('3'));
+        press(&mut tab, KeyCode::Char('x'));
+        assert_eq!(tab.lines()[2].to_string(), "    Maximum redirects: 3▏");
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.options.max_redirects, 3);
+        assert!(!tab.is_editing());
+
+        while !matches!(SETTINGS[tab.selected].kind, Kind::Statuses(_)) {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Enter);
+        "500, 503"
+            .chars()
+            .for_each(|c| press(&mut tab, KeyCode::Char(c)));
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.options.retry_statuses, [500, 503]);
+
+        while SETTINGS[tab.selected].label != "HTTP version" {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Char(' '));
+        assert_eq!(tab.options.http_version, HttpVersion::Http2);
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Char(' '));
+        assert_eq!(tab.options.compress_body, BodyCompression::Gzip);
+        press(&mut tab, KeyCode::Up);
+        press(&mut tab, KeyCode::Char(' '));
+        press(&mut tab, KeyCode::Char(' '));
+        assert_eq!(tab.options.http_version, HttpVersion::Http10);
+        assert_eq!(
+            tab.lines()[tab.selected].to_string(),
+            "    HTTP version: HTTP/1.0"
+        );
+    }
+}
@@ -0,0 +1,1685 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use color_eyre::Result;
+use crossterm::event::{
+    KeyCode,
+    KeyEvent,
+    KeyEventKind,
+    KeyModifiers,
+    MouseButton,
+    MouseEvent,
+    MouseEventKind,
+};
+use encoding_rs::Encoding;
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+use ratatui::style::{palette::tailwind, Stylize};
+use ratatui::widgets::{Block, Cell, Clear, Padding, Paragraph, Row, Table, Tabs};
+use ratatui::Frame;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use tracing::error;
+use tui_textarea::{CursorMove, TextArea};
+
+use self::download::{progress_line, resolve_path, suggest_path};
+use self::filter::{Filter, SavedFilters};
+use self::json::JsonTree;
+use self::search::{Match, Search};
+use self::viewer::charset::{self, Charset, Source};
+use self::viewer::{hex, BodyView, Document, Format, SyntaxStyles};
+use crate::action::Action;
+use crate::app::Mode;
+use crate::components::Component;
+use crate::config::Config;
+use crate::executor::{body::Progress, HttpRequest, HttpResponse};
+use crate::history::{History, HistoryEntry};
+use crate::jsonrpc::{pair, parse_request_body, Call};
+use crate::session::{ResponseState, MAX_SAVED_BODY};
+use crate::variables::Variables;
+
+pub mod download;
+pub mod filter;
+pub mod json;
+pub mod jsonrpc;
+pub mod raw;
+pub mod redirects;
+pub mod search;
+pub mod timing;
+pub mod viewer;
+
+/// Number of lines moved by a single mouse wheel notch.
+const SCROLL_STEP: usize = 3;
+
+#[derive(
+    Debug, Default, Display, FromRepr, EnumIter, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum ResponseTab {
+    #[default]
+    #[strum(to_string = "Body")]
+    Body,
+    #[strum(to_string = "Headers")]
+    Headers,
+    #[strum(to_string = "Raw")]
+    Raw,
+    #[strum(to_string = "Timing")]
+    Timing,
+    #[strum(to_string = "Redirects")]
+    Redirects,
+}
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    Pending,
+    Failed(String),
+    Received(Box<HttpResponse>),
+}
+
+/// What the response panes of all tabs keep in the data directory. It is loaded once and shared,
+/// as each save rewrites or appends to files that every tab reads.
+#[derive(Debug, Default, Clone)]
+pub struct Shared {
+    pub saved_filters: Rc<RefCell<SavedFilters>>,
+    pub history: Rc<RefCell<History>>,
+    pub variables: Rc<RefCell<Variables>>,
+}
+
+#[derive(Default)]
+pub struct Response {
+    command_tx: Option<UnboundedSender<Action>>,
+    config: Config,
+    styles: SyntaxStyles,
+    focused: bool,
+    state: State,
+    /// The redirects that led to the response followed by the response itself, one of which is
+    /// shown.
+    chain: Vec<HttpResponse>,
+    hop: usize,
+    selected_tab: ResponseTab,
+    view: BodyView,
+    format_override: Option<Format>,
+    /// How far along the body of the pending response is, once it has started arriving.
+    progress: Option<Progress>,
+    /// The start of the pending response's body as it arrives, shown until it is complete.
+    partial: Vec<u8>,
+    /// Where each line of `partial` starts.
+    partial_lines: Vec<usize>,
+    /// Whether the body is shown as it arrived rather than decoded, if it was compressed.
+    show_encoded: bool,
+    /// The text encoding picked by the user, in place of the one the response declares.
+    encoding_override: Option<&'static Encoding>,
+    document: Option<Document>,
+    loading: Option<oneshot::Receiver<Document>>,
+    /// The calls of the JSON-RPC request the response is for, if it was one.
+    rpc_calls: Option<Vec<Call>>,
+    /// The JSON-RPC view, when the body answers the calls.
+    rpc_lines: Vec<Line<'static>>,
+    /// The Raw tab of the response being shown, laid out once as it can be long.
+    raw_lines: Vec<Line<'static>>,
+    filter_input: TextArea<'static>,
+    editing_filter: bool,
+    filter_error: Option<String>,
+    filtered: Option<JsonTree>,
+    filtering: Option<oneshot::Receiver<JsonTree>>,
+    shared: Shared,
+    search: Search,
+    /// Text of the lines of the current view, kept until the view changes so that each search
+    /// doesn't format them again.
+    search_text: Option<Arc<Vec<String>>>,
+    /// Matches being found in the background, and whether to bring the first into view.
+    finding: Option<(oneshot::Receiver<Vec<Match>>, bool)>,
+    search_input: TextArea<'static>,
+    searching: bool,
+    save_input: Option<TextArea<'static>>,
+    /// The name to capture the filter's first match under, while it is being entered.
+    capture_input: Option<TextArea<'static>>,
+    saving: Option<oneshot::Receiver<Result<PathBuf, String>>>,
+    notice: Option<Line<'static>>,
+    match_style: Style,
+    current_match_style: Style,
+    body_offsets: HashMap<BodyView, usize>,
+    headers_offset: usize,
+    raw_offset: usize,
+    timing_offset: usize,
+    redirects_offset: usize,
+    table_column: usize,
+    body_area: Rect,
+    filter_area: Rect,
+}
+
+impl ResponseTab {
+    pub fn previous(self) -> Self {
+        let current_idx: usize = self as usize;
+        let previous_idx = current_idx.saturating_sub(1);
+
+        Self::from_repr(previous_idx).unwrap_or(self)
+    }
+
+    pub fn next(self) -> Self {
+        let current_idx: usize = self as usize;
+        let next_idx = current_idx.saturating_add(1);
+
+        Self::from_repr(next_idx).unwrap_or(self)
+    }
+
+    pub const fn palette(self) -> tailwind::Palette {
+        match self {
+            Self::Body => tailwind::GREEN,
+            Self::Headers => tailwind::YELLOW,
+            Self::Raw => tailwind::ORANGE,
+            Self::Timing => tailwind::BLUE,
+            Self::Redirects => tailwind::PURPLE,
+        }
+    }
+
+    fn title(self) -> Line<'static> {
+        format!("  {self}  ")
+            .fg(tailwind::SLATE.c900)
+            .bg(self.palette().c900)
+            .into()
+    }
+}
+
+impl Shared {
+    pub fn load(data_dir: PathBuf, workspace_dir: PathBuf) -> Self {
+        Self {
+            saved_filters: Rc::new(RefCell::new(SavedFilters::load(data_dir.clone()))),
+            history: Rc::new(RefCell::new(History::load(data_dir))),
+            variables: Rc::new(RefCell::new(Variables::load(workspace_dir))),
+        }
+    }
+}
+
+impl Response {
+    pub fn new(shared: Shared) -> Self {
+        Self {
+            shared,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Marks a request as in flight, discarding the previous response.
+    pub fn set_pending(&mut self) {
+        self.state = State::Pending;
+        self.progress = None;
+        self.partial.clear();
+        self.partial_lines.clear();
+        self.chain.clear();
+        self.document = None;
+        self.loading = None;
+    }
+
+    /// The response and scroll positions to save with the session, unless there is no response
+    /// or it is too large to keep.
+    pub fn snapshot(&self) -> Option<ResponseState> {
+        let too_large = |response: &HttpResponse| {
+            response.spilled.is_some() || response.body.len() > MAX_SAVED_BODY
+        };
+        if self.chain.is_empty() || self.chain.iter().any(too_large) {
+            return None;
+        }
+        Some(ResponseState {
+            chain: self.chain.clone(),
+            hop: self.hop,
+            tab: self.selected_tab,
+            view: self.view,
+            body_offsets: self.body_offsets.clone(),
+            headers_offset: self.headers_offset,
+            timing_offset: self.timing_offset,
+            redirects_offset: self.redirects_offset,
+        })
+    }
+
+    /// Shows a response saved with the session, without recording it in the history again.
+    pub fn restore(&mut self, state: ResponseState) {
+        self.chain = state.chain;
+        self.redirects_offset = state.redirects_offset;
+        self.show_hop(state.hop);
+        self.selected_tab = state.tab;
+        self.view = state.view;
+        self.body_offsets = state.body_offsets;
+        self.headers_offset = state.headers_offset;
+        self.timing_offset = state.timing_offset;
+    }
+
+    /// Remembers the request the next response answers, to pair JSON-RPC results with its calls.
+    pub fn set_request(&mut self, request: &HttpRequest) {
+        self.rpc_calls = parse_request_body(&request.body);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state, State::Pending)
+    }
+
+    /// Adds to the body shown while the response is still arriving.
+    fn add_partial(&mut self, data: &[u8]) {
+        for &byte in data {
+            if self.partial.is_empty() || self.partial.ends_with(b"\n") {
+                self.partial_lines.push(self.partial.len());
+            }
+            self.partial.push(byte);
+        }
+    }
+
+    /// The `n`th line of the body received so far, cut to `width` characters.
+    fn partial_line(&self, n: usize, width: usize) -> Line<'static> {
+        let start = self.partial_lines[n];
+        let end = self
+            .partial_lines
+            .get(n + 1)
+            .copied()
+            .unwrap_or(self.partial.len());
+        let line = String::from_utf8_lossy(&self.partial[start..end]);
+        Line::raw(
+            line.trim_end_matches(['\r', '\n'])
+                .chars()
+                .take(width)
+                .collect::<String>(),
+        )
+    }
+
+    fn set_response(&mut self, mut response: Box<HttpResponse>) {
+        if let Some(expression) = self.shared.saved_filters.borrow().get(&response.url) {
+            self.filter_input = TextArea::from([expression]);
+        }
+        let recorded = self
+            .shared
+            .history
+            .borrow_mut()
+            .record(HistoryEntry::new(&response));
+        if let Err(err) = recorded {
+            error!("Failed to record history: {err}");
+        }
+        self.chain = std::mem::take(&mut response.redirects);
+        self.chain.push(*response);
+        self.redirects_offset = 0;
+        self.partial = Vec::new();
+        self.partial_lines = Vec::new();
+        self.show_hop(self.chain.len() - 1);
+    }
+
+    /// Shows the `n`th response of the redirect chain in the other tabs.
+    fn show_hop(&mut self, n: usize) {
+        let Some(response) = self.chain.get(n) else {
+            return;
+        };
+        self.hop = n;
+        self.raw_lines = match (&response.raw, &response.http2) {
+            (Some(exchange), _) => raw::lines(exchange),
+            (None, Some(_)) => vec![Line::raw(
+                "HTTP/2 sends binary frames with compressed headers, so there is no raw text",
+            )
+            .dark_gray()],
+            (None, None) => {
+                vec![Line::raw("The raw exchange isn't kept with saved responses").dark_gray()]
+            }
+        };
+        self.state = State::Received(Box::new(response.clone()));
+        self.format_override = None;
+        self.show_encoded = false;
+        self.encoding_override = None;
+        self.headers_offset = 0;
+        self.raw_offset = 0;
+        self.timing_offset = 0;
+        let height = self.body_height().max(1);
+        self.redirects_offset = self
+            .redirects_offset
+            .clamp((n + 1).saturating_sub(height), n);
+        self.load_document();
+    }
+
+    /// Prepares the body for display in the background, in the overridden format if one is set.
+    fn load_document(&mut self) {
+        let State::Received(response) = &self.state else {
+            return;
+        };
+        let body = self.shown_body(response).to_vec();
+        // Compressed bytes mean nothing in the format of what they decode to.
+        let (content_type, format) = match self.show_encoded {
+            true => (None, Some(Format::Binary)),
+            false => (
+                response.header("content-type").map(str::to_string),
+                self.format_override,
+            ),
+        };
+        let encoding = self.encoding_override;
+        let (tx, rx) = oneshot::channel();
+        tokio::task::spawn_blocking(move || {
+            let document = Document::new(&body, content_type.as_deref(), format, encoding);
+            let _ = tx.send(document);
+        });
+
+        self.document = None;
+        self.loading = Some(rx);
+        self.filtered = None;
+        self.filtering = None;
+        self.invalidate_search();
+        self.body_offsets.clear();
+        self.table_column = 0;
+    }
+
+    /// The body as decoded, or as it arrived while that is what is being shown.
+    fn shown_body<'a>(&self, response: &'a HttpResponse) -> &'a [u8] {
+        match &response.decoding {
+            Some(decoding) if self.show_encoded => &decoding.encoded,
+            _ => &response.body,
+        }
+    }
+
+    /// Reads the body as text in the next encoding, for servers that don't say or get it wrong.
+    fn cycle_encoding(&mut self) {
+        if self.document.as_ref().is_some_and(|d| d.charset.is_some()) {
+            self.encoding_override = charset::next_override(self.encoding_override);
+            self.load_document();
+        }
+    }
+
+    /// Switches between the decoded body and the compressed bytes it arrived as.
+    fn toggle_encoded(&mut self) {
+        let State::Received(response) = &self.state else {
+            return;
+        };
+        if response.decoding.is_some() {
+            self.show_encoded = !self.show_encoded;
+            self.load_document();
+        }
+    }
+
+    /// Forces the body to be shown as the next format, for servers that mislabel their content.
+    fn cycle_format(&mut self) {
+        self.format_override = Format::next_override(self.format_override);
+        self.load_document();
+    }
+
+    /// Picks up the document once it has been prepared in the background.
+    fn poll_document(&mut self) {
+        let Some(loading) = self.loading.as_mut() else {
+            return;
+        };
+        if let Ok(document) = loading.try_recv() {
+            let exchanges = match (&self.rpc_calls, &document.value) {
+                (Some(calls), Some(value)) => pair(calls, value),
+                _ => None,
+            };
+            self.rpc_lines = exchanges
+                .map(|exchanges| jsonrpc::lines(&exchanges, &self.styles))
+                .unwrap_or_default();
+            let views = self.views(&document);
+            if self.view == BodyView::Raw
+                || !views.contains(&self.view)
+                || views[0] == BodyView::JsonRpc
+            {
+                self.view = views[0];
+            }
+            self.document = Some(document);
+            self.loading = None;
+            self.invalidate_search();
+            self.apply_filter();
+        }
+    }
+
+    /// Picks up the filtered tree once the filter has been evaluated in the background.
+    fn poll_filter(&mut self) {
+        let Some(filtering) = self.filtering.as_mut() else {
+            return;
+        };
+        if let Ok(tree) = filtering.try_recv() {
+            self.filtered = Some(tree);
+            self.filtering = None;
+            self.invalidate_search();
+            self.body_offsets.remove(&BodyView::Tree);
+            self.body_offsets.remove(&BodyView::Pretty);
+        }
+    }
+
+    /// Reports the outcome of saving the body once the write has finished.
+    fn poll_saving(&mut self) {
+        let Some(saving) = self.saving.as_mut() else {
+            return;
+        };
+        if let Ok(result) = saving.try_recv() {
+            self.notice = Some(match result {
+                Ok(path) => Line::raw(format!("Saved to {}", path.display())).green(),
+                Err(err) => Line::raw(format!("Failed to save: {err}")).red(),
+            });
+            self.saving = None;
+        }
+    }
+
+    /// Opens the prompt for where to save the body, suggesting a file in the data directory.
+    fn start_save(&mut self) -> Option<Action> {
+        let State::Received(response) = &self.state else {
+            return None;
+        };
+        let file_type = self.document.as_ref().and_then(|d| d.file_type);
+        let path = suggest_path(&self.config.config.data_dir, response, file_type);
+        let mut input = TextArea::from([path.to_string_lossy()]);
+        input.move_cursor(CursorMove::End);
+        self.save_input = Some(input);
+        Some(Action::SwitchMode(Mode::Prompt))
+    }
+
+    fn handle_save_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let input = self.save_input.as_mut()?;
+        match key.code {
+            KeyCode::Enter => {
+                let path = resolve_path(&input.lines().join(""));
+                if let State::Received(response) = &self.state {
+                    let body = self.shown_body(response).to_vec();
+                    // Held until the copy is done, as the file goes once the response does.
+                    let spilled = response.spilled.clone();
+                    let (tx, rx) = oneshot::channel();
+                    tokio::spawn(async move {
+                        let result = match spilled {
+                            Some(spilled) => download::copy(path, spilled.path().into()).await,
+                            None => download::save(path, body).await,
+                        };
+                        let _ = tx.send(result.map_err(|err| err.to_string()));
+                    });
+                    self.saving = Some(rx);
+                }
+            }
+            KeyCode::Esc => {}
+            _ => {
+                input.input(key);
+                return None;
+            }
+        }
+        self.save_input = None;
+        Some(Action::SwitchMode(Mode::Response))
+    }
+
+    /// Opens the prompt for the name of a variable to capture the filter's first match in.
+    fn start_capture(&mut self) -> Option<Action> {
+        if self.filter_error.is_some() || self.filter_expression().trim().is_empty() {
+            return None;
+        }
+        self.editing_filter = false;
+        self.capture_input = Some(TextArea::default());
+        Some(Action::SwitchMode(Mode::Prompt))
+    }
+
+    fn handle_capture_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let input = self.capture_input.as_mut()?;
+        match key.code {
+            KeyCode::Enter => {
+                let name = input.lines().join("").trim().to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                self.notice = Some(self.capture(&name));
+            }
+            KeyCode::Esc => {}
+            _ => {
+                input.input(key);
+                return None;
+            }
+        }
+        self.capture_input = None;
+        Some(Action::SwitchMode(Mode::Response))
+    }
+
+    /// Stores the filter's first match as the variable `name`, for requests to use as
+    /// `{{name}}`.
+    fn capture(&self, name: &str) -> Line<'static> {
+        let value = Filter::parse(&self.filter_expression())
+            .ok()
+            .zip(self.document.as_ref().and_then(|d| d.value.as_ref()))
+            .and_then(|(filter, value)| filter.capture(value));
+        let Some(value) = value else {
+            return Line::raw("Nothing matches the filter").red();
+        };
+        match self.shared.variables.borrow_mut().set(name, &value) {
+            Ok(()) => Line::raw(format!("Captured {{{{{name}}}}} = {value}")).green(),
+            Err(err) => Line::raw(format!("Failed to capture: {err}")).red(),
+        }
+    }
+
+    fn filter_expression(&self) -> String {
+        self.filter_input.lines().join("")
+    }
+
+    /// Re-evaluates the filter against the current document.
+    ///
+    /// An invalid expression keeps the last good result on screen and reports the error next to
+    /// the input instead.
+    fn apply_filter(&mut self) {
+        let expression = self.filter_expression();
+        if expression.trim().is_empty() {
+            self.filter_error = None;
+            self.filtered = None;
+            self.filtering = None;
+            return;
+        }
+        let filter = match Filter::parse(&expression) {
+            Ok(filter) => filter,
+            Err(err) => {
+                self.filter_error = Some(err);
+                return;
+            }
+        };
+        self.filter_error = None;
+
+        let Some(value) = self.document.as_ref().and_then(|d| d.value.clone()) else {
+            return;
+        };
+        let (tx, rx) = oneshot::channel();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(JsonTree::from_value(&filter.apply(&value)));
+        });
+        self.filtering = Some(rx);
+    }
+
+    fn save_filter(&mut self) {
+        let State::Received(response) = &self.state else {
+            return;
+        };
+        let expression = self.filter_expression();
+        let saved = self
+            .shared
+            .saved_filters
+            .borrow_mut()
+            .save(&response.url, &expression);
+        if let Err(err) = saved {
+            error!("Failed to save filter: {err}");
+        }
+    }
+
+    /// The views that can show the document, starting with the JSON-RPC one when it applies.
+    fn views(&self, document: &Document) -> Vec<BodyView> {
+        let mut views = document.views();
+        if !self.rpc_lines.is_empty() {
+            views.insert(0, BodyView::JsonRpc);
+        }
+        views
+    }
+
+    fn has_json(&self) -> bool {
+        matches!(self.document, Some(Document { json: Some(_), .. }))
+    }
+
+    /// The tree shown in the tree and pretty views, after filtering.
+    fn tree(&self) -> Option<&JsonTree> {
+        self.filtered
+            .as_ref()
+            .or_else(|| self.document.as_ref().and_then(|d| d.json.as_ref()))
+    }
+
+    fn tree_mut(&mut self) -> Option<&mut JsonTree> {
+        match self.filtered {
+            Some(ref mut tree) => Some(tree),
+            None => self.document.as_mut().and_then(|d| d.json.as_mut()),
+        }
+    }
+
+    /// Whether the pretty view shows the JSON tree rather than the pretty-printed document, which
+    /// is the case for JSON bodies and whenever a filter is active.
+    fn pretty_shows_tree(&self) -> bool {
+        self.filtered.is_some() || self.document.as_ref().is_some_and(|d| d.pretty.is_empty())
+    }
+
+    fn body_height(&self) -> usize {
+        let height = self.body_area.height as usize;
+        match (self.selected_tab, self.view) {
+            // The table header takes up a row.
+            (ResponseTab::Body, BodyView::Table) => height.saturating_sub(1),
+            _ => height,
+        }
+    }
+
+    fn line_count(&self) -> usize {
+        match (self.selected_tab, &self.state) {
+            (ResponseTab::Headers, State::Received(response)) => header_rows(response).count(),
+            (ResponseTab::Raw, State::Received(_)) => self.raw_lines.len(),
+            (ResponseTab::Timing, State::Received(_)) => self.timing_lines().len(),
+            (ResponseTab::Redirects, _) => self.chain.len(),
+            (ResponseTab::Body, State::Pending) => self.partial_lines.len(),
+            (ResponseTab::Body, _) => match (self.view, self.tree(), &self.document) {
+                (_, _, None) => 0,
+                (BodyView::Tree, Some(tree), _) => tree.visible_len(),
+                (BodyView::Pretty, Some(tree), _) if self.pretty_shows_tree() => tree.nodes().len(),
+                (BodyView::Pretty, _, Some(document)) => document.pretty.len(),
+                (BodyView::Readable, _, Some(document)) => document.readable.len(),
+                (BodyView::Table, _, Some(document)) => {
+                    document.table.as_ref().map_or(0, |table| table.rows.len())
+                }
+                (BodyView::Hex, _, Some(document)) => hex::line_count(document.bytes.len()),
+                (BodyView::JsonRpc, _, Some(_)) => self.rpc_lines.len(),
+                (_, _, Some(document)) => document.line_count(),
+            },
+            _ => 0,
+        }
+    }
+
+    /// The `n`th line of the current body view, cut to `width` characters where lines can be long.
+    fn body_line(&self, n: usize, width: usize) -> Line<'static> {
+        let Some(document) = &self.document else {
+            return Line::default();
+        };
+        match (self.view, self.tree()) {
+            (BodyView::Tree, Some(tree)) => tree
+                .visible_node(n)
+                .map(|node| tree.line(node, &self.styles, true))
+                .unwrap_or_default(),
+            (BodyView::Pretty, Some(tree)) if self.pretty_shows_tree() => {
+                tree.line(n, &self.styles, false)
+            }
+            (BodyView::Pretty, _) => document
+                .pretty
+                .get(n)
+                .map(|fragments| self.styles.clipped_line(fragments, width))
+                .unwrap_or_default(),
+            (BodyView::Readable, _) => {
+                Line::raw(document.readable[n].chars().take(width).collect::<String>())
+            }
+            (BodyView::Table, _) => document
+                .table
+                .as_ref()
+                .map(|table| Line::raw(table.rows[n].join("  ")))
+                .unwrap_or_default(),
+            (BodyView::Hex, _) => self.styles.line(&hex::line(&document.bytes, n)),
+            (BodyView::JsonRpc, _) => self.rpc_lines.get(n).cloned().unwrap_or_default(),
+            _ => Line::raw(document.line(n).chars().take(width).collect::<String>()),
+        }
+    }
+
+    fn offset(&self) -> usize {
+        match self.selected_tab {
+            ResponseTab::Headers => self.headers_offset,
+            ResponseTab::Raw => self.raw_offset,
+            ResponseTab::Timing => self.timing_offset,
+            ResponseTab::Redirects => self.redirects_offset,
+            ResponseTab::Body => self
+                .body_offsets
+                .get(&self.view)
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn offset_mut(&mut self) -> &mut usize {
+        match self.selected_tab {
+            ResponseTab::Headers => &mut self.headers_offset,
+            ResponseTab::Raw => &mut self.raw_offset,
+            ResponseTab::Timing => &mut self.timing_offset,
+            ResponseTab::Redirects => &mut self.redirects_offset,
+            ResponseTab::Body => self.body_offsets.entry(self.view).or_default(),
+        }
+    }
+
+    /// Scrolls the current view, or moves the cursor in the tree view.
+    fn scroll(&mut self, delta: isize) {
+        if self.selected_tab == ResponseTab::Body && self.view == BodyView::Tree {
+            if let Some(tree) = self.tree_mut() {
+                tree.move_cursor(delta);
+                return;
+            }
+        }
+        let max = self.line_count().saturating_sub(self.body_height());
+        let offset = self.offset_mut();
+        *offset = offset.saturating_add_signed(delta).min(max);
+    }
+
+    fn page(&self) -> isize {
+        self.body_height().max(1) as isize
+    }
+
+    /// Leaves any input inside the pane, e.g. when another pane is focused.
+    pub fn blur(&mut self) {
+        self.searching = false;
+        self.editing_filter = false;
+        self.save_input = None;
+        self.capture_input = None;
+    }
+
+    /// Text of every line in the current view, as searched.
+    fn view_lines(&self) -> Vec<String> {
+        let text = |line: Line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        };
+        match (self.selected_tab, &self.state) {
+            (ResponseTab::Headers, State::Received(response)) => header_rows(response)
+                .map(|(name, value)| format!("{name}: {value}"))
+                .collect(),
+            (ResponseTab::Raw, State::Received(_)) => {
+                self.raw_lines.iter().cloned().map(text).collect()
+            }
+            (ResponseTab::Timing, _) => self.timing_lines().into_iter().map(text).collect(),
+            (ResponseTab::Redirects, _) => redirects::lines(&self.chain, self.hop)
+                .into_iter()
+                .map(text)
+                .collect(),
+            (ResponseTab::Body, _) => (0..self.line_count())
+                .map(|n| text(self.body_line(n, usize::MAX)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// First line of the current view that is on screen, or the cursor in the tree view.
+    fn top_line(&self) -> usize {
+        match (self.selected_tab, self.view, self.tree()) {
+            (ResponseTab::Body, BodyView::Tree, Some(tree)) => tree.cursor(),
+            _ => self.offset(),
+        }
+    }
+
+    /// Marks the matches as out of date because the lines of the view changed.
+    fn invalidate_search(&mut self) {
+        self.search.invalidate();
+        self.search_text = None;
+    }
+
+    /// Starts finding the matches again in the background, as a large body takes a while to scan.
+    fn refresh_search(&mut self, jump: bool) {
+        self.finding = None;
+        let Some(pattern) = self.search.start() else {
+            return;
+        };
+        let text = match &self.search_text {
+            Some(text) => text.clone(),
+            None => self.search_text.insert(Arc::new(self.view_lines())).clone(),
+        };
+        let (tx, rx) = oneshot::channel();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(search::find(&pattern, text.iter().map(String::as_str)));
+        });
+        self.finding = Some((rx, jump));
+    }
+
+    /// Picks up the matches once they have been found in the background.
+    fn poll_search(&mut self) {
+        let Some((finding, jump)) = self.finding.as_mut() else {
+            return;
+        };
+        if let Ok(matches) = finding.try_recv() {
+            let jump = *jump;
+            self.finding = None;
+            let top = self.top_line();
+            self.search.finish(matches, top);
+            if jump {
+                self.jump_to(self.search.current());
+            }
+        }
+    }
+
+    /// Brings a match into view, moving the cursor onto it in the tree view.
+    fn jump_to(&mut self, found: Option<Match>) {
+        let Some(found) = found else {
+            return;
+        };
+        if self.selected_tab == ResponseTab::Body && self.view == BodyView::Tree {
+            if let Some(tree) = self.tree_mut() {
+                tree.set_cursor(found.line);
+                return;
+            }
+        }
+        let height = self.body_height();
+        let max = self.line_count().saturating_sub(height);
+        let offset = self.offset_mut();
+        if found.line < *offset || found.line >= *offset + height {
+            *offset = found.line.saturating_sub(height / 2).min(max);
+        }
+    }
+
+    fn search_step(&mut self, forward: bool) {
+        // The lines changed, so the first match from the top is as far as it can go for now.
+        if self.search.is_stale() {
+            self.refresh_search(true);
+            return;
+        }
+        let found = if forward {
+            self.search.next()
+        } else {
+            self.search.previous()
+        };
+        self.jump_to(found);
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Enter => {}
+            KeyCode::Esc => {
+                self.search.clear();
+                self.finding = None;
+                self.search_input = TextArea::default();
+            }
+            // Modified keys are left to the keybindings, e.g. the case and regex toggles.
+            _ if key.modifiers.contains(KeyModifiers::ALT) => return None,
+            _ => {
+                if self.search_input.input(key) {
+                    self.search.set_query(self.search_input.lines().join(""));
+                    self.refresh_search(true);
+                }
+                return None;
+            }
+        }
+        self.searching = false;
+        Some(Action::SwitchMode(Mode::Response))
+    }
+
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        let line = match &self.state {
+            State::Idle => Line::raw("No response yet").dark_gray(),
+            State::Pending => {
+                let mut line = match &self.progress {
+                    Some(progress) => progress_line(progress),
+                    None => Line::raw("Sending request…").yellow(),
+                };
+                line.push_span("  <Esc> to cancel".dark_gray());
+                line
+            }
+            State::Failed(err) => Line::raw(err.clone()).red(),
+            State::Received(response) => {
+                let status_color = match response.status {
+                    200..=299 => Color::Green,
+                    300..=399 => Color::Cyan,
+                    400..=499 => Color::Yellow,
+                    _ => Color::Red,
+                };
+                let mut line = Line::from(vec![
+                    format!("{} {}", response.status, response.reason)
+                        .fg(status_color)
+                        .bold(),
+                    format!(
+                        "  {}  {} ms  {}",
+                        response.version,
+                        response.elapsed.as_millis(),
+                        format_size(response.size() as usize)
+                    )
+                    .dark_gray(),
+                ]);
+                if response.spilled.is_some() {
+                    let shown = format_size(response.body.len());
+                    line.push_span(format!("  showing the first {shown}").yellow());
+                }
+                match &response.decoding {
+                    Some(decoding) if decoding.error.is_some() => line.push_span(
+                        format!("  {}", decoding.error.as_deref().unwrap_or_default()).red(),
+                    ),
+                    Some(decoding) => line.push_span(
+                        format!(
+                            "  decoded from {} ({})",
+                            format_size(decoding.encoded.len()),
+                            decoding.encodings.join(", ")
+                        )
+                        .dark_gray(),
+                    ),
+                    None => {}
+                }
+                match self.chain.len() {
+                    0 | 1 => {}
+                    len if self.hop + 1 < len => {
+                        line.push_span(format!("  hop {} of {len}", self.hop + 1).cyan())
+                    }
+                    2 => line.push_span("  after 1 redirect".cyan()),
+                    len => line.push_span(format!("  after {} redirects", len - 1).cyan()),
+                }
+                if response.attempts > 1 {
+                    line.push_span(format!("  {} attempts", response.attempts).yellow());
+                }
+                line
+            }
+        };
+        line.render(area, buf);
+    }
+
+    fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
+        let titles = ResponseTab::iter().map(ResponseTab::title);
+        let highlight_style = (Color::default(), self.selected_tab.palette().c700);
+        let selected_tab_idx = self.selected_tab as usize;
+
+        Tabs::new(titles)
+            .highlight_style(highlight_style)
+            .select(selected_tab_idx)
+            .padding("", "")
+            .divider(" ")
+            .render(area, buf);
+    }
+
+    fn render_footer(&mut self, area: Rect, buf: &mut Buffer) {
+        if self.searching || self.search.is_active() {
+            self.render_search(area, buf);
+            return;
+        }
+        if let Some(notice) = &self.notice {
+            notice.clone().centered().render(area, buf);
+            return;
+        }
+        let mut hint: String = match (self.selected_tab, self.tree()) {
+            _ if self.editing_filter => {
+                "<Enter> apply  <Ctrl-s> save for this URL  <Ctrl-r> capture as variable".into()
+            }
+            (ResponseTab::Body, _) if self.view == BodyView::Hex => {
+                let len = self.document.as_ref().map_or(0, |d| d.bytes.len());
+                let offset = self.offset() * hex::BYTES_PER_LINE;
+                format!("{offset:08x} / {len:08x}  <PgUp/PgDn> page  <Ctrl-s> save body")
+            }
+            (ResponseTab::Body, Some(tree)) if self.view == BodyView::Tree => tree.focused_path(),
+            (ResponseTab::Body, Some(_)) => {
+                "<Tab> change tab  <v> change view  <o> override type  <f> filter".into()
+            }
+            (ResponseTab::Body, None) if self.view == BodyView::Table => {
+                "<Tab> change tab  <v> change view  <o> override type  <←/→> columns".into()
+            }
+            (ResponseTab::Body, None) if self.document.is_some() => {
+                "<Tab> change tab  <v> change view  <o> override type".into()
+            }
+            (ResponseTab::Redirects, _) if !self.chain.is_empty() => {
+                "<Tab> change tab  <↑/↓> inspect a response".into()
+            }
+            _ => "<Tab> change tab".into(),
+        };
+        let compressed = matches!(&self.state, State::Received(r) if r.decoding.is_some());
+        if compressed && self.selected_tab == ResponseTab::Body && !self.editing_filter {
+            hint.push_str(match self.show_encoded {
+                true => "  <z> decoded",
+                false => "  <z> as it arrived",
+            });
+        }
+        let text = self.document.as_ref().is_some_and(|d| d.charset.is_some());
+        if text && self.selected_tab == ResponseTab::Body && !self.editing_filter {
+            hint.push_str("  <E> encoding");
+        }
+        Line::raw(hint).centered().render(area, buf);
+    }
+
+    fn render_search(&mut self, area: Rect, buf: &mut Buffer) {
+        let flag = |on: bool, label: &'static str| {
+            if on {
+                Span::styled(label, Style::default().fg(Color::Yellow).bold())
+            } else {
+                Span::styled(label, Style::default().fg(Color::DarkGray))
+            }
+        };
+        let status = match self.search.error() {
+            Some(err) => Span::styled(format!(" {err} "), Style::default().fg(Color::Red)),
+            None => Span::raw(format!(" {} ", self.search.counter())),
+        };
+        let status = Line::from(vec![
+            status,
+            flag(self.search.case_sensitive(), "Aa"),
+            Span::raw(" "),
+            flag(self.search.regex(), ".*"),
+        ]);
+        let [input_area, status_area] = Layout::horizontal([
+            Constraint::Min(0),
+            Constraint::Length(status.width() as u16),
+        ])
+        .areas(area);
+
+        if self.searching {
+            let [prompt_area, input_area] =
+                Layout::horizontal([Constraint::Length(1), Constraint::Min(0)]).areas(input_area);
+            Span::raw("/").render(prompt_area, buf);
+            self.search_input.set_cursor_line_style(Style::default());
+            self.search_input.render(input_area, buf);
+        } else {
+            Line::raw(format!("/{}", self.search.query())).render(input_area, buf);
+        }
+        status.render(status_area, buf);
+    }
+
+    fn block(&self, title: impl Into<Line<'static>>) -> Block<'static> {
+        let border_style = if self.focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered()
+            .border_style(border_style)
+            .title(title)
+            .padding(Padding::horizontal(1))
+    }
+
+    fn render_headers(&mut self, area: Rect, buf: &mut Buffer) {
+        let title = match &self.state {
+            State::Received(response) => match &response.http2 {
+                Some(http2) => match http2.stream_id {
+                    Some(id) => Line::raw(format!(" HTTP/2 stream {id} ")),
+                    None => Line::raw(" HTTP/2 stream unavailable "),
+                },
+                None => Line::default(),
+            },
+            _ => Line::default(),
+        };
+        let block = self.block(title);
+        self.body_area = block.inner(area);
+
+        let State::Received(response) = &self.state else {
+            block.render(area, buf);
+            return;
+        };
+        let lines: Vec<Line> = header_rows(response)
+            .enumerate()
+            .skip(self.headers_offset)
+            .take(self.body_area.height as usize)
+            .map(|(n, (name, value))| {
+                // Pseudo-headers are set apart, as they come from the frame rather than the
+                // header list.
+                let style = match name.starts_with(':') {
+                    true => self.styles.key.italic(),
+                    false => self.styles.key,
+                };
+                let line = Line::from(vec![
+                    Span::styled(format!("{name}: "), style),
+                    Span::raw(value.clone()),
+                ]);
+                self.highlight(line, n)
+            })
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn render_raw(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = self.block("");
+        self.body_area = block.inner(area);
+        let State::Received(_) = &self.state else {
+            block.render(area, buf);
+            return;
+        };
+        let width = self.body_area.width as usize;
+        let lines: Vec<Line> = self
+            .raw_lines
+            .iter()
+            .enumerate()
+            .skip(self.raw_offset)
+            .take(self.body_area.height as usize)
+            .map(|(n, line)| self.highlight(clip(line, width), n))
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn timing_lines(&self) -> Vec<Line<'static>> {
+        let State::Received(response) = &self.state else {
+            return vec![];
+        };
+        let history = self.shared.history.borrow();
+        // Skip the entry recorded for this response itself.
+        let runs = history.runs(&response.method, &response.url).skip(1);
+        timing::lines(response, runs, self.body_area.width as usize)
+    }
+
+    fn render_timing(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = self.block("");
+        self.body_area = block.inner(area);
+        let lines: Vec<Line> = self
+            .timing_lines()
+            .into_iter()
+            .enumerate()
+            .skip(self.timing_offset)
+            .take(self.body_area.height as usize)
+            .map(|(n, line)| self.highlight(line, n))
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn render_redirects(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = self.block("");
+        self.body_area = block.inner(area);
+        let lines: Vec<Line> = redirects::lines(&self.chain, self.hop)
+            .into_iter()
+            .enumerate()
+            .skip(self.redirects_offset)
+            .take(self.body_area.height as usize)
+            .map(|(n, line)| self.highlight(line, n))
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn render_body(&mut self, area: Rect, buf: &mut Buffer) {
+        self.filter_area = Rect::default();
+        let area = if self.has_json() {
+            let [body_area, filter_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+            self.render_filter(filter_area, buf);
+            body_area
+        } else {
+            area
+        };
+
+        let title = match &self.document {
+            Some(document) => {
+                let mut title = vec![Span::raw(format!(" {} · {} ", self.view, document.format))];
+                if let Some(file_type) = document.file_type {
+                    title.push(Span::raw(format!("({}) ", file_type.name)));
+                }
+                if self.show_encoded {
+                    title.push(Span::styled(
+                        "(as it arrived) ",
+                        Style::default().fg(Color::Yellow),
+                    ));
+                } else if self.format_override.is_some() {
+                    title.push(Span::styled(
+                        "(override) ",
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                if let Some(charset) = document.charset.filter(Charset::is_notable) {
+                    title.push(Span::raw(format!("· {} ", charset.encoding.name())));
+                    if charset.source == Source::Override {
+                        title.push(Span::styled(
+                            "(override) ",
+                            Style::default().fg(Color::Yellow),
+                        ));
+                    }
+                    if charset.lossy {
+                        title.push(Span::styled(
+                            "(lossy: some bytes aren't valid in this encoding) ",
+                            Style::default().fg(Color::Yellow),
+                        ));
+                    }
+                }
+                if let Some(err) = &document.error {
+                    title.push(Span::styled(
+                        format!("{err} "),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                Line::from(title)
+            }
+            None if !self.partial.is_empty() => Line::raw(" Received so far "),
+            None => Line::default(),
+        };
+        let block = self.block(title);
+        self.body_area = block.inner(area);
+        let height = self.body_height();
+
+        if let (BodyView::Tree, Some(cursor)) = (self.view, self.tree().map(JsonTree::cursor)) {
+            let offset = self.offset_mut();
+            if cursor < *offset {
+                *offset = cursor;
+            } else if cursor >= *offset + height {
+                *offset = cursor + 1 - height;
+            }
+        }
+        if self.view == BodyView::Table && !self.is_pending() {
+            self.render_table(block, area, buf);
+            return;
+        }
+
+        let offset = self.offset();
+        let width = self.body_area.width as usize;
+        let lines: Vec<Line> = match (self.view, self.tree()) {
+            _ if self.is_pending() => {
+                let end = self.partial_lines.len().min(offset + height);
+                (offset..end).map(|n| self.partial_line(n, width)).collect()
+            }
+            _ if self.document.is_none() && self.loading.is_some() => {
+                vec![Line::raw("Formatting…").dark_gray()]
+            }
+            (BodyView::Tree, Some(tree)) => {
+                let cursor = tree.cursor();
+                let end = tree.visible_len().min(offset + height);
+                (offset..end)
+                    .map(|n| {
+                        let line = self.highlight(self.body_line(n, width), n);
+                        if n == cursor && self.focused {
+                            line.patch_style(Modifier::REVERSED)
+                        } else {
+                            line
+                        }
+                    })
+                    .collect()
+            }
+            _ => {
+                let end = self.line_count().min(offset + height);
+                (offset..end)
+                    .map(|n| self.highlight(self.body_line(n, width), n))
+                    .collect()
+            }
+        };
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    /// Draws a CSV body as a table, scrolled horizontally by whole columns.
+    fn render_table(&self, block: Block<'static>, area: Rect, buf: &mut Buffer) {
+        let Some(table) = self.document.as_ref().and_then(|d| d.table.as_ref()) else {
+            block.render(area, buf);
+            return;
+        };
+        let offset = self.offset();
+        let current = self.search.current().map(|found| found.line);
+        let cells = |row: &[String]| {
+            row.iter()
+                .skip(self.table_column)
+                .map(|cell| Cell::from(cell.clone()))
+                .collect::<Vec<_>>()
+        };
+        let rows = table
+            .rows
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(self.body_height())
+            .map(|(n, row)| {
+                let style = match current {
+                    Some(line) if line == n && self.search.is_active() => self.current_match_style,
+                    _ => Style::default(),
+                };
+                Row::new(cells(row)).style(style)
+            });
+        let widths = table
+            .widths
+            .iter()
+            .skip(self.table_column)
+            .map(|&width| Constraint::Length(width));
+
+        let table = Table::new(rows, widths)
+            .header(Row::new(cells(&table.header)).style(self.styles.key.bold()))
+            .column_spacing(2)
+            .block(block);
+        Widget::render(table, area, buf);
+    }
+
+    fn render_save_prompt(&mut self, area: Rect, buf: &mut Buffer) {
+        if let Some(input) = self.save_input.as_mut() {
+            render_prompt(
+                input,
+                " Save body to ",
+                " <Enter> save  <Esc> cancel ",
+                area,
+                buf,
+            );
+        }
+    }
+
+    fn render_capture_prompt(&mut self, area: Rect, buf: &mut Buffer) {
+        if let Some(input) = self.capture_input.as_mut() {
+            input.set_placeholder_text("e.g. token, used in requests as {{token}}");
+            render_prompt(
+                input,
+                " Capture first match as ",
+                " <Enter> capture  <Esc> cancel ",
+                area,
+                buf,
+            );
+        }
+    }
+
+    fn scroll_columns(&mut self, delta: isize) {
+        let columns = self
+            .document
+            .as_ref()
+            .and_then(|d| d.table.as_ref())
+            .map_or(0, |table| table.widths.len());
+        self.table_column = self
+            .table_column
+            .saturating_add_signed(delta)
+            .min(columns.saturating_sub(1));
+    }
+
+    fn highlight(&self, line: Line<'static>, n: usize) -> Line<'static> {
+        if self.search.is_active() {
+            self.search
+                .highlight(line, n, self.match_style, self.current_match_style)
+        } else {
+            line
+        }
+    }
+
+    fn render_filter(&mut self, area: Rect, buf: &mut Buffer) {
+        self.filter_area = area;
+        let saved = match &self.state {
+            State::Received(response) => self
+                .shared
+                .saved_filters
+                .borrow()
+                .get(&response.url)
+                .is_some_and(|saved| saved == self.filter_expression()),
+            _ => false,
+        };
+        let (title, title_style) = match &self.filter_error {
+            Some(err) => (format!(" {err} "), Style::default().fg(Color::Red)),
+            None if saved => (" Filter (saved) ".into(), Style::default()),
+            None => (" Filter ".into(), Style::default()),
+        };
+        let border_style = if self.focused && self.editing_filter {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+
+        self.filter_input
+            .set_placeholder_text("JSONPath or jq path, e.g. $.items[*].id or .items[].id");
+        self.filter_input.set_cursor_line_style(Style::default());
+        self.filter_input.set_block(
+            Block::bordered()
+                .border_style(border_style)
+                .title(Span::styled(title, title_style)),
+        );
+        self.filter_input.render(area, buf);
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc => {
+                self.editing_filter = false;
+                return Some(Action::SwitchMode(Mode::Response));
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.save_filter();
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return self.start_capture();
+            }
+            _ => {
+                if self.filter_input.input(key) {
+                    self.apply_filter();
+                }
+            }
+        }
+        None
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) -> bool {
+        let Some(tree) = self.tree_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Enter | KeyCode::Char(' ') => tree.toggle(),
+            KeyCode::Left | KeyCode::Char('h') => tree.collapse(),
+            KeyCode::Right | KeyCode::Char('l') => tree.expand(),
+            KeyCode::Char('e') => tree.expand_all(),
+            KeyCode::Char('c') => tree.collapse_all(),
+            _ => return false,
+        }
+        self.invalidate_search();
+        true
+    }
+
+    fn handle_click(&mut self, row: u16) {
+        if row < self.body_area.y || row >= self.body_area.bottom() {
+            return;
+        }
+        let n = self.offset() + (row - self.body_area.y) as usize;
+        if self.selected_tab == ResponseTab::Redirects {
+            self.show_hop(n);
+            return;
+        }
+        if let Some(tree) = self.tree_mut() {
+            if n >= tree.visible_len() {
+                return;
+            }
+            if tree.cursor() == n {
+                tree.toggle();
+                self.invalidate_search();
+            } else {
+                tree.set_cursor(n);
+            }
+        }
+    }
+}
+
+impl Widget for &mut Response {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let vertical = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]);
+        let [header_area, status_area, inner_area, footer_area] = vertical.areas(area);
+
+        if self.search.is_stale() {
+            self.refresh_search(false);
+        }
+        self.render_tabs(header_area, buf);
+        self.render_status(status_area, buf);
+        match self.selected_tab {
+            ResponseTab::Body => self.render_body(inner_area, buf),
+            ResponseTab::Headers => self.render_headers(inner_area, buf),
+            ResponseTab::Raw => self.render_raw(inner_area, buf),
+            ResponseTab::Timing => self.render_timing(inner_area, buf),
+            ResponseTab::Redirects => self.render_redirects(inner_area, buf),
+        }
+        self.render_footer(footer_area, buf);
+        self.render_save_prompt(inner_area, buf);
+        self.render_capture_prompt(inner_area, buf);
+    }
+}
+
+impl Component for Response {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        let styles = config.styles.get(&Mode::Response);
+        let style = |name: &str| {
+            styles
+                .and_then(|styles| styles.get(name))
+                .copied()
+                .unwrap_or_default()
+        };
+        self.styles = SyntaxStyles {
+            key: style("json_key"),
+            string: style("json_string"),
+            number: style("json_number"),
+            literal: style("json_literal"),
+            punctuation: style("json_punctuation"),
+            hint: style("json_hint"),
+        };
+        self.match_style = style("search_match");
+        self.current_match_style = style("search_current");
+        self.config = config;
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        self.notice = None;
+        if self.save_input.is_some() {
+            return Ok(self.handle_save_key(key));
+        }
+        if self.capture_input.is_some() {
+            return Ok(self.handle_capture_key(key));
+        }
+        if self.editing_filter {
+            return Ok(self.handle_filter_key(key));
+        }
+        if self.searching {
+            return Ok(self.handle_search_key(key));
+        }
+        match key.code {
+            KeyCode::Tab => {
+                self.selected_tab = self.selected_tab.next();
+                self.invalidate_search();
+            }
+            KeyCode::BackTab => {
+                self.selected_tab = self.selected_tab.previous();
+                self.invalidate_search();
+            }
+            KeyCode::Char('v') => {
+                if let Some(document) = &self.document {
+                    let views = self.views(document);
+                    let current = views.iter().position(|view| *view == self.view);
+                    self.view = views[current.map_or(0, |i| (i + 1) % views.len())];
+                    self.invalidate_search();
+                }
+            }
+            KeyCode::Char('o') if self.selected_tab == ResponseTab::Body => self.cycle_format(),
+            KeyCode::Char('z') if self.selected_tab == ResponseTab::Body => self.toggle_encoded(),
+            KeyCode::Char('E') if self.selected_tab == ResponseTab::Body => self.cycle_encoding(),
+            KeyCode::Char('f') if self.selected_tab == ResponseTab::Body && self.has_json() => {
+                self.editing_filter = true;
+                return Ok(Some(Action::SwitchMode(Mode::Filter)));
+            }
+            KeyCode::Up | KeyCode::Char('k') if self.selected_tab == ResponseTab::Redirects => {
+                self.show_hop(self.hop.saturating_sub(1))
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected_tab == ResponseTab::Redirects => {
+                self.show_hop(self.hop + 1)
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-self.page()),
+            KeyCode::PageDown => self.scroll(self.page()),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll(-(self.line_count() as isize)),
+            KeyCode::End | KeyCode::Char('G') => self.scroll(self.line_count() as isize),
+            KeyCode::Left | KeyCode::Char('h') if self.view == BodyView::Table => {
+                self.scroll_columns(-1)
+            }
+            KeyCode::Right | KeyCode::Char('l') if self.view == BodyView::Table => {
+                self.scroll_columns(1)
+            }
+            _ if self.selected_tab == ResponseTab::Body && self.view == BodyView::Tree => {
+                self.handle_tree_key(key);
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        let position = Position {
+            x: mouse.column,
+            y: mouse.row,
+        };
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll(-(SCROLL_STEP as isize)),
+            MouseEventKind::ScrollDown => self.scroll(SCROLL_STEP as isize),
+            MouseEventKind::Down(MouseButton::Left) if self.filter_area.contains(position) => {
+                self.editing_filter = true;
+                return Ok(Some(Action::SwitchMode(Mode::Filter)));
+            }
+            MouseEventKind::Down(MouseButton::Left)
+                if self.selected_tab == ResponseTab::Redirects =>
+            {
+                self.handle_click(mouse.row);
+            }
+            MouseEventKind::Down(MouseButton::Left)
+                if self.selected_tab == ResponseTab::Body && self.view == BodyView::Tree =>
+            {
+                self.editing_filter = false;
+                self.handle_click(mouse.row);
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Render => {
+                self.poll_document();
+                self.poll_filter();
+                self.poll_saving();
+                self.poll_search();
+            }
+            Action::SaveBody => return Ok(self.start_save()),
+            Action::Search => {
+                self.searching = true;
+                self.search_input = TextArea::from([self.search.query()]);
+                self.search_input.move_cursor(CursorMove::End);
+                return Ok(Some(Action::SwitchMode(Mode::Search)));
+            }
+            Action::SearchNext => self.search_step(true),
+            Action::SearchPrevious => self.search_step(false),
+            Action::ToggleSearchCase => {
+                self.search.toggle_case();
+                self.refresh_search(false);
+            }
+            Action::ToggleSearchRegex => {
+                self.search.toggle_regex();
+                self.refresh_search(false);
+            }
+            Action::ResponseReceived(_, response) => self.set_response(response),
+            Action::ResponseProgress(_, progress) if self.is_pending() => {
+                self.add_partial(&progress.data);
+                self.progress = Some(progress)
+            }
+            Action::RequestFailed(_, err) => {
+                self.state = State::Failed(err);
+                self.document = None;
+                self.loading = None;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        frame.render_widget(self, area);
+
+        Ok(())
+    }
+}
+
+/// Draws a one-line prompt over the middle of the pane.
+fn render_prompt(input: &mut TextArea, title: &str, keys: &str, area: Rect, buf: &mut Buffer) {
+    let [_, area, _] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(3),
+        Constraint::Min(0),
+    ])
+    .areas(area);
+    let area = area.inner(Margin::new(2, 0));
+
+    input.set_cursor_line_style(Style::default());
+    input.set_block(
+        Block::bordered()
+            .border_style(Style::default().fg(Color::White))
+            .title(title.to_string())
+            .title_bottom(Line::raw(keys.to_string()).right_aligned()),
+    );
+    Clear.render(area, buf);
+    input.render(area, buf);
+}
+
+/// The rows of the Headers tab: any HTTP/2 pseudo-headers, then the headers proper.
+fn header_rows(response: &HttpResponse) -> impl Iterator<Item = &(String, String)> {
+    response
+        .http2
+        .iter()
+        .flat_map(|http2| &http2.pseudo_headers)
+        .chain(&response.headers)
+}
+
+/// The part of a line that fits in `width` characters, copying no more of it than that.
+fn clip(line: &Line<'static>, width: usize) -> Line<'static> {
+    let mut left = width;
+    let mut spans = Vec::new();
+    for span in &line.spans {
+        if left == 0 {
+            break;
+        }
+        let content: String = span.content.chars().take(left).collect();
+        left -= content.chars().count();
+        spans.push(Span::styled(content, span.style));
+    }
+    Line::from(spans).style(line.style)
+}
+
+/// Formats a byte count for display, e.g. `1.5 KiB`.
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
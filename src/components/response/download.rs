@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use directories::BaseDirs;
+use percent_encoding::percent_decode_str;
+use ratatui::{style::Stylize, text::Line};
+
+use super::format_size;
+use super::viewer::magic::FileType;
+use crate::executor::{body::Progress, HttpResponse};
+
+/// Width of the bar showing how much of a body has arrived.
+const PROGRESS_WIDTH: usize = 20;
+
+/// Directory inside the data directory that bodies are saved to by default.
+const DOWNLOADS_DIR: &str = "downloads";
+
+/// Extensions for media types whose bodies have no recognisable magic bytes.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("application/json", "json"),
+    ("application/xml", "xml"),
+    ("text/xml", "xml"),
+    ("text/html", "html"),
+    ("text/plain", "txt"),
+    ("text/csv", "csv"),
+    ("application/yaml", "yaml"),
+    ("application/x-yaml", "yaml"),
+    ("text/yaml", "yaml"),
+    ("image/svg+xml", "svg"),
+    ("application/msgpack", "msgpack"),
+    ("application/cbor", "cbor"),
+];
+
+/// Suggests where to save a response body: the downloads directory inside the data directory,
+/// under the name from `Content-Disposition` or else the last segment of the URL.
+pub fn suggest_path(
+    data_dir: &Path,
+    response: &HttpResponse,
+    file_type: Option<FileType>,
+) -> PathBuf {
+    data_dir
+        .join(DOWNLOADS_DIR)
+        .join(suggest_filename(response, file_type))
+}
+
+fn suggest_filename(response: &HttpResponse, file_type: Option<FileType>) -> String {
+    if let Some(name) = response
+        .header("content-disposition")
+        .and_then(disposition_filename)
+    {
+        return name;
+    }
+    let name = url::Url::parse(&response.url)
+        .ok()
+        .and_then(|url| {
+            let segment = url.path_segments()?.next_back()?.to_string();
+            sanitize(&percent_decode_str(&segment).decode_utf8_lossy())
+        })
+        .unwrap_or_else(|| "response".into());
+    if Path::new(&name).extension().is_some() {
+        return name;
+    }
+    let media_type = response.media_type();
+    let extension = file_type.map(|t| t.extension).or_else(|| {
+        EXTENSIONS
+            .iter()
+            .find(|(t, _)| Some(*t) == media_type.as_deref())
+            .map(|(_, extension)| *extension)
+    });
+    match extension {
+        Some(extension) => format!("{name}.{extension}"),
+        None => name,
+    }
+}
+
+/// Extracts the file name from a `Content-Disposition` header, preferring the RFC 6266
+/// `filename*` form over the plain one.
+fn disposition_filename(value: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+    for param in value.split(';').skip(1) {
+        let Some((name, value)) = param.split_once('=') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                // charset'language'percent-encoded-name
+                let encoded = value.trim().splitn(3, '\'').nth(2)?;
+                extended = Some(percent_decode_str(encoded).decode_utf8_lossy().into_owned());
+            }
+            "filename" => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .map_or_else(|| value.to_string(), |v| v.replace("\\\"", "\""));
+                plain = Some(value);
+            }
+            _ => {}
+        }
+    }
+    extended.or(plain).as_deref().and_then(sanitize)
+}
+
+/// Keeps only the last path component of a server-supplied name, so that it can't be used to
+/// write outside the chosen directory.
+fn sanitize(name: &str) -> Option<String> {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()?
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let name = name.trim();
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn resolve_path(input: &str) -> PathBuf {
+    let input = input.trim();
+    match (input.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(input),
+    }
+}
+
+/// Writes a body to disk, creating missing parent directories.
+pub async fn save(path: PathBuf, body: Vec<u8>) -> Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&path, body).await?;
+    Ok(path)
+}
+
+/// Saves a body that was too large to keep in memory by copying the file it went to.
+pub async fn copy(path: PathBuf, from: PathBuf) -> Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::copy(from, &path).await?;
+    Ok(path)
+}
+
+/// Describes how far along a body is, with a bar if its size is known, e.g.
+/// `Downloading ██████░░░░ 60%  6.0 MiB of 10.0 MiB  2.0 MiB/s`.
+pub fn progress_line(progress: &Progress) -> Line<'static> {
+    let received = format_size(progress.received as usize);
+    let rate = format_size(progress.rate() as usize);
+    let Some(total) = progress.total.filter(|total| *total > 0) else {
+        return Line::from(vec![
+            "Downloading".yellow(),
+            format!("  {received}  {rate}/s").dark_gray(),
+        ]);
+    };
+    let done = progress.received.min(total) as f64 / total as f64;
+    let filled = (done * PROGRESS_WIDTH as f64).round() as usize;
+    Line::from(vec![
+        "Downloading ".yellow(),
+        "█".repeat(filled).yellow(),
+        "░".repeat(PROGRESS_WIDTH - filled).dark_gray(),
+        format!(" {:.0}%", done * 100.0).yellow(),
+        format!("  {received} of {}  {rate}/s", format_size(total as usize)).dark_gray(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn response(url: &str, headers: &[(&str, &str)]) -> HttpResponse {
+        HttpResponse {
+            url: url.into(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..HttpResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_disposition_filename() {
+        assert_eq!(
+            disposition_filename("attachment; filename=\"report 1.pdf\"").as_deref(),
+            Some("report 1.pdf")
+        );
+        assert_eq!(
+            disposition_filename(
+                "attachment; filename=\"fallback.txt\"; filename*=UTF-8''na%C3%AFve.txt"
+            )
+            .as_deref(),
+            Some("naïve.txt")
+        );
+        assert_eq!(
+            disposition_filename("attachment; filename=../../etc/passwd").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(disposition_filename("inline"), None);
+    }
+
+    #[test]
+    fn test_suggest_filename() {
+        let png = FileType {
+            name: "PNG image",
+            extension: "png",
+        };
+        assert_eq!(
+            suggest_filename(&response("http://x/img/logo", &[]), Some(png)),
+            "logo.png"
+        );
+        assert_eq!(
+            suggest_filename(
+                &response("http://x/users", &[("Content-Type", "application/json")]),
+                None
+            ),
+            "users.json"
+        );
+        assert_eq!(
+            suggest_filename(&response("http://x/", &[]), None),
+            "response"
+        );
+        assert_eq!(
+            suggest_filename(
+                &response(
+                    "http://x/download?id=1",
+                    &[("Content-Disposition", "attachment; filename=data.csv")]
+                ),
+                None
+            ),
+            "data.csv"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_creates_directories() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-test-{}", std::process::id()));
+        let path = save(dir.join("nested/body.bin"), vec![1, 2, 3]).await?;
+        assert_eq!(std::fs::read(&path)?, vec![1, 2, 3]);
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_progress_line() {
+        let progress = Progress {
+            received: 6 * 1024 * 1024,
+            total: Some(10 * 1024 * 1024),
+            elapsed: Duration::from_secs(3),
+            ..Progress::default()
+        };
+        assert_eq!(
+            progress_line(&progress).to_string(),
+            "Downloading ████████████░░░░░░░░ 60%  6.0 MiB of 10.0 MiB  2.0 MiB/s"
+        );
+        let progress = Progress {
+            total: None,
+            ..progress
+        };
+        assert_eq!(
+            progress_line(&progress).to_string(),
+            "Downloading  6.0 MiB  2.0 MiB/s"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use color_eyre::Result;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+const FILTERS_FILE: &str = "filters.json";
+
+/// A compiled filter expression.
+///
+/// Expressions are JSONPath (`$.items[*].name`) or the jq-like shorthand for the same paths
+/// (`.items[].name`).
+#[derive(Debug, Clone)]
+pub struct Filter {
+    path: JsonPath,
+}
+
+/// Filter expressions saved per request URL, persisted in the data directory.
+#[derive(Debug, Default, Clone)]
+pub struct SavedFilters {
+    file: Option<PathBuf>,
+    filters: HashMap<String, String>,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let path = JsonPath::parse(&to_json_path(expression)?).map_err(|err| err.to_string())?;
+        Ok(Self { path })
+    }
+
+    /// Returns an array of the matching nodes, however many there are.
+    pub fn apply(&self, value: &Value) -> Value {
+        Value::Array(self.path.query(value).all().into_iter().cloned().collect())
+    }
+
+    /// Evaluates the filter as a capture expression, returning the first match as text.
+    ///
+    /// Strings are returned without their quotes; anything else is serialized as JSON.
+    pub fn capture(&self, value: &Value) -> Option<String> {
+        match self.path.query(value).first()? {
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+impl SavedFilters {
+    pub fn load(data_dir: PathBuf) -> Self {
+        let file = data_dir.join(FILTERS_FILE);
+        let filters = fs::read_to_string(&file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            file: Some(file),
+            filters,
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Option<&str> {
+        self.filters.get(url).map(String::as_str)
+    }
+
+    /// Saves the filter for a request, or forgets it when the expression is empty.
+    pub fn save(&mut self, url: &str, expression: &str) -> Result<()> {
+        if expression.trim().is_empty() {
+            self.filters.remove(url);
+        } else {
+            self.filters.insert(url.to_string(), expression.to_string());
+        }
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(file, serde_json::to_string_pretty(&self.filters)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites the jq-like shorthand into JSONPath, leaving JSONPath expressions untouched.
+///
+/// The shorthand is read segment by segment: `.name`, `."quoted name"`, `.*`, `[]` and any
+/// bracketed selector, each of which may follow `..` for recursive descent.
+fn to_json_path(expression: &str) -> Result<String, String> {
+    let expression = expression.trim();
+    if expression.starts_with('$') {
+        return Ok(expression.to_string());
+    }
+    if expression == "." {
+        return Ok("$".into());
+    }
+    let mut path = String::from("$");
+    let mut rest = expression;
+    while !rest.is_empty() {
+        let descend = if let Some(after) = rest.strip_prefix("..") {
+            rest = after;
+            true
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+            false
+        } else if rest.starts_with('[') {
+            false
+        } else {
+            return Err(format!("expected '.' or '[' at '{rest}'"));
+        };
+        if descend {
+            path.push_str("..");
+        }
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = closing(after, ']').ok_or("unclosed '['")?;
+            let selector = after[..end].trim();
+            path.push('[');
+            path.push_str(if selector.is_empty() { "*" } else { selector });
+            path.push(']');
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('*') {
+            path.push_str(if descend { "*" } else { ".*" });
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('"') {
+            let end = string_end(after, '"').ok_or("unclosed '\"'")?;
+            path.push_str(&format!("[\"{}\"]", &after[..end]));
+            rest = &after[end + 1..];
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            if name.is_empty() {
+                return Err(format!("expected a name at '{rest}'"));
+            }
+            if name.starts_with(|c: char| c.is_ascii_digit()) {
+                path.push_str(&format!("['{name}']"));
+            } else {
+                if !descend {
+                    path.push('.');
+                }
+                path.push_str(name);
+            }
+            rest = &rest[end..];
+        }
+    }
+    Ok(path)
+}
+
+/// The position of the unquoted `close` in `text`, skipping over quoted strings.
+fn closing(text: &str, close: char) -> Option<usize> {
+    let mut start = 0;
+    while let Some(i) = text[start..].find([close, '"', '\'']) {
+        let i = start + i;
+        let c = text[i..].chars().next()?;
+        if c == close {
+            return Some(i);
+        }
+        start = i + 1 + string_end(&text[i + 1..], c)? + 1;
+    }
+    None
+}
+
+/// The position of the unescaped `quote` ending the string that `text` starts inside of.
+fn string_end(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    text.char_indices().find_map(|(i, c)| {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == quote => return Some(i),
+            _ => {}
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_to_json_path() {
+        assert_eq!(to_json_path("$.a[0]").unwrap(), "$.a[0]");
+        assert_eq!(to_json_path(".").unwrap(), "$");
+        assert_eq!(to_json_path(".items[].name").unwrap(), "$.items[*].name");
+        assert_eq!(to_json_path(".[0].id").unwrap(), "$[0].id");
+        assert_eq!(to_json_path("..[0]").unwrap(), "$..[0]");
+        assert_eq!(to_json_path("..id").unwrap(), "$..id");
+        assert_eq!(to_json_path(".a..*").unwrap(), "$.a..*");
+        assert_eq!(to_json_path(".a[1:3]").unwrap(), "$.a[1:3]");
+        assert_eq!(to_json_path(r#".["a.[]b"]"#).unwrap(), r#"$["a.[]b"]"#);
+        assert_eq!(to_json_path(r#"."a b".c"#).unwrap(), r#"$["a b"].c"#);
+        assert_eq!(to_json_path(r#"."it's""#).unwrap(), r#"$["it's"]"#);
+        assert!(to_json_path(".a[0").is_err());
+        assert!(to_json_path("a").is_err());
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        let value = json!({"a": [{"id": 1}, [2, 3]]});
+        let filter = Filter::parse("..[0]").unwrap();
+        assert_eq!(filter.apply(&value), json!([{"id": 1}, 2]));
+    }
+
+    #[test]
+    fn test_apply() {
+        let value = json!({"items": [{"id": 1}, {"id": 2}]});
+        let filter = Filter::parse(".items[].id").unwrap();
+        assert_eq!(filter.apply(&value), json!([1, 2]));
+        let filter = Filter::parse("$.items[0]").unwrap();
+        assert_eq!(filter.apply(&value), json!([{"id": 1}]));
+        let filter = Filter::parse(".missing").unwrap();
+        assert_eq!(filter.apply(&value), json!([]));
+    }
+
+    #[test]
+    fn test_capture() {
+        let value = json!({"token": "abc", "ttl": 60});
+        let filter = Filter::parse(".token").unwrap();
+        assert_eq!(filter.capture(&value), Some("abc".into()));
+        let filter = Filter::parse(".ttl").unwrap();
+        assert_eq!(filter.capture(&value), Some("60".into()));
+        let filter = Filter::parse(".missing").unwrap();
+        assert_eq!(filter.capture(&value), None);
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        assert!(Filter::parse("$.items[").is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use std::fmt::Write;
+
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+
+use super::viewer::SyntaxStyles;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    Name(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Open {
+        container: Container,
+        len: usize,
+        close: usize,
+    },
+    Close(Container),
+    String(String),
+    Number(String),
+    Literal(&'static str),
+}
+
+/// A single line of the pretty-printed document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub depth: usize,
+    pub parent: Option<usize>,
+    pub key: Option<Key>,
+    pub token: Token,
+    pub comma: bool,
+}
+
+/// A JSON document flattened into pretty-printed lines, with collapsible objects and arrays.
+///
+/// Flattening happens once when the document is loaded so that drawing only has to look at the
+/// lines that are actually on screen, no matter how large the document is.
+#[derive(Debug, Default, Clone)]
+pub struct JsonTree {
+    nodes: Vec<Node>,
+    collapsed: Vec<bool>,
+    visible: Vec<usize>,
+    cursor: usize,
+}
+
+impl Container {
+    fn brackets(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Object => ("{", "}"),
+            Self::Array => ("[", "]"),
+        }
+    }
+}
+
+impl JsonTree {
+    pub fn from_value(value: &Value) -> Self {
+        let mut nodes = Vec::new();
+        flatten(value, None, None, 0, false, &mut nodes);
+        let mut tree = Self {
+            collapsed: vec![false; nodes.len()],
+            nodes,
+            ..Self::default()
+        };
+        tree.refresh();
+        tree
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Number of lines shown with the current collapsed state.
+    pub fn visible_len(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// Node index of the `n`th visible line.
+    pub fn visible_node(&self, n: usize) -> Option<usize> {
+        self.visible.get(n).copied()
+    }
+
+    /// Position of the focused line among the visible lines.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.visible.len().saturating_sub(1));
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        self.set_cursor(self.cursor.saturating_add_signed(delta));
+    }
+
+    /// Toggles the object or array on the focused line.
+    pub fn toggle(&mut self) {
+        let node = self.focused_container();
+        if let Some(node) = node {
+            self.set_collapsed(node, !self.collapsed[node]);
+        }
+    }
+
+    /// Collapses the focused container, or the one enclosing the focused line.
+    pub fn collapse(&mut self) {
+        let Some(focused) = self.visible_node(self.cursor) else {
+            return;
+        };
+        let target = match self.nodes[focused].token {
+            Token::Open { .. } if !self.collapsed[focused] => Some(focused),
+            _ => self.nodes[focused].parent,
+        };
+        if let Some(target) = target {
+            self.set_collapsed(target, true);
+        }
+    }
+
+    /// Expands the focused container.
+    pub fn expand(&mut self) {
+        if let Some(node) = self.focused_container() {
+            self.set_collapsed(node, false);
+        }
+    }
+
+    pub fn expand_all(&mut self) {
+        let focused = self.visible_node(self.cursor);
+        self.collapsed.iter_mut().for_each(|c| *c = false);
+        self.refresh_keeping(focused);
+    }
+
+    /// Collapses every container below the root.
+    pub fn collapse_all(&mut self) {
+        for (node, collapsed) in self.nodes.iter().zip(self.collapsed.iter_mut()) {
+            *collapsed = node.depth > 0 && matches!(node.token, Token::Open { .. });
+        }
+        self.refresh_keeping(Some(0));
+    }
+
+    /// JSONPath-style location of the focused line, e.g. `$.items[2].name`.
+    pub fn focused_path(&self) -> String {
+        let Some(mut node) = self.visible_node(self.cursor) else {
+            return "$".into();
+        };
+        // A closing line belongs to the container that opened it.
+        if let Token::Close(_) = self.nodes[node].token {
+            node = self.nodes[node].parent.unwrap_or_default();
+        }
+        self.path(node)
+    }
+
+    /// JSONPath-style location of a node.
+    pub fn path(&self, node: usize) -> String {
+        let mut keys = Vec::new();
+        let mut current = Some(node);
+        while let Some(index) = current {
+            if let Some(key) = &self.nodes[index].key {
+                keys.push(key);
+            }
+            current = self.nodes[index].parent;
+        }
+
+        let mut path = String::from("$");
+        for key in keys.into_iter().rev() {
+            match key {
+                Key::Index(i) => {
+                    let _ = write!(path, "[{i}]");
+                }
+                Key::Name(name) if is_identifier(name) => {
+                    let _ = write!(path, ".{name}");
+                }
+                Key::Name(name) => {
+                    let _ = write!(path, "[{}]", Value::String(name.clone()));
+                }
+            }
+        }
+        path
+    }
+
+    /// Renders a node as a highlighted line.
+    ///
+    /// With `tree` set, containers get an expand/collapse marker and collapsed containers are
+    /// summarised on a single line.
+    pub fn line(&self, node: usize, styles: &SyntaxStyles, tree: bool) -> Line<'static> {
+        let n = &self.nodes[node];
+        let mut spans = Vec::with_capacity(6);
+        spans.push(Span::raw("  ".repeat(n.depth)));
+
+        let collapsed = tree && self.collapsed[node];
+        if tree {
+            let marker = match n.token {
+                Token::Open { .. } if collapsed => "▸ ",
+                Token::Open { .. } => "▾ ",
+                _ => "  ",
+            };
+            spans.push(Span::styled(marker, styles.punctuation));
+        }
+
+        if let Some(Key::Name(name)) = &n.key {
+            spans.push(Span::styled(
+                Value::String(name.clone()).to_string(),
+                styles.key,
+            ));
+            spans.push(Span::styled(": ", styles.punctuation));
+        }
+
+        let mut comma = n.comma;
+        match &n.token {
+            Token::Open {
+                container,
+                len,
+                close,
+            } => {
+                let (open, end) = container.brackets();
+                if collapsed {
+                    comma = self.nodes[*close].comma;
+                    spans.push(Span::styled(format!("{open}…{end}"), styles.punctuation));
+                } else {
+                    spans.push(Span::styled(open, styles.punctuation));
+                }
+                let hint = match container {
+                    Container::Object => plural(*len, "key"),
+                    Container::Array => plural(*len, "item"),
+                };
+                if tree {
+                    spans.push(Span::styled(format!(" {hint}"), styles.hint));
+                }
+            }
+            Token::Close(container) => {
+                spans.push(Span::styled(container.brackets().1, styles.punctuation));
+            }
+            Token::String(s) => spans.push(Span::styled(
+                Value::String(s.clone()).to_string(),
+                styles.string,
+            )),
+            Token::Number(n) => spans.push(Span::styled(n.clone(), styles.number)),
+            Token::Literal(l) => spans.push(Span::styled(*l, styles.literal)),
+        }
+        if comma {
+            spans.push(Span::styled(",", styles.punctuation));
+        }
+
+        Line::from(spans)
+    }
+
+    fn focused_container(&self) -> Option<usize> {
+        let focused = self.visible_node(self.cursor)?;
+        match self.nodes[focused].token {
+            Token::Open { .. } => Some(focused),
+            _ => None,
+        }
+    }
+
+    fn set_collapsed(&mut self, node: usize, collapsed: bool) {
+        self.collapsed[node] = collapsed;
+        self.refresh_keeping(Some(node));
+    }
+
+    /// Recomputes the visible lines, keeping the cursor on `focus` if it is still visible.
+    fn refresh_keeping(&mut self, focus: Option<usize>) {
+        self.refresh();
+        if let Some(focus) = focus {
+            if let Ok(position) = self.visible.binary_search(&focus) {
+                self.cursor = position;
+                return;
+            }
+        }
+        self.set_cursor(self.cursor);
+    }
+
+    fn refresh(&mut self) {
+        self.visible.clear();
+        let mut node = 0;
+        while node < self.nodes.len() {
+            self.visible.push(node);
+            node = match self.nodes[node].token {
+                Token::Open { close, .. } if self.collapsed[node] => close + 1,
+                _ => node + 1,
+            };
+        }
+    }
+}
+
+fn flatten(
+    value: &Value,
+    key: Option<Key>,
+    parent: Option<usize>,
+    depth: usize,
+    comma: bool,
+    nodes: &mut Vec<Node>,
+) {
+    let index = nodes.len();
+    let (container, len) = match value {
+        Value::Object(map) => (Container::Object, map.len()),
+        Value::Array(items) => (Container::Array, items.len()),
+        _ => {
+            let token = match value {
+                Value::String(s) => Token::String(s.clone()),
+                Value::Number(n) => Token::Number(n.to_string()),
+                Value::Bool(true) => Token::Literal("true"),
+                Value::Bool(false) => Token::Literal("false"),
+                _ => Token::Literal("null"),
+            };
+            nodes.push(Node {
+                depth,
+                parent,
+                key,
+                token,
+                comma,
+            });
+            return;
+        }
+    };
+
+    nodes.push(Node {
+        depth,
+        parent,
+        key,
+        token: Token::Open {
+            container,
+            len,
+            close: 0,
+        },
+        comma: false,
+    });
+    match value {
+        Value::Object(map) => {
+            for (i, (name, child)) in map.iter().enumerate() {
+                let key = Some(Key::Name(name.clone()));
+                flatten(child, key, Some(index), depth + 1, i + 1 < len, nodes);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                let key = Some(Key::Index(i));
+                flatten(child, key, Some(index), depth + 1, i + 1 < len, nodes);
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    let close = nodes.len();
+    nodes.push(Node {
+        depth,
+        parent: Some(index),
+        key: None,
+        token: Token::Close(container),
+        comma,
+    });
+    if let Token::Open { close: c, .. } = &mut nodes[index].token {
+        *c = close;
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("{n} {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn text(tree: &JsonTree, node: usize, as_tree: bool) -> String {
+        tree.line(node, &SyntaxStyles::default(), as_tree)
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_pretty_lines() {
+        let tree = JsonTree::from_value(&json!({"a": [1, true], "b": null}));
+        let lines: Vec<String> = (0..tree.nodes().len())
+            .map(|node| text(&tree, node, false))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "{",
+                "  \"a\": [",
+                "    1,",
+                "    true",
+                "  ],",
+                "  \"b\": null",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_and_expand() {
+        let mut tree = JsonTree::from_value(&json!({"a": [1, 2, 3], "b": {}}));
+        assert_eq!(tree.visible_len(), 9);
+
+        tree.set_cursor(1);
+        tree.toggle();
+        assert_eq!(tree.visible_len(), 5);
+        assert_eq!(text(&tree, 1, true), "  ▸ \"a\": […] 3 items,");
+
+        tree.expand();
+        assert_eq!(tree.visible_len(), 9);
+
+        tree.collapse_all();
+        assert_eq!(tree.visible_len(), 4);
+        tree.expand_all();
+        assert_eq!(tree.visible_len(), 9);
+    }
+
+    #[test]
+    fn test_collapse_moves_to_parent() {
+        let mut tree = JsonTree::from_value(&json!({"a": [1, 2]}));
+        tree.set_cursor(3);
+        tree.collapse();
+        assert_eq!(tree.cursor(), 1);
+        assert_eq!(tree.visible_len(), 3);
+    }
+
+    #[test]
+    fn test_focused_path() {
+        let mut tree = JsonTree::from_value(&json!({"items": [{"name": "x"}], "odd key": 1}));
+        tree.set_cursor(3);
+        assert_eq!(tree.focused_path(), "$.items[0].name");
+        tree.set_cursor(4);
+        assert_eq!(tree.focused_path(), "$.items[0]");
+        tree.set_cursor(6);
+        assert_eq!(tree.focused_path(), "$[\"odd key\"]");
+    }
+}
@@ -0,0 +1,131 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+use serde_json::Value;
+
+use super::json::JsonTree;
+use super::viewer::SyntaxStyles;
+use crate::jsonrpc::{Exchange, Outcome};
+
+/// Lays out the JSON-RPC view: each call with the result or error it got back, followed by
+/// responses that match no call.
+pub fn lines(exchanges: &[Exchange], styles: &SyntaxStyles) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    for exchange in exchanges {
+        let id = match &exchange.id {
+            Some(id) => format!("#{id} "),
+            None => String::new(),
+        };
+        let method = match &exchange.method {
+            Some(method) => Span::raw(method.clone()).bold(),
+            None => Span::raw("(no matching call)").dark_gray(),
+        };
+        let mut head = vec![Span::raw(id).dark_gray(), method, Span::raw("  ")];
+        match &exchange.outcome {
+            Outcome::Result(_) => head.push(Span::raw("✓ result").green()),
+            Outcome::Error { code, message, .. } => head.push(
+                Span::raw(format!(" ✗ {code} {message} "))
+                    .style(Style::default().fg(Color::White).bg(Color::Red).bold()),
+            ),
+            Outcome::Missing => head.push(Span::raw("no response").yellow()),
+            Outcome::Notified => head.push(Span::raw("notification").dark_gray()),
+        }
+        lines.push(Line::from(head));
+
+        match &exchange.outcome {
+            Outcome::Result(value) => lines.extend(value_lines(value, styles)),
+            Outcome::Error {
+                code,
+                data: Some(data),
+                ..
+            } => {
+                lines.push(Line::raw(format!("  {}", error_name(*code))).dark_gray());
+                lines.extend(value_lines(data, styles));
+            }
+            Outcome::Error { code, .. } => {
+                lines.push(Line::raw(format!("  {}", error_name(*code))).dark_gray())
+            }
+            Outcome::Missing | Outcome::Notified => {}
+        }
+        lines.push(Line::default());
+    }
+    lines.pop();
+    lines
+}
+
+/// A value pretty-printed below its call.
+fn value_lines(value: &Value, styles: &SyntaxStyles) -> Vec<Line<'static>> {
+    let tree = JsonTree::from_value(value);
+    (0..tree.nodes().len())
+        .map(|node| {
+            let mut line = tree.line(node, styles, false);
+            line.spans.insert(0, Span::raw("  "));
+            line
+        })
+        .collect()
+}
+
+/// What the specification reserves an error code for.
+fn error_name(code: i64) -> &'static str {
+    match code {
+        -32700 => "Parse error",
+        -32600 => "Invalid request",
+        -32601 => "Method not found",
+        -32602 => "Invalid params",
+        -32603 => "Internal error",
+        -32099..=-32000 => "Server error",
+        _ => "Application error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let exchanges = [
+            Exchange {
+                method: Some("sum".into()),
+                id: Some(json!(1)),
+                outcome: Outcome::Result(json!([3])),
+            },
+            Exchange {
+                method: Some("divide".into()),
+                id: Some(json!(2)),
+                outcome: Outcome::Error {
+                    code: -32602,
+                    message: "Division by zero".into(),
+                    data: None,
+                },
+            },
+            Exchange {
+                method: Some("ping".into()),
+                id: None,
+                outcome: Outcome::Notified,
+            },
+        ];
+        let lines: Vec<String> = lines(&exchanges, &SyntaxStyles::default())
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "#1 sum  ✓ result",
+                "  [",
+                "    3",
+                "  ]",
+                "",
+                "#2 divide   ✗ -32602 Division by zero ",
+                "  Invalid params",
+                "",
+                "ping  notification",
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use super::format_size;
+use crate::executor::{RawExchange, MAX_RAW};
+
+/// Lays out the Raw tab: the request as written to the connection, then the response as read
+/// from it, with line endings and bytes that aren't text spelled out.
+pub fn lines(raw: &RawExchange) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if raw.truncated {
+        lines.push(
+            Line::raw(format!(
+                "Only the first {} of each side were kept",
+                format_size(MAX_RAW)
+            ))
+            .yellow(),
+        );
+        lines.push(Line::default());
+    }
+    for (name, bytes) in [("Request", &raw.request), ("Response", &raw.response)] {
+        if name == "Response" {
+            lines.push(Line::default());
+        }
+        lines.push(Line::raw(format!("{name} · {}", format_size(bytes.len()))).bold());
+        lines.extend(byte_lines(bytes));
+    }
+    lines
+}
+
+/// Splits bytes into lines at each `\n`, marking how every line ended.
+fn byte_lines(bytes: &[u8]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (line, ending, len) = match rest.iter().position(|&b| b == b'\n') {
+            Some(i) if i > 0 && rest[i - 1] == b'\r' => (&rest[..i - 1], "␍␊", i + 1),
+            Some(i) => (&rest[..i], "␊", i + 1),
+            None => (rest, "", rest.len()),
+        };
+        rest = &rest[len..];
+        let mut spans = text_spans(line);
+        if !ending.is_empty() {
+            spans.push(Span::raw(ending).dark_gray());
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// Shows text as it is, and control characters and invalid UTF-8 as `\xNN`.
+fn text_spans(bytes: &[u8]) -> Vec<Span<'static>> {
+    fn escape(spans: &mut Vec<Span<'static>>, text: &mut String, byte: u8) {
+        if !text.is_empty() {
+            spans.push(Span::raw(std::mem::take(text)));
+        }
+        spans.push(Span::raw(format!("\\x{byte:02x}")).dark_gray());
+    }
+
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\t' => text.push(c),
+                c if c.is_control() && c.is_ascii() => escape(&mut spans, &mut text, c as u8),
+                c => text.push(c),
+            }
+        }
+        for &byte in chunk.invalid() {
+            escape(&mut spans, &mut text, byte);
+        }
+    }
+    if !text.is_empty() {
+        spans.push(Span::raw(text));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let raw = RawExchange {
+            request: b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n".to_vec(),
+            response: b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+                        3\r\n\x1f\x8b\xff\r\n0\r\n\r\n"
+                .to_vec(),
+            truncated: false,
+        };
+        let lines: Vec<String> = lines(&raw).iter().map(Line::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "Request · 37 B",
+                "GET / HTTP/1.1␍␊",
+                "host: example.com␍␊",
+                "␍␊",
+                "",
+                "Response · 60 B",
+                "HTTP/1.1 200 OK␍␊",
+                "transfer-encoding: chunked␍␊",
+                "␍␊",
+                "3␍␊",
+                "\\x1f\\x8b\\xff␍␊",
+                "0␍␊",
+                "␍␊",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_lines_without_final_newline() {
+        let lines: Vec<String> = byte_lines(b"a\nb").iter().map(Line::to_string).collect();
+        assert_eq!(lines, ["a␊", "b"]);
+    }
+}
@@ -0,0 +1,77 @@
+use ratatui::{
+    style::{Color, Stylize},
+    text::{Line, Span},
+};
+
+use super::timing::format_duration;
+use crate::executor::HttpResponse;
+
+/// Lays out the Redirects tab: a line per response in the chain, oldest first, each showing where
+/// it sent the client next.
+pub fn lines(chain: &[HttpResponse], current: usize) -> Vec<Line<'static>> {
+    chain
+        .iter()
+        .enumerate()
+        .map(|(i, response)| {
+            let color = match response.status {
+                200..=299 => Color::Green,
+                300..=399 => Color::Cyan,
+                400..=499 => Color::Yellow,
+                _ => Color::Red,
+            };
+            let mut spans = vec![
+                Span::raw(format!("{:<4}", i + 1)).dark_gray(),
+                Span::raw(format!("{} {:<20}", response.status, response.reason)).fg(color),
+                Span::raw(format!("{:>10}  ", format_duration(response.elapsed))).dark_gray(),
+                Span::raw(format!("{} {}", response.method, response.url)),
+            ];
+            if let Some(location) = response.header("location").filter(|_| i + 1 < chain.len()) {
+                spans.push(Span::raw(format!("  → {location}")).dark_gray());
+            }
+            let line = Line::from(spans);
+            if i == current {
+                line.reversed()
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let response =
+            |status: u16, reason: &str, url: &str, location: Option<&str>| HttpResponse {
+                method: "GET".into(),
+                url: url.into(),
+                status,
+                reason: reason.into(),
+                headers: location
+                    .map(|location| ("Location".to_string(), location.to_string()))
+                    .into_iter()
+                    .collect(),
+                elapsed: Duration::from_millis(12),
+                ..HttpResponse::default()
+            };
+        let chain = [
+            response(301, "Moved Permanently", "http://a/", Some("https://a/")),
+            response(200, "OK", "https://a/", None),
+        ];
+        let lines: Vec<String> = lines(&chain, 1).iter().map(Line::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "1   301 Moved Permanently      12.0 ms  GET http://a/  → https://a/",
+                "2   200 OK                     12.0 ms  GET https://a/",
+            ]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use regex::{Regex, RegexBuilder};
+
+/// A match of the search query within a line of the current view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Incremental search state for the lines of the response pane.
+#[derive(Debug, Default, Clone)]
+pub struct Search {
+    query: String,
+    case_sensitive: bool,
+    regex: bool,
+    matches: Vec<Match>,
+    current: usize,
+    error: Option<String>,
+    stale: bool,
+}
+
+impl Search {
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        if self.query != query {
+            self.query = query;
+            self.stale = true;
+        }
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    pub fn toggle_case(&mut self) {
+        self.case_sensitive = !self.case_sensitive;
+        self.stale = true;
+    }
+
+    pub fn regex(&self) -> bool {
+        self.regex
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.regex = !self.regex;
+        self.stale = true;
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Marks the matches as out of date, e.g. because the lines on screen changed.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale && self.is_active()
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matches.clear();
+        self.current = 0;
+        self.error = None;
+        self.stale = false;
+    }
+
+    /// Forgets the matches so that they can be found again, returning the pattern to look for,
+    /// or `None` when there is nothing to find.
+    pub fn start(&mut self) -> Option<Regex> {
+        self.stale = false;
+        self.matches.clear();
+        self.current = 0;
+        if self.query.is_empty() {
+            self.error = None;
+            return None;
+        }
+        match self.pattern() {
+            Ok(pattern) => {
+                self.error = None;
+                Some(pattern)
+            }
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Takes the matches found for the pattern from [`Search::start`], keeping the current match
+    /// on the first one at or after `from_line`.
+    pub fn finish(&mut self, matches: Vec<Match>, from_line: usize) {
+        self.matches = matches;
+        self.current = self
+            .matches
+            .partition_point(|m| m.line < from_line)
+            .min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn current(&self) -> Option<Match> {
+        self.matches.get(self.current).copied()
+    }
+
+    pub fn next(&mut self) -> Option<Match> {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+        self.current()
+    }
+
+    pub fn previous(&mut self) -> Option<Match> {
+        if !self.matches.is_empty() {
+            self.current = self
+                .current
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+        }
+        self.current()
+    }
+
+    /// Match counter for display, e.g. `3/17`.
+    pub fn counter(&self) -> String {
+        if self.matches.is_empty() {
+            "0/0".into()
+        } else {
+            format!("{}/{}", self.current + 1, self.matches.len())
+        }
+    }
+
+    /// Highlights the matches on a line, using `current_style` for the current match.
+    pub fn highlight(
+        &self,
+        line: Line<'static>,
+        n: usize,
+        style: Style,
+        current_style: Style,
+    ) -> Line<'static> {
+        let first = self.matches.partition_point(|m| m.line < n);
+        let ranges: Vec<(usize, usize, Style)> = self.matches[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, m)| m.line == n)
+            .map(|(i, m)| {
+                let style = if first + i == self.current {
+                    current_style
+                } else {
+                    style
+                };
+                (m.start, m.end, style)
+            })
+            .collect();
+        if ranges.is_empty() {
+            return line;
+        }
+        highlight_ranges(line, &ranges)
+    }
+
+    fn pattern(&self) -> Result<Regex, String> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|err| match err {
+                regex::Error::Syntax(_) => "Invalid regex".to_string(),
+                err => err.to_string(),
+            })
+    }
+}
+
+/// Finds every match of `pattern` in `lines`, in order.
+pub fn find<'a>(pattern: &Regex, lines: impl Iterator<Item = &'a str>) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for (line, text) in lines.enumerate() {
+        matches.extend(
+            pattern
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| Match {
+                    line,
+                    start: m.start(),
+                    end: m.end(),
+                }),
+        );
+    }
+    matches
+}
+
+/// Patches `ranges` (byte offsets into the line's text) with their styles, splitting spans where
+/// needed.
+fn highlight_ranges(line: Line<'static>, ranges: &[(usize, usize, Style)]) -> Line<'static> {
+    let mut spans = Vec::with_capacity(line.spans.len() + ranges.len() * 2);
+    let mut offset = 0;
+    for span in line.spans {
+        let content = span.content.into_owned();
+        let span_end = offset + content.len();
+        let mut cursor = offset;
+        for &(start, end, style) in ranges {
+            let (start, end) = (start.max(cursor), end.min(span_end));
+            if start >= end {
+                continue;
+            }
+            if start > cursor {
+                spans.push(Span::styled(
+                    content[cursor - offset..start - offset].to_string(),
+                    span.style,
+                ));
+            }
+            spans.push(Span::styled(
+                content[start - offset..end - offset].to_string(),
+                span.style.patch(style),
+            ));
+            cursor = end;
+        }
+        if cursor < span_end {
+            spans.push(Span::styled(
+                content[cursor - offset..].to_string(),
+                span.style,
+            ));
+        }
+        offset = span_end;
+    }
+    Line::from(spans).style(line.style)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::style::{Color, Stylize};
+
+    use super::*;
+
+    fn run(search: &mut Search, lines: &[&str], from_line: usize) {
+        if let Some(pattern) = search.start() {
+            search.finish(find(&pattern, lines.iter().copied()), from_line);
+        }
+    }
+
+    fn search(query: &str, lines: &[&str]) -> Search {
+        let mut search = Search::default();
+        search.set_query(query.into());
+        run(&mut search, lines, 0);
+        search
+    }
+
+    #[test]
+    fn test_case_insensitive_by_default() {
+        let mut search = search("id", &["\"id\": 1", "\"ID\": 2", "none"]);
+        assert_eq!(search.counter(), "1/2");
+        search.toggle_case();
+        run(&mut search, &["\"id\": 1", "\"ID\": 2"], 0);
+        assert_eq!(search.counter(), "1/1");
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let mut search = search("a", &["a", "b", "aa"]);
+        assert_eq!(search.next().map(|m| m.line), Some(2));
+        assert_eq!(search.next().map(|m| (m.line, m.start)), Some((2, 1)));
+        assert_eq!(search.next().map(|m| m.line), Some(0));
+        assert_eq!(search.previous().map(|m| (m.line, m.start)), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_regex() {
+        let mut search = Search::default();
+        search.toggle_regex();
+        search.set_query("[0-9]+".into());
+        run(&mut search, &["a1", "22b"], 1);
+        assert_eq!(
+            search.current(),
+            Some(Match {
+                line: 1,
+                start: 0,
+                end: 2
+            })
+        );
+
+        search.set_query("(".into());
+        run(&mut search, &["a1"], 0);
+        assert_eq!(search.error(), Some("Invalid regex"));
+    }
+
+    #[test]
+    fn test_highlight_splits_spans() {
+        let search = search("lo w", &["hello world"]);
+        let line = Line::from(vec!["hello".red(), " world".into()]);
+        let highlighted = search.highlight(line, 0, Style::new().bg(Color::Yellow), Style::new());
+        let parts: Vec<(&str, Style)> = highlighted
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("hel", Style::new().red()),
+                ("lo", Style::new().red()),
+                (" w", Style::new()),
+                ("orld", Style::new()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+
+use super::format_size;
+use crate::executor::cookies::{format_date, now};
+use crate::executor::tls::CertificateInfo;
+use crate::executor::{HttpResponse, Timing};
+use crate::history::HistoryEntry;
+
+/// Width of the phase names to the left of the waterfall.
+const LABEL_WIDTH: usize = 10;
+
+/// Width of the durations to the right of the waterfall.
+const DURATION_WIDTH: usize = 10;
+
+/// Certificates expiring within this many days are highlighted.
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+/// Number of previous runs listed under the waterfall.
+const MAX_RUNS: usize = 10;
+
+/// The phases of a request in the order they happen, with the colour of their bar.
+fn phases(timing: &Timing) -> [(&'static str, Option<Duration>, Color); 6] {
+    [
+        ("DNS", timing.dns, Color::Cyan),
+        ("Connect", Some(timing.connect), Color::Yellow),
+        ("TLS", timing.tls, Color::Magenta),
+        ("Request", Some(timing.request_write), Color::Green),
+        ("Waiting", Some(timing.first_byte), Color::Blue),
+        ("Download", Some(timing.download), Color::Red),
+    ]
+}
+
+/// Lays out the Timing tab: a waterfall of the request phases, details about the connection, and
+/// how earlier runs of the same request compare.
+pub fn lines<'a>(
+    response: &HttpResponse,
+    runs: impl Iterator<Item = &'a HistoryEntry>,
+    width: usize,
+) -> Vec<Line<'static>> {
+    let timing = &response.timing;
+    let bar_width = width
+        .saturating_sub(LABEL_WIDTH + DURATION_WIDTH + 2)
+        .max(1);
+    let total: Duration = phases(timing).iter().filter_map(|(_, d, _)| *d).sum();
+    let columns = |d: Duration| {
+        if total.is_zero() {
+            0
+        } else {
+            (d.as_secs_f64() / total.as_secs_f64() * bar_width as f64).round() as usize
+        }
+    };
+
+    let mut lines = Vec::new();
+    let mut elapsed = Duration::ZERO;
+    for (name, duration, color) in phases(timing) {
+        let label = Span::raw(format!("{name:<LABEL_WIDTH$}"));
+        let Some(duration) = duration else {
+            lines.push(Line::from(vec![
+                label.dark_gray(),
+                Span::raw("—").dark_gray(),
+            ]));
+            continue;
+        };
+        let start = columns(elapsed).min(bar_width - 1);
+        let len = columns(duration).clamp(1, bar_width - start);
+        elapsed += duration;
+        lines.push(Line::from(vec![
+            label,
+            Span::raw(" ".repeat(start)),
+            Span::styled("█".repeat(len), Style::default().fg(color)),
+            Span::raw(" ".repeat(bar_width - start - len)),
+            Span::raw(format!("  {:>DURATION_WIDTH$}", format_duration(duration))),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::raw(format!("{:<LABEL_WIDTH$}", "Total")).bold(),
+        Span::raw(" ".repeat(bar_width)),
+        Span::raw(format!(
+            "  {:>DURATION_WIDTH$}",
+            format_duration(response.elapsed)
+        ))
+        .bold(),
+    ]));
+
+    let detail = |name: &str, value: String| {
+        Line::from(vec![
+            Span::raw(format!("{name:<16}")).dark_gray(),
+            Span::raw(value),
+        ])
+    };
+    let tls = match (&timing.tls_version, &timing.alpn) {
+        (Some(version), Some(alpn)) => format!("{version}, ALPN {alpn}"),
+        (Some(version), None) => version.clone(),
+        _ => "none".into(),
+    };
+    lines.push(Line::default());
+    let remote = match (&timing.socket, timing.remote_addr) {
+        (Some(socket), _) => format!("Unix socket {socket}"),
+        (None, Some(addr)) => addr.to_string(),
+        (None, None) => "unknown".into(),
+    };
+    lines.push(detail("Remote address", remote));
+    let mut resolved_by = detail("Resolved by", String::new());
+    match &timing.resolved_by {
+        // Stands out, as it bypasses what the rest of the world sees.
+        Some(by) => resolved_by.push_span(Span::raw(by.clone()).yellow()),
+        None => resolved_by.push_span("system resolver"),
+    }
+    lines.push(resolved_by);
+    lines.push(detail(
+        "Proxy",
+        timing.proxy.clone().unwrap_or_else(|| "none".into()),
+    ));
+    lines.push(detail("Protocol", response.version.clone()));
+    let mut tls = detail("TLS", tls);
+    if timing.insecure {
+        tls.push_span(
+            Span::raw("  certificate not verified ")
+                .white()
+                .on_red()
+                .bold(),
+        );
+    }
+    lines.push(tls);
+    lines.push(detail(
+        "Connection",
+        if timing.reused {
+            "reused".into()
+        } else {
+            "new (each request opens its own)".into()
+        },
+    ));
+    if !timing.certificates.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::raw("Certificate chain").bold());
+        lines.extend(certificate_lines(&timing.certificates, now() as i64));
+    }
+
+    let runs: Vec<&HistoryEntry> = runs.take(MAX_RUNS).collect();
+    if !runs.is_empty() {
+        lines.push(Line::default());
+        lines.push(
+            Line::raw(format!(
+                "{:<12}{:<8}{:>DURATION_WIDTH$}{:>DURATION_WIDTH$}{:>DURATION_WIDTH$}",
+                "Recent runs", "Status", "Total", "Waiting", "Size"
+            ))
+            .bold(),
+        );
+        lines.extend(runs.into_iter().map(|run| {
+            Line::raw(format!(
+                "{:<12}{:<8}{:>DURATION_WIDTH$}{:>DURATION_WIDTH$}{:>DURATION_WIDTH$}",
+                format_age(run.age()),
+                run.status,
+                format_duration(run.elapsed),
+                format_duration(run.timing.first_byte),
+                format_size(run.size),
+            ))
+        }));
+    }
+    lines
+}
+
+/// Describes each certificate of a chain, flagging ones that are expired or about to be.
+fn certificate_lines(certificates: &[CertificateInfo], now: i64) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (i, cert) in certificates.iter().enumerate() {
+        let days = (cert.not_after - now).div_euclid(86400);
+        let (remaining, color) = if cert.not_after <= now {
+            (format!("expired {} d ago", -days), Color::Red)
+        } else if now < cert.not_before {
+            ("not yet valid".into(), Color::Red)
+        } else if days < EXPIRY_WARNING_DAYS {
+            (format!("in {days} d"), Color::Yellow)
+        } else {
+            (format!("in {days} d"), Color::default())
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{i:<4}")).dark_gray(),
+            Span::raw(cert.subject.clone()),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:<4}{:<12}", "", "Issuer")).dark_gray(),
+            Span::raw(cert.issuer.clone()),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:<4}{:<12}", "", "Expires")).dark_gray(),
+            Span::raw(format_date(cert.not_after.max(0) as u64)),
+            Span::styled(format!(" ({remaining})"), Style::default().fg(color)),
+        ]));
+    }
+    lines
+}
+
+/// Formats a duration for display, e.g. `12.3 ms` or `1.25 s`.
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_secs_f64() * 1000.0;
+    if ms < 1000.0 {
+        format!("{ms:.1} ms")
+    } else {
+        format!("{:.2} s", ms / 1000.0)
+    }
+}
+
+/// Formats how long ago something happened, e.g. `5 min ago`.
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=4 => "just now".into(),
+        5..=59 => format!("{seconds} s ago"),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} d ago", seconds / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_waterfall() {
+        let response = HttpResponse {
+            version: "HTTP/1.1".into(),
+            elapsed: Duration::from_millis(40),
+            timing: Timing {
+                connect: Duration::from_millis(10),
+                request_write: Duration::ZERO,
+                first_byte: Duration::from_millis(20),
+                download: Duration::from_millis(10),
+                ..Timing::default()
+            },
+            ..HttpResponse::default()
+        };
+        let lines = lines(&response, std::iter::empty(), 30);
+        let lines: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(lines[0], "DNS       —");
+        assert_eq!(lines[1], "Connect   ██           10.0 ms");
+        assert_eq!(lines[3], "Request     █           0.0 ms");
+        assert_eq!(lines[4], "Waiting     ████       20.0 ms");
+        assert_eq!(lines[5], "Download        ██     10.0 ms");
+        assert_eq!(lines[9], "Resolved by     system resolver");
+        assert_eq!(lines[10], "Proxy           none");
+        assert_eq!(lines[11], "Protocol        HTTP/1.1");
+        assert_eq!(
+            lines[13],
+            "Connection      new (each request opens its own)"
+        );
+    }
+
+    #[test]
+    fn test_certificate_lines() {
+        let cert = |not_after| CertificateInfo {
+            subject: "CN=example.com".into(),
+            issuer: "CN=Example CA".into(),
+            not_before: 0,
+            not_after,
+        };
+        let now = 1445412480;
+        let lines = certificate_lines(&[cert(now + 90 * 86400), cert(now - 86400)], now);
+        let lines: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(lines[0], "0   CN=example.com");
+        assert_eq!(lines[1], "    Issuer      CN=Example CA");
+        assert_eq!(
+            lines[2],
+            "    Expires     Tue, 19 Jan 2016 07:28:00 GMT (in 90 d)"
+        );
+        assert_eq!(
+            lines[5],
+            "    Expires     Tue, 20 Oct 2015 07:28:00 GMT (expired 1 d ago)"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_micros(12_345)), "12.3 ms");
+        assert_eq!(format_duration(Duration::from_millis(1_250)), "1.25 s");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(3), "just now");
+        assert_eq!(format_age(125), "2 min ago");
+        assert_eq!(format_age(90_000), "1 d ago");
+    }
+}
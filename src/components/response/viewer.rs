@@ -0,0 +1,503 @@
+use std::sync::Arc;
+
+use encoding_rs::Encoding;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use self::charset::Charset;
+use self::magic::FileType;
+use self::table::Table;
+use super::json::JsonTree;
+
+pub mod binary;
+pub mod charset;
+pub mod hex;
+pub mod magic;
+pub mod markup;
+pub mod table;
+pub mod yaml;
+
+/// Width that HTML is wrapped to in the readable view.
+const READABLE_WIDTH: usize = 100;
+
+/// How much of the body is looked at when sniffing whether it is binary.
+const SNIFF_LEN: usize = 1024;
+
+/// Media types each format is registered for. Entries starting with `+` match structured syntax
+/// suffixes, e.g. `application/problem+json`, and entries ending in `/*` match a whole top-level
+/// type.
+const REGISTRY: &[(Format, &[&str])] = &[
+    (Format::Json, &["application/json", "text/json", "+json"]),
+    (Format::Html, &["text/html", "application/xhtml+xml"]),
+    (Format::Xml, &["application/xml", "text/xml", "+xml"]),
+    (
+        Format::Yaml,
+        &[
+            "application/yaml",
+            "application/x-yaml",
+            "text/yaml",
+            "text/x-yaml",
+            "+yaml",
+        ],
+    ),
+    (Format::Csv, &["text/csv", "application/csv"]),
+    (
+        Format::MessagePack,
+        &[
+            "application/msgpack",
+            "application/x-msgpack",
+            "application/vnd.msgpack",
+        ],
+    ),
+    (Format::Cbor, &["application/cbor", "+cbor"]),
+    (
+        Format::Binary,
+        &[
+            "application/octet-stream",
+            "application/pdf",
+            "application/zip",
+            "application/gzip",
+            "application/wasm",
+            "image/*",
+            "audio/*",
+            "video/*",
+            "font/*",
+        ],
+    ),
+];
+
+/// Styles used to highlight structured bodies.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SyntaxStyles {
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub literal: Style,
+    pub punctuation: Style,
+    pub hint: Style,
+}
+
+/// What a piece of highlighted text is, resolved to a style when drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Plain,
+    Key,
+    String,
+    Number,
+    Literal,
+    Punctuation,
+    Hint,
+}
+
+/// A pretty-printed line, as pieces of highlighted text.
+pub type Fragments = Vec<(Kind, String)>;
+
+/// The formats the response pane knows how to display.
+#[derive(Debug, Default, Display, EnumIter, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[strum(to_string = "JSON")]
+    Json,
+    #[strum(to_string = "XML")]
+    Xml,
+    #[strum(to_string = "HTML")]
+    Html,
+    #[strum(to_string = "YAML")]
+    Yaml,
+    #[strum(to_string = "CSV")]
+    Csv,
+    #[strum(to_string = "MessagePack")]
+    MessagePack,
+    #[strum(to_string = "CBOR")]
+    Cbor,
+    #[strum(to_string = "Binary")]
+    Binary,
+    #[default]
+    #[strum(to_string = "Text")]
+    Text,
+}
+
+#[derive(Debug, Default, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BodyView {
+    #[default]
+    #[strum(to_string = "Tree")]
+    Tree,
+    #[strum(to_string = "Pretty")]
+    Pretty,
+    #[strum(to_string = "Readable")]
+    Readable,
+    #[strum(to_string = "Table")]
+    Table,
+    #[strum(to_string = "Raw")]
+    Raw,
+    #[strum(to_string = "Hex")]
+    Hex,
+    #[strum(to_string = "JSON-RPC")]
+    JsonRpc,
+}
+
+/// A response body prepared for display.
+///
+/// Building one walks the whole body, so it is done on a blocking task instead of the render
+/// loop.
+#[derive(Default)]
+pub struct Document {
+    pub format: Format,
+    pub file_type: Option<FileType>,
+    pub error: Option<String>,
+    pub value: Option<Arc<Value>>,
+    pub json: Option<JsonTree>,
+    pub pretty: Vec<Fragments>,
+    pub readable: Vec<String>,
+    pub table: Option<Table>,
+    pub bytes: Vec<u8>,
+    /// How the body was read as text, or `None` if it is binary.
+    pub charset: Option<Charset>,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SyntaxStyles {
+    pub fn get(&self, kind: Kind) -> Style {
+        match kind {
+            Kind::Plain => Style::default(),
+            Kind::Key => self.key,
+            Kind::String => self.string,
+            Kind::Number => self.number,
+            Kind::Literal => self.literal,
+            Kind::Punctuation => self.punctuation,
+            Kind::Hint => self.hint,
+        }
+    }
+
+    pub fn line(&self, fragments: &Fragments) -> Line<'static> {
+        fragments
+            .iter()
+            .map(|(kind, text)| Span::styled(text.clone(), self.get(*kind)))
+            .collect()
+    }
+
+    /// Like [`SyntaxStyles::line`], but stops after `width` characters so that long lines cost only
+    /// what fits on screen.
+    pub fn clipped_line(&self, fragments: &Fragments, width: usize) -> Line<'static> {
+        let mut left = width;
+        let mut spans = Vec::new();
+        for (kind, text) in fragments {
+            if left == 0 {
+                break;
+            }
+            let text: String = text.chars().take(left).collect();
+            left -= text.chars().count();
+            spans.push(Span::styled(text, self.get(*kind)));
+        }
+        Line::from(spans)
+    }
+}
+
+impl Format {
+    /// Looks up the format registered for a media type.
+    pub fn for_media_type(media_type: &str) -> Option<Self> {
+        REGISTRY.iter().find_map(|(format, types)| {
+            types
+                .iter()
+                .any(|t| {
+                    if let Some(suffix) = t.strip_prefix('+') {
+                        media_type.ends_with(&format!("+{suffix}"))
+                    } else if let Some(top_level) = t.strip_suffix("/*") {
+                        media_type.split('/').next() == Some(top_level)
+                    } else {
+                        media_type == *t
+                    }
+                })
+                .then_some(*format)
+        })
+    }
+
+    /// Picks the format from the media type, falling back to sniffing the start of the body.
+    pub fn detect(body: &[u8], media_type: Option<&str>) -> Self {
+        if let Some(format) = media_type.and_then(Self::for_media_type) {
+            return format;
+        }
+        let start = body
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map_or(&[][..], |i| &body[i..]);
+        let lowercase = String::from_utf8_lossy(&start[..start.len().min(15)]).to_lowercase();
+        match start.first() {
+            Some(b'{' | b'[') => Self::Json,
+            _ if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") => {
+                Self::Html
+            }
+            _ if lowercase.starts_with("<?xml") => Self::Xml,
+            _ if magic::detect(body).is_some()
+                || body[..body.len().min(SNIFF_LEN)].contains(&0) =>
+            {
+                Self::Binary
+            }
+            _ => Self::Text,
+        }
+    }
+
+    /// The next format to force when cycling through overrides, `None` going back to detection.
+    pub fn next_override(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Self::iter().next(),
+            Some(format) => Self::iter().skip_while(|f| *f != format).nth(1),
+        }
+    }
+}
+
+impl Document {
+    /// Prepares a body for display, using `format` instead of detecting it and `encoding`
+    /// instead of the one the body or its `Content-Type` declares when given.
+    pub fn new(
+        body: &[u8],
+        content_type: Option<&str>,
+        format: Option<Format>,
+        encoding: Option<&'static Encoding>,
+    ) -> Self {
+        let media_type = content_type.map(|content_type| {
+            let media_type = content_type.split(';').next().unwrap_or_default();
+            media_type.trim().to_ascii_lowercase()
+        });
+        let (text, charset) = charset::decode(body, content_type, encoding);
+        // UTF-16 is full of zero bytes, which would make it look binary.
+        let format = format.unwrap_or_else(|| match charset.encoding.is_ascii_compatible() {
+            true => Format::detect(body, media_type.as_deref()),
+            false => Format::detect(text.as_bytes(), media_type.as_deref()),
+        });
+        let (text, charset) = match format {
+            Format::Binary | Format::MessagePack | Format::Cbor => {
+                (String::from_utf8_lossy(body).into_owned(), None)
+            }
+            _ => (text, Some(charset)),
+        };
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .filter(|&start| start < text.len())
+            .collect();
+        let mut document = Self {
+            format,
+            file_type: magic::detect(body),
+            bytes: body.to_vec(),
+            charset,
+            line_starts,
+            ..Self::default()
+        };
+        // Text formats are parsed from the decoded text, binary ones from the bytes.
+        let body = match charset {
+            Some(_) => text.as_bytes(),
+            None => body,
+        };
+
+        let result = match format {
+            Format::Json => serde_json::from_slice(body)
+                .map(|value| document.set_value(value))
+                .map_err(|err| err.to_string()),
+            Format::Yaml => yaml::parse(body).map(|(value, pretty)| {
+                document.pretty = pretty;
+                if let Some(value) = value {
+                    document.set_value(value);
+                }
+            }),
+            Format::MessagePack => binary::msgpack_to_json(body).map(|v| document.set_value(v)),
+            Format::Cbor => binary::cbor_to_json(body).map(|v| document.set_value(v)),
+            Format::Xml => markup::pretty_xml(body).map(|pretty| document.pretty = pretty),
+            Format::Html => markup::pretty_html(body).map(|pretty| {
+                document.pretty = pretty;
+                document.readable = html2text::from_read(body, READABLE_WIDTH)
+                    .lines()
+                    .map(str::to_string)
+                    .collect();
+            }),
+            Format::Csv => Table::parse(body).map(|table| document.table = Some(table)),
+            Format::Binary | Format::Text => Ok(()),
+        };
+        if let Err(err) = result {
+            document.error = Some(format!("Not valid {format}: {err}"));
+        }
+        document.text = text;
+        document
+    }
+
+    fn set_value(&mut self, value: Value) {
+        self.json = Some(JsonTree::from_value(&value));
+        self.value = Some(Arc::new(value));
+    }
+
+    /// The views that can show this document, in the order they are cycled through.
+    pub fn views(&self) -> Vec<BodyView> {
+        if self.format == Format::Binary {
+            return vec![BodyView::Hex, BodyView::Raw];
+        }
+        let mut views = Vec::with_capacity(4);
+        if self.json.is_some() {
+            views.push(BodyView::Tree);
+        }
+        if self.json.is_some() || !self.pretty.is_empty() {
+            views.push(BodyView::Pretty);
+        }
+        if !self.readable.is_empty() {
+            views.push(BodyView::Readable);
+        }
+        if self.table.is_some() {
+            views.push(BodyView::Table);
+        }
+        views.push(BodyView::Raw);
+        views.push(BodyView::Hex);
+        views
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn line(&self, n: usize) -> &str {
+        let start = self.line_starts[n];
+        let end = self
+            .line_starts
+            .get(n + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        self.text[start..end].trim_end_matches(['\r', '\n'])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_for_media_type() {
+        assert_eq!(
+            Format::for_media_type("application/problem+json"),
+            Some(Format::Json)
+        );
+        assert_eq!(
+            Format::for_media_type("application/xhtml+xml"),
+            Some(Format::Html)
+        );
+        assert_eq!(
+            Format::for_media_type("application/atom+xml"),
+            Some(Format::Xml)
+        );
+        assert_eq!(
+            Format::for_media_type("application/x-msgpack"),
+            Some(Format::MessagePack)
+        );
+        assert_eq!(Format::for_media_type("text/plain"), None);
+    }
+
+    #[test]
+    fn test_detect_sniffs_body() {
+        assert_eq!(Format::detect(b" [1]", Some("text/plain")), Format::Json);
+        assert_eq!(Format::detect(b"<!DOCTYPE html><p>", None), Format::Html);
+        assert_eq!(
+            Format::detect(b"<?xml version=\"1.0\"?>", None),
+            Format::Xml
+        );
+        assert_eq!(Format::detect(b"a,b", None), Format::Text);
+        assert_eq!(Format::detect(b"a,b", Some("text/csv")), Format::Csv);
+        assert_eq!(Format::detect(b"\x00\x01", None), Format::Binary);
+        assert_eq!(
+            Format::detect(b"%PDF-1.7", Some("text/plain")),
+            Format::Binary
+        );
+        assert_eq!(
+            Format::detect(b"<svg/>", Some("image/svg+xml")),
+            Format::Xml
+        );
+    }
+
+    #[test]
+    fn test_next_override_cycles_back_to_detection() {
+        let mut format = None;
+        let mut seen = vec![];
+        loop {
+            format = Format::next_override(format);
+            match format {
+                Some(f) => seen.push(f),
+                None => break,
+            }
+        }
+        assert_eq!(seen, Format::iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_views() {
+        let document = Document::new(b"{\"a\": 1}", Some("application/json"), None, None);
+        assert_eq!(
+            document.views(),
+            vec![
+                BodyView::Tree,
+                BodyView::Pretty,
+                BodyView::Raw,
+                BodyView::Hex
+            ]
+        );
+
+        let document = Document::new(b"<p>hi</p>", Some("text/html"), None, None);
+        assert_eq!(
+            document.views(),
+            vec![
+                BodyView::Pretty,
+                BodyView::Readable,
+                BodyView::Raw,
+                BodyView::Hex
+            ]
+        );
+
+        let document = Document::new(
+            b"{\"a\": 1}",
+            Some("application/json"),
+            Some(Format::Csv),
+            None,
+        );
+        assert_eq!(document.format, Format::Csv);
+        assert_eq!(
+            document.views(),
+            vec![BodyView::Table, BodyView::Raw, BodyView::Hex]
+        );
+
+        let document = Document::new(b"\x89PNG\r\n\x1a\n", Some("image/png"), None, None);
+        assert_eq!(document.format, Format::Binary);
+        assert_eq!(document.file_type.map(|t| t.name), Some("PNG image"));
+        assert_eq!(document.views(), vec![BodyView::Hex, BodyView::Raw]);
+    }
+
+    #[test]
+    fn test_invalid_body_reports_error() {
+        let document = Document::new(b"{", Some("application/json"), None, None);
+        assert!(document
+            .error
+            .as_ref()
+            .is_some_and(|err| err.starts_with("Not valid JSON")));
+        assert_eq!(document.views(), vec![BodyView::Raw, BodyView::Hex]);
+    }
+
+    #[test]
+    fn test_decodes_charset() {
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("{\"a\": \"é\"}".encode_utf16().flat_map(u16::to_le_bytes));
+        let document = Document::new(&utf16, None, None, None);
+        assert_eq!(document.format, Format::Json);
+        assert_eq!(document.line(0), "{\"a\": \"é\"}");
+        assert_eq!(document.bytes, utf16);
+        assert!(document.views().contains(&BodyView::Tree));
+
+        let latin1 = Document::new(b"caf\xe9", Some("text/plain; charset=latin1"), None, None);
+        assert_eq!(latin1.line(0), "café");
+        let charset = latin1.charset.unwrap();
+        assert!(!charset.lossy);
+        assert_eq!(charset.encoding, encoding_rs::WINDOWS_1252);
+
+        let binary = Document::new(b"\x89PNG\r\n\x1a\n", Some("image/png"), None, None);
+        assert_eq!(binary.charset, None);
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use serde_json::{Map, Number, Value};
+
+/// Decodes a MessagePack body. A body holding several values in a row decodes to an array.
+pub fn msgpack_to_json(body: &[u8]) -> Result<Value, String> {
+    let mut rest = body;
+    let mut values = Vec::new();
+    while !rest.is_empty() {
+        let value = rmpv::decode::read_value(&mut rest).map_err(|err| err.to_string())?;
+        values.push(from_msgpack(value));
+    }
+    Ok(match values.len() {
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    })
+}
+
+/// Decodes a CBOR body.
+pub fn cbor_to_json(body: &[u8]) -> Result<Value, String> {
+    let value: ciborium::Value = ciborium::from_reader(body).map_err(|err| err.to_string())?;
+    Ok(from_cbor(value))
+}
+
+fn from_msgpack(value: rmpv::Value) -> Value {
+    use rmpv::Value as Msgpack;
+
+    match value {
+        Msgpack::Nil => Value::Null,
+        Msgpack::Boolean(b) => Value::Bool(b),
+        Msgpack::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => i.into(),
+            (_, Some(u)) => u.into(),
+            _ => Value::Null,
+        },
+        Msgpack::F32(f) => float(f.into()),
+        Msgpack::F64(f) => float(f),
+        Msgpack::String(s) => match s.as_str() {
+            Some(s) => Value::String(s.to_string()),
+            None => Value::String(hex(s.as_bytes())),
+        },
+        Msgpack::Binary(bytes) => Value::String(hex(&bytes)),
+        Msgpack::Array(items) => Value::Array(items.into_iter().map(from_msgpack).collect()),
+        Msgpack::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(from_msgpack(key)), from_msgpack(value)))
+                .collect(),
+        ),
+        Msgpack::Ext(kind, data) => {
+            let mut ext = Map::new();
+            ext.insert("$ext".into(), kind.into());
+            ext.insert("data".into(), Value::String(hex(&data)));
+            Value::Object(ext)
+        }
+    }
+}
+
+fn from_cbor(value: ciborium::Value) -> Value {
+    use ciborium::Value as Cbor;
+
+    match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => i.into(),
+                (_, Ok(u)) => u.into(),
+                _ => Value::String(i.to_string()),
+            }
+        }
+        Cbor::Float(f) => float(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(bytes) => Value::String(hex(&bytes)),
+        Cbor::Array(items) => Value::Array(items.into_iter().map(from_cbor).collect()),
+        Cbor::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(from_cbor(key)), from_cbor(value)))
+                .collect(),
+        ),
+        Cbor::Tag(tag, value) => {
+            let mut tagged = Map::new();
+            tagged.insert("$tag".into(), tag.into());
+            tagged.insert("value".into(), from_cbor(*value));
+            Value::Object(tagged)
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Non-finite floats have no JSON representation, so they are shown as text.
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map_or_else(|| Value::String(f.to_string()), Value::Number)
+}
+
+/// JSON only has string keys; other keys are written out as JSON.
+fn key_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        key => key.to_string(),
+    }
+}
+
+/// Formats bytes in CBOR diagnostic notation, e.g. `h'cafe'`.
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2 + 3);
+    hex.push_str("h'");
+    for b in bytes {
+        let _ = write!(hex, "{b:02x}");
+    }
+    hex.push('\'');
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_msgpack_to_json() {
+        // {"id": 1, "ok": true, "data": bin [0xca, 0xfe]}
+        let body = b"\x83\xa2id\x01\xa2ok\xc3\xa4data\xc4\x02\xca\xfe";
+        assert_eq!(
+            msgpack_to_json(body).unwrap(),
+            json!({"id": 1, "ok": true, "data": "h'cafe'"})
+        );
+        // Two values in a row: 1, nil.
+        assert_eq!(msgpack_to_json(b"\x01\xc0").unwrap(), json!([1, null]));
+        assert!(msgpack_to_json(b"\x92\x01").is_err());
+    }
+
+    #[test]
+    fn test_cbor_to_json() {
+        // {1: "a", "b": [-1, 1.5]}
+        let body = b"\xa2\x01\x61a\x61b\x82\x20\xf9\x3e\x00";
+        assert_eq!(
+            cbor_to_json(body).unwrap(),
+            json!({"1": "a", "b": [-1, 1.5]})
+        );
+        // Tag 1 (epoch time) on 0.
+        assert_eq!(
+            cbor_to_json(b"\xc1\x00").unwrap(),
+            json!({"$tag": 1, "value": 0})
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use encoding_rs::{
+    Encoding,
+    BIG5,
+    EUC_JP,
+    EUC_KR,
+    GBK,
+    KOI8_R,
+    SHIFT_JIS,
+    UTF_16BE,
+    UTF_16LE,
+    UTF_8,
+    WINDOWS_1251,
+    WINDOWS_1252,
+};
+
+/// How far into a body a `<meta>` or XML declaration naming the charset is looked for.
+const SNIFF_LEN: usize = 1024;
+
+/// The encodings offered when overriding the detected one, in the order they are cycled through.
+/// Browsers read ISO-8859-1 as windows-1252, which is what it stands for here too.
+const OVERRIDES: &[&Encoding] = &[
+    UTF_8,
+    WINDOWS_1252,
+    SHIFT_JIS,
+    EUC_JP,
+    GBK,
+    BIG5,
+    EUC_KR,
+    WINDOWS_1251,
+    KOI8_R,
+    UTF_16LE,
+    UTF_16BE,
+];
+
+/// Where the encoding of a body came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// A byte order mark at the start of the body.
+    Bom,
+    /// The `charset` parameter of `Content-Type`.
+    Header,
+    /// A `<meta>` tag or XML declaration in the body.
+    Markup,
+    /// Nothing said otherwise, so UTF-8.
+    Default,
+    /// The user picked it.
+    Override,
+}
+
+/// How the bytes of a body were turned into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Charset {
+    pub encoding: &'static Encoding,
+    pub source: Source,
+    /// Whether some bytes weren't valid in the encoding and were replaced with `�`.
+    pub lossy: bool,
+}
+
+impl Charset {
+    /// Whether there is anything worth pointing out about how the body was read.
+    pub fn is_notable(&self) -> bool {
+        self.lossy || self.encoding != UTF_8 || self.source == Source::Override
+    }
+}
+
+/// The encoding to force after `current` when cycling through overrides, `None` going back to
+/// detection.
+pub fn next_override(current: Option<&'static Encoding>) -> Option<&'static Encoding> {
+    match current {
+        None => OVERRIDES.first().copied(),
+        Some(encoding) => OVERRIDES
+            .iter()
+            .skip_while(|e| **e != encoding)
+            .nth(1)
+            .copied(),
+    }
+}
+
+/// Turns a body into text, in `forced` if given, or else the encoding named by its byte order
+/// mark, `Content-Type` header or markup, in that order.
+pub fn decode(
+    body: &[u8],
+    content_type: Option<&str>,
+    forced: Option<&'static Encoding>,
+) -> (String, Charset) {
+    if let Some(encoding) = forced {
+        let (text, lossy) = encoding.decode_without_bom_handling(body);
+        let charset = Charset {
+            encoding,
+            source: Source::Override,
+            lossy,
+        };
+        return (text.into_owned(), charset);
+    }
+    let (encoding, source) = detect(body, content_type);
+    let (text, lossy) = encoding.decode_with_bom_removal(body);
+    let charset = Charset {
+        encoding,
+        source,
+        lossy,
+    };
+    (text.into_owned(), charset)
+}
+
+/// Works out the encoding of a body, defaulting to UTF-8.
+fn detect(body: &[u8], content_type: Option<&str>) -> (&'static Encoding, Source) {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return (encoding, Source::Bom);
+    }
+    let declared = content_type
+        .and_then(|content_type| parameter(content_type, "charset"))
+        .and_then(|label| Encoding::for_label(label.as_bytes()));
+    if let Some(encoding) = declared {
+        return (encoding, Source::Header);
+    }
+    // Markup declaring UTF-16 can't have been read as ASCII to find that out, so it is UTF-8.
+    match sniff_markup(&body[..body.len().min(SNIFF_LEN)]) {
+        Some(encoding) if encoding == UTF_16LE || encoding == UTF_16BE => (UTF_8, Source::Markup),
+        Some(encoding) => (encoding, Source::Markup),
+        None => (UTF_8, Source::Default),
+    }
+}
+
+/// The value of a parameter of a header such as `Content-Type`, without quotes.
+fn parameter(header: &str, name: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+    })
+}
+
+/// Finds the encoding named by a `<meta>` tag of an HTML page or the declaration of an XML one.
+fn sniff_markup(start: &[u8]) -> Option<&'static Encoding> {
+    let start = String::from_utf8_lossy(start).to_ascii_lowercase();
+    let label_after = |tag: &str, key: &str| {
+        let at = tag.find(key)? + key.len();
+        let label: String = tag[at..]
+            .trim_start_matches([' ', '"', '\''])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+            .collect();
+        Encoding::for_label(label.as_bytes())
+    };
+    if let Some(declaration) = start.trim_start().strip_prefix("<?xml") {
+        let declaration = declaration.split("?>").next().unwrap_or_default();
+        return label_after(declaration, "encoding=");
+    }
+    start
+        .split("<meta")
+        .skip(1)
+        .find_map(|tag| label_after(tag.split('>').next().unwrap_or_default(), "charset="))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let (text, charset) = decode(b"caf\xe9", Some("text/plain; charset=ISO-8859-1"), None);
+        assert_eq!(text, "café");
+        assert_eq!(charset.encoding, WINDOWS_1252);
+        assert_eq!(charset.source, Source::Header);
+        assert!(!charset.lossy);
+
+        let (text, charset) = decode(b"\xff\xfeh\0i\0", Some("text/plain; charset=utf-8"), None);
+        assert_eq!(text, "hi");
+        assert_eq!((charset.encoding, charset.source), (UTF_16LE, Source::Bom));
+
+        let html = b"<html><head><meta charset=\"shift_jis\"></head>\x93\xfa\x96\x7b</html>";
+        let (text, charset) = decode(html, Some("text/html"), None);
+        assert!(text.contains("日本"), "{text}");
+        assert_eq!(
+            (charset.encoding, charset.source),
+            (SHIFT_JIS, Source::Markup)
+        );
+
+        let xml = b"<?xml version=\"1.0\" encoding='EUC-JP'?><a/>";
+        assert_eq!(decode(xml, None, None).1.encoding, EUC_JP);
+
+        let (text, charset) = decode(b"caf\xe9", None, None);
+        assert_eq!(text, "caf\u{fffd}");
+        assert_eq!((charset.encoding, charset.source), (UTF_8, Source::Default));
+        assert!(charset.lossy);
+        assert!(charset.is_notable());
+
+        let (text, charset) = decode(b"caf\xe9", None, Some(WINDOWS_1252));
+        assert_eq!(text, "café");
+        assert_eq!(charset.source, Source::Override);
+    }
+
+    #[test]
+    fn test_next_override() {
+        assert_eq!(next_override(None), Some(UTF_8));
+        assert_eq!(next_override(Some(UTF_8)), Some(WINDOWS_1252));
+        assert_eq!(next_override(Some(UTF_16BE)), None);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use super::{Fragments, Kind};
+
+pub const BYTES_PER_LINE: usize = 16;
+
+/// Number of lines in the dump of `len` bytes.
+pub fn line_count(len: usize) -> usize {
+    len.div_ceil(BYTES_PER_LINE)
+}
+
+/// The `n`th line of a hex dump, e.g.
+/// `00000010  00 00 00 0d 49 48 44 52  00 00 01 00 00 00 01 00  |....IHDR........|`.
+///
+/// Lines are formatted on demand so that the dump of a large body costs nothing up front.
+pub fn line(bytes: &[u8], n: usize) -> Fragments {
+    let start = n * BYTES_PER_LINE;
+    let chunk = &bytes[start.min(bytes.len())..(start + BYTES_PER_LINE).min(bytes.len())];
+
+    let mut hex = String::with_capacity(BYTES_PER_LINE * 3 + 1);
+    for i in 0..BYTES_PER_LINE {
+        if i == BYTES_PER_LINE / 2 {
+            hex.push(' ');
+        }
+        match chunk.get(i) {
+            Some(b) => {
+                let _ = write!(hex, "{b:02x} ");
+            }
+            None => hex.push_str("   "),
+        }
+    }
+    let ascii: String = chunk
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    vec![
+        (Kind::Hint, format!("{start:08x}  ")),
+        (Kind::Plain, hex),
+        (Kind::Punctuation, " |".into()),
+        (Kind::String, ascii),
+        (Kind::Punctuation, "|".into()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn text(fragments: Fragments) -> String {
+        fragments.into_iter().map(|(_, text)| text).collect()
+    }
+
+    #[test]
+    fn test_line() {
+        let bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x01";
+        assert_eq!(line_count(bytes.len()), 2);
+        assert_eq!(
+            text(line(bytes, 0)),
+            "00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|"
+        );
+        assert_eq!(
+            text(line(bytes, 1)),
+            "00000010  00 01                                             |..|"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+/// A file type recognised from the first bytes of a body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileType {
+    pub name: &'static str,
+    pub extension: &'static str,
+}
+
+/// Signatures as (offset, magic bytes, name, extension), checked in order.
+const SIGNATURES: &[(usize, &[u8], &str, &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image", "png"),
+    (0, b"\xff\xd8\xff", "JPEG image", "jpg"),
+    (0, b"GIF87a", "GIF image", "gif"),
+    (0, b"GIF89a", "GIF image", "gif"),
+    (8, b"WEBP", "WebP image", "webp"),
+    (0, b"BM", "BMP image", "bmp"),
+    (0, b"\x00\x00\x01\x00", "ICO image", "ico"),
+    (0, b"II*\x00", "TIFF image", "tiff"),
+    (0, b"MM\x00*", "TIFF image", "tiff"),
+    (0, b"%PDF-", "PDF document", "pdf"),
+    (0, b"PK\x03\x04", "ZIP archive", "zip"),
+    (0, b"\x1f\x8b", "gzip archive", "gz"),
+    (0, b"BZh", "bzip2 archive", "bz2"),
+    (0, b"\xfd7zXZ\x00", "xz archive", "xz"),
+    (0, b"\x28\xb5\x2f\xfd", "Zstandard archive", "zst"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7-Zip archive", "7z"),
+    (0, b"Rar!\x1a\x07", "RAR archive", "rar"),
+    (257, b"ustar", "tar archive", "tar"),
+    (0, b"\x7fELF", "ELF executable", "elf"),
+    (0, b"MZ", "Windows executable", "exe"),
+    (0, b"\xcf\xfa\xed\xfe", "Mach-O executable", "macho"),
+    (0, b"\x00asm", "WebAssembly module", "wasm"),
+    (0, b"SQLite format 3\x00", "SQLite database", "sqlite"),
+    (0, b"ID3", "MP3 audio", "mp3"),
+    (0, b"OggS", "Ogg media", "ogg"),
+    (0, b"fLaC", "FLAC audio", "flac"),
+    (8, b"WAVE", "WAV audio", "wav"),
+    (4, b"ftyp", "MP4 video", "mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "Matroska video", "mkv"),
+    (0, b"wOFF", "WOFF font", "woff"),
+    (0, b"wOF2", "WOFF2 font", "woff2"),
+];
+
+/// Detects the file type of a body from its magic bytes.
+pub fn detect(body: &[u8]) -> Option<FileType> {
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, ..)| body.get(*offset..offset + magic.len()) == Some(*magic))
+        .map(|&(_, _, name, extension)| FileType { name, extension })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let png = detect(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR");
+        assert_eq!(png.map(|t| t.extension), Some("png"));
+        let webp = detect(b"RIFF\x00\x00\x00\x00WEBPVP8 ");
+        assert_eq!(webp.map(|t| t.name), Some("WebP image"));
+        assert_eq!(detect(b"%PDF-1.7").map(|t| t.extension), Some("pdf"));
+        assert_eq!(detect(b"{\"a\": 1}"), None);
+        assert_eq!(detect(b""), None);
+    }
+}
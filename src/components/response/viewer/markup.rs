@@ -0,0 +1,296 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use quick_xml::{events::Event, Reader};
+
+use super::{Fragments, Kind};
+
+/// HTML elements that never have content or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// HTML elements whose text is not markup and must not be escaped.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// A piece of a markup document, as laid out by the pretty printer.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    Close(String),
+    Empty {
+        name: String,
+        attributes: Vec<(String, String)>,
+        end: &'static str,
+    },
+    Text(String),
+    Comment(String),
+    /// Declarations, doctypes, processing instructions and CDATA, shown verbatim.
+    Markup(String),
+}
+
+/// Pretty-prints an XML document with one element per line.
+pub fn pretty_xml(body: &[u8]) -> Result<Vec<Fragments>, String> {
+    let mut reader = Reader::from_reader(body);
+    reader.config_mut().trim_text(true);
+    let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    let attributes = |e: &quick_xml::events::BytesStart| {
+        e.attributes()
+            .map(|attribute| {
+                attribute
+                    .map(|a| (lossy(a.key.as_ref()), lossy(&a.value)))
+                    .map_err(|err| err.to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let mut tokens = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        let token = match reader
+            .read_event_into(&mut buf)
+            .map_err(|err| format!("{err} at byte {}", reader.error_position()))?
+        {
+            Event::Start(e) => Token::Open {
+                name: lossy(e.name().as_ref()),
+                attributes: attributes(&e)?,
+            },
+            Event::End(e) => Token::Close(lossy(e.name().as_ref())),
+            Event::Empty(e) => Token::Empty {
+                name: lossy(e.name().as_ref()),
+                attributes: attributes(&e)?,
+                end: "/>",
+            },
+            Event::Text(e) => Token::Text(lossy(&e)),
+            Event::Comment(e) => Token::Comment(lossy(&e)),
+            Event::CData(e) => Token::Markup(format!("<![CDATA[{}]]>", lossy(&e))),
+            Event::Decl(e) => Token::Markup(format!("<?{}?>", lossy(&e))),
+            Event::PI(e) => Token::Markup(format!("<?{}?>", lossy(&e))),
+            Event::DocType(e) => Token::Markup(format!("<!DOCTYPE {}>", lossy(&e))),
+            Event::Eof => break,
+        };
+        tokens.push(token);
+        buf.clear();
+    }
+    Ok(layout(&tokens))
+}
+
+/// Pretty-prints an HTML document, as parsed by a browser-grade parser so that sloppy markup
+/// still comes out well-formed.
+pub fn pretty_html(mut body: &[u8]) -> Result<Vec<Fragments>, String> {
+    let dom = parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut body)
+        .map_err(|err| err.to_string())?;
+    let mut tokens = Vec::new();
+    walk(&dom.document, false, &mut tokens);
+    Ok(layout(&tokens))
+}
+
+fn walk(handle: &Handle, raw_text: bool, tokens: &mut Vec<Token>) {
+    match &handle.data {
+        NodeData::Document => {}
+        NodeData::Doctype { name, .. } => tokens.push(Token::Markup(format!("<!DOCTYPE {name}>"))),
+        NodeData::Text { contents } => {
+            let text = contents.borrow();
+            if raw_text {
+                tokens.push(Token::Text(text.to_string()));
+            } else {
+                tokens.push(Token::Text(escape(&text, false)));
+            }
+        }
+        NodeData::Comment { contents } => tokens.push(Token::Comment(contents.to_string())),
+        NodeData::ProcessingInstruction { target, contents } => {
+            tokens.push(Token::Markup(format!("<?{target} {contents}>")))
+        }
+        NodeData::Element { name, attrs, .. } => {
+            let name = name.local.to_string();
+            let attributes = attrs
+                .borrow()
+                .iter()
+                .map(|a| (a.name.local.to_string(), escape(&a.value, true)))
+                .collect();
+            if VOID_ELEMENTS.contains(&name.as_str()) {
+                tokens.push(Token::Empty {
+                    name,
+                    attributes,
+                    end: ">",
+                });
+                return;
+            }
+            let raw_text = RAW_TEXT_ELEMENTS.contains(&name.as_str());
+            tokens.push(Token::Open {
+                name: name.clone(),
+                attributes,
+            });
+            for child in handle.children.borrow().iter() {
+                walk(child, raw_text, tokens);
+            }
+            tokens.push(Token::Close(name));
+            return;
+        }
+    }
+    for child in handle.children.borrow().iter() {
+        walk(child, raw_text, tokens);
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    if attribute {
+        text.replace('"', "&quot;")
+    } else {
+        text
+    }
+}
+
+/// Lays tokens out one per line, indented by depth. Elements holding nothing but a short piece
+/// of text are kept on a single line.
+fn layout(tokens: &[Token]) -> Vec<Fragments> {
+    let mut lines = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let indent = (Kind::Plain, "  ".repeat(depth));
+        match &tokens[i] {
+            Token::Open { name, attributes } => {
+                let mut line = vec![indent];
+                push_tag(&mut line, name, attributes, ">");
+                match (tokens.get(i + 1), tokens.get(i + 2)) {
+                    (Some(Token::Close(_)), _) => {
+                        push_close(&mut line, name);
+                        i += 1;
+                    }
+                    (Some(Token::Text(text)), Some(Token::Close(_)))
+                        if !text.trim().contains('\n') =>
+                    {
+                        line.push((Kind::Plain, text.trim().to_string()));
+                        push_close(&mut line, name);
+                        i += 2;
+                    }
+                    _ => depth += 1,
+                }
+                lines.push(line);
+            }
+            Token::Close(name) => {
+                depth = depth.saturating_sub(1);
+                let mut line = vec![(Kind::Plain, "  ".repeat(depth))];
+                push_close(&mut line, name);
+                lines.push(line);
+            }
+            Token::Empty {
+                name,
+                attributes,
+                end,
+            } => {
+                let mut line = vec![indent];
+                push_tag(&mut line, name, attributes, end);
+                lines.push(line);
+            }
+            Token::Text(text) => lines.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|line| vec![indent.clone(), (Kind::Plain, line.to_string())]),
+            ),
+            Token::Comment(text) => {
+                let comment = format!("<!--{text}-->");
+                lines.extend(
+                    comment
+                        .lines()
+                        .map(|line| vec![indent.clone(), (Kind::Hint, line.to_string())]),
+                );
+            }
+            Token::Markup(text) => lines.extend(
+                text.lines()
+                    .map(|line| vec![indent.clone(), (Kind::Hint, line.to_string())]),
+            ),
+        }
+        i += 1;
+    }
+    lines
+}
+
+fn push_tag(line: &mut Fragments, name: &str, attributes: &[(String, String)], end: &str) {
+    line.push((Kind::Punctuation, "<".into()));
+    line.push((Kind::Key, name.into()));
+    for (key, value) in attributes {
+        line.push((Kind::Plain, " ".into()));
+        line.push((Kind::Literal, key.clone()));
+        line.push((Kind::Punctuation, "=".into()));
+        line.push((Kind::String, format!("\"{value}\"")));
+    }
+    line.push((Kind::Punctuation, end.into()));
+}
+
+fn push_close(line: &mut Fragments, name: &str) {
+    line.push((Kind::Punctuation, "</".into()));
+    line.push((Kind::Key, name.into()));
+    line.push((Kind::Punctuation, ">".into()));
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn text(lines: Vec<Fragments>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|line| line.into_iter().map(|(_, text)| text).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_pretty_xml() {
+        let body = br#"<?xml version="1.0"?><feed><entry id="1"><title>Hi &amp; bye</title><link href="/a"/></entry><!-- end --></feed>"#;
+        assert_eq!(
+            text(pretty_xml(body).unwrap()),
+            vec![
+                r#"<?xml version="1.0"?>"#,
+                "<feed>",
+                r#"  <entry id="1">"#,
+                "    <title>Hi &amp; bye</title>",
+                r#"    <link href="/a"/>"#,
+                "  </entry>",
+                "  <!-- end -->",
+                "</feed>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pretty_xml_reports_mismatched_tags() {
+        assert!(pretty_xml(b"<a><b></a>").is_err());
+    }
+
+    #[test]
+    fn test_pretty_html_fixes_up_markup() {
+        let body = b"<!doctype html><title>T</title><p class=x>a<br>b";
+        assert_eq!(
+            text(pretty_html(body).unwrap()),
+            vec![
+                "<!DOCTYPE html>",
+                "<html>",
+                "  <head>",
+                "    <title>T</title>",
+                "  </head>",
+                "  <body>",
+                r#"    <p class="x">"#,
+                "      a",
+                "      <br>",
+                "      b",
+                "    </p>",
+                "  </body>",
+                "</html>",
+            ]
+        );
+    }
+}
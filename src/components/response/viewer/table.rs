@@ -0,0 +1,85 @@
+/// Columns never get wider than this, however long their longest cell is.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Delimiters recognised when sniffing a CSV body.
+const DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+/// A CSV body parsed into rows, with the first row taken as the header.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub widths: Vec<u16>,
+}
+
+impl Table {
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        let mut records = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(sniff_delimiter(body))
+            .from_reader(body)
+            .into_records()
+            .map(|record| {
+                record
+                    .map(|record| record.iter().map(str::to_string).collect::<Vec<_>>())
+                    .map_err(|err| err.to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let header = records.next().unwrap_or_default();
+        let rows: Vec<Vec<String>> = records.collect();
+
+        let columns = rows.iter().map(Vec::len).chain([header.len()]).max();
+        let widths = (0..columns.unwrap_or_default())
+            .map(|column| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+                    .min(MAX_COLUMN_WIDTH) as u16
+            })
+            .collect();
+        Ok(Self {
+            header,
+            rows,
+            widths,
+        })
+    }
+}
+
+/// Picks the delimiter that occurs most often on the first line.
+fn sniff_delimiter(body: &[u8]) -> u8 {
+    let first_line = body.split(|&b| b == b'\n').next().unwrap_or_default();
+    DELIMITERS
+        .into_iter()
+        .rev()
+        .max_by_key(|&d| first_line.iter().filter(|&&b| b == d).count())
+        .filter(|&d| first_line.contains(&d))
+        .unwrap_or(b',')
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let table = Table::parse(b"id,name\n1,\"Smith, J\"\n2\n").unwrap();
+        assert_eq!(table.header, vec!["id", "name"]);
+        assert_eq!(table.rows, vec![vec!["1", "Smith, J"], vec!["2"]]);
+        assert_eq!(table.widths, vec![2, 8]);
+    }
+
+    #[test]
+    fn test_sniff_delimiter() {
+        assert_eq!(sniff_delimiter(b"a;b;c\n1,5;2;3"), b';');
+        assert_eq!(sniff_delimiter(b"a\tb\n"), b'\t');
+        assert_eq!(sniff_delimiter(b"a,b\n"), b',');
+        assert_eq!(sniff_delimiter(b"single"), b',');
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{Fragments, Kind};
+
+/// Parses a YAML body, which may hold several documents.
+///
+/// Returns the body re-serialized and highlighted, plus the data as JSON for the tree view when
+/// it can be represented as JSON (YAML allows e.g. non-string keys, JSON does not).
+pub fn parse(body: &[u8]) -> Result<(Option<Value>, Vec<Fragments>), String> {
+    let documents = serde_yaml::Deserializer::from_slice(body)
+        .map(serde_yaml::Value::deserialize)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+
+    let mut text = String::new();
+    for (i, document) in documents.iter().enumerate() {
+        if i > 0 {
+            text.push_str("---\n");
+        }
+        text.push_str(&serde_yaml::to_string(document).map_err(|err| err.to_string())?);
+    }
+    let value = match documents.as_slice() {
+        [document] => serde_json::to_value(document).ok(),
+        documents => serde_json::to_value(documents).ok(),
+    };
+    Ok((value, highlight(&text)))
+}
+
+/// Highlights YAML line by line. This only needs to handle the block style that `serde_yaml`
+/// writes, not arbitrary YAML.
+fn highlight(text: &str) -> Vec<Fragments> {
+    // Indentation of the line that started a block scalar, whose more indented lines are text.
+    let mut block: Option<usize> = None;
+    text.lines()
+        .map(|line| {
+            let rest = line.trim_start();
+            let indent = line.len() - rest.len();
+            if let Some(start) = block {
+                if indent > start || rest.is_empty() {
+                    return vec![(Kind::String, line.to_string())];
+                }
+                block = None;
+            }
+
+            let mut fragments = vec![(Kind::Plain, line[..indent].to_string())];
+            let mut rest = rest;
+            if rest == "---" {
+                fragments.push((Kind::Hint, rest.into()));
+                return fragments;
+            }
+            while let Some(item) = rest.strip_prefix("- ") {
+                fragments.push((Kind::Punctuation, "- ".into()));
+                rest = item;
+            }
+            if rest == "-" {
+                fragments.push((Kind::Punctuation, rest.into()));
+                return fragments;
+            }
+            if let Some((key, value)) = split_key(rest) {
+                fragments.push((Kind::Key, key.into()));
+                fragments.push((Kind::Punctuation, ":".into()));
+                rest = value;
+            }
+            let kind = match rest.trim() {
+                "" => Kind::Plain,
+                "null" | "~" | "true" | "false" | "{}" | "[]" => Kind::Literal,
+                value if value.starts_with(['|', '>']) => {
+                    block = Some(indent);
+                    Kind::Punctuation
+                }
+                value if value.parse::<f64>().is_ok() => Kind::Number,
+                _ => Kind::String,
+            };
+            fragments.push((kind, rest.into()));
+            fragments
+        })
+        .collect()
+}
+
+/// Splits `key: value` into the key and what follows the colon.
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let end = match line.chars().next()? {
+        quote @ ('"' | '\'') => line[1..].find(quote)? + 2,
+        _ => line
+            .find(": ")
+            .or_else(|| line.strip_suffix(':').map(str::len))?,
+    };
+    line[end..]
+        .starts_with(':')
+        .then(|| (&line[..end], &line[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (value, lines) = parse(b"name:   api\ntags: [a, b]\ncount: 2\n").unwrap();
+        assert_eq!(
+            value,
+            Some(json!({"name": "api", "tags": ["a", "b"], "count": 2}))
+        );
+        assert_eq!(
+            lines[0],
+            vec![
+                (Kind::Plain, "".into()),
+                (Kind::Key, "name".into()),
+                (Kind::Punctuation, ":".into()),
+                (Kind::String, " api".into()),
+            ]
+        );
+        assert_eq!(
+            lines[2],
+            vec![
+                (Kind::Plain, "".into()),
+                (Kind::Punctuation, "- ".into()),
+                (Kind::String, "a".into()),
+            ]
+        );
+        assert_eq!(lines[4][3], (Kind::Number, " 2".into()));
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        let (value, lines) = parse(b"a: 1\n---\nb: 2\n").unwrap();
+        assert_eq!(value, Some(json!([{"a": 1}, {"b": 2}])));
+        assert_eq!(
+            lines[1],
+            vec![(Kind::Plain, "".into()), (Kind::Hint, "---".into())]
+        );
+    }
+
+    #[test]
+    fn test_non_string_keys_have_no_tree() {
+        let (value, lines) = parse(b"? [1, 2]\n: pair\n").unwrap();
+        assert_eq!(value, None);
+        assert!(!lines.is_empty());
+    }
+}
@@ -0,0 +1,475 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Padding, Paragraph};
+use ratatui::Frame;
+use serde_json::Value;
+use tui_textarea::TextArea;
+
+use crate::action::Action;
+use crate::app::Mode;
+use crate::components::response::format_size;
+use crate::components::Component;
+use crate::executor::websocket::{Direction, Handshake, Payload, WebSocket, WsEvent, WsMessage};
+
+/// Status code sent when the user closes the connection.
+const NORMAL_CLOSURE: u16 = 1000;
+
+/// How the composer's text is turned into a message.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    #[default]
+    Text,
+    /// Text that must be valid JSON, sent compacted.
+    Json,
+    /// Bytes typed in hex, e.g. `de ad be ef`.
+    Binary,
+}
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    Connecting,
+    Open(Handshake),
+    Closed,
+    Failed(String),
+}
+
+/// A WebSocket connection made from a tab, with a log of its messages and a composer.
+#[derive(Default)]
+pub struct WebSocketPane {
+    focused: bool,
+    state: State,
+    socket: Option<WebSocket>,
+    log: Vec<WsMessage>,
+    /// First line of the log on screen.
+    offset: usize,
+    /// Keeps the newest messages in view as they arrive, until the log is scrolled up.
+    follow: bool,
+    log_height: usize,
+    composer: TextArea<'static>,
+    composing: bool,
+    kind: MessageKind,
+    composer_error: Option<String>,
+}
+
+impl MessageKind {
+    fn next(self) -> Self {
+        match self {
+            Self::Text => Self::Json,
+            Self::Json => Self::Binary,
+            Self::Binary => Self::Text,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Json => "JSON",
+            Self::Binary => "Binary (hex)",
+        }
+    }
+
+    /// Turns what was typed into the message to send.
+    pub fn payload(self, input: &str) -> Result<Payload, String> {
+        match self {
+            Self::Text => Ok(Payload::Text(input.to_string())),
+            Self::Json => serde_json::from_str::<Value>(input)
+                .map(|value| Payload::Text(value.to_string()))
+                .map_err(|err| format!("Invalid JSON: {err}")),
+            Self::Binary => {
+                let digits: Vec<u8> = input.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+                if !digits.len().is_multiple_of(2) {
+                    return Err("Hex needs two digits per byte".into());
+                }
+                digits
+                    .chunks(2)
+                    .map(|pair| {
+                        std::str::from_utf8(pair)
+                            .ok()
+                            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                            .ok_or_else(|| {
+                                format!("Not a hex byte: {}", String::from_utf8_lossy(pair))
+                            })
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Payload::Binary)
+            }
+        }
+    }
+}
+
+impl WebSocketPane {
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Whether a connection has been made or attempted since the pane was last reset.
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, State::Idle)
+    }
+
+    pub fn is_connecting(&self) -> bool {
+        matches!(self.state, State::Connecting)
+    }
+
+    /// Starts showing a new connection, dropping the previous one and its log.
+    pub fn connect(&mut self, socket: WebSocket) {
+        self.socket = Some(socket);
+        self.state = State::Connecting;
+        self.log.clear();
+        self.offset = 0;
+        self.follow = true;
+        self.composer_error = None;
+    }
+
+    /// Drops the connection without a closing handshake.
+    pub fn disconnect(&mut self, reason: &str) {
+        if self.socket.take().is_some() {
+            self.state = State::Failed(reason.into());
+        }
+    }
+
+    /// Drops the connection and goes back to showing nothing, for when an HTTP request is sent.
+    pub fn reset(&mut self) {
+        self.socket = None;
+        self.state = State::Idle;
+        self.log.clear();
+        self.composing = false;
+    }
+
+    /// Picks up whatever happened on the connection since the last call.
+    pub fn poll(&mut self) {
+        while let Some(event) = self.socket.as_mut().and_then(WebSocket::poll) {
+            match event {
+                WsEvent::Connected(handshake) => self.state = State::Open(handshake),
+                WsEvent::Message(message) => self.log.push(message),
+                WsEvent::Closed => {
+                    self.state = State::Closed;
+                    self.socket = None;
+                }
+                WsEvent::Failed(err) => {
+                    self.state = State::Failed(err);
+                    self.socket = None;
+                }
+            }
+        }
+        if self.follow {
+            self.offset = self.log.len().saturating_sub(self.log_height);
+        }
+    }
+
+    fn send(&mut self, payload: Payload) {
+        if let (Some(socket), State::Open(_)) = (&self.socket, &self.state) {
+            socket.send(payload);
+        }
+    }
+
+    fn send_composed(&mut self) {
+        let input = self.composer.lines().join("\n");
+        match self.kind.payload(&input) {
+            Ok(payload) => {
+                self.send(payload);
+                self.composer = TextArea::default();
+                self.composer_error = None;
+            }
+            Err(err) => self.composer_error = Some(err),
+        }
+    }
+
+    fn scroll(&mut self, delta: isize) {
+        let max = self.log.len().saturating_sub(self.log_height);
+        self.offset = self.offset.saturating_add_signed(delta).min(max);
+        self.follow = self.offset == max;
+    }
+
+    fn handle_composer_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Enter => self.send_composed(),
+            KeyCode::Tab => self.kind = self.kind.next(),
+            KeyCode::Esc => {
+                self.composing = false;
+                return Some(Action::SwitchMode(Mode::Response));
+            }
+            _ => {
+                if self.composer.input(key) {
+                    self.composer_error = None;
+                }
+            }
+        }
+        None
+    }
+
+    fn block(&self, title: impl Into<Line<'static>>, focused: bool) -> Block<'static> {
+        let border_style = if focused {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Block::bordered()
+            .border_style(border_style)
+            .title(title)
+            .padding(Padding::horizontal(1))
+    }
+
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        let line = match &self.state {
+            State::Idle => Line::raw("Not connected").dark_gray(),
+            State::Connecting => Line::from(vec![
+                "Connecting…".yellow(),
+                "  <Esc> to cancel".dark_gray(),
+            ]),
+            State::Open(handshake) => {
+                let mut line = Line::from(vec![
+                    format!("{} Connected", handshake.status).green().bold(),
+                    format!("  {} ms", handshake.elapsed.as_millis()).dark_gray(),
+                ]);
+                if let Some(protocol) = &handshake.protocol {
+                    line.push_span(format!("  protocol {protocol}").cyan());
+                }
+                line
+            }
+            State::Closed => Line::raw("Connection closed").yellow(),
+            State::Failed(err) => Line::raw(err.clone()).red(),
+        };
+        line.render(area, buf);
+    }
+
+    fn render_log(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = self.block(format!(" {} messages ", self.log.len()), self.focused);
+        self.log_height = block.inner(area).height as usize;
+        if self.follow {
+            self.offset = self.log.len().saturating_sub(self.log_height);
+        }
+        let lines: Vec<Line> = self
+            .log
+            .iter()
+            .skip(self.offset)
+            .take(self.log_height)
+            .map(log_line)
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn render_composer(&mut self, area: Rect, buf: &mut Buffer) {
+        let (title, title_style) = match &self.composer_error {
+            Some(err) => (format!(" {err} "), Style::default().fg(Color::Red)),
+            None => (format!(" {} ", self.kind.label()), Style::default()),
+        };
+        let block = self.block(
+            Span::styled(title, title_style),
+            self.focused && self.composing,
+        );
+        self.composer.set_placeholder_text(match self.kind {
+            MessageKind::Text => "Message",
+            MessageKind::Json => "{\"type\": \"subscribe\"}",
+            MessageKind::Binary => "de ad be ef",
+        });
+        self.composer.set_cursor_line_style(Style::default());
+        let cursor_style = if self.composing {
+            Style::default().reversed()
+        } else {
+            Style::default()
+        };
+        self.composer.set_cursor_style(cursor_style);
+        self.composer.set_block(block);
+        self.composer.render(area, buf);
+    }
+
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        let hint = match &self.state {
+            _ if self.composing => "<Enter> send  <Tab> message type  <Esc> done",
+            State::Open(_) => "<Enter> compose  <Tab> message type  <p> ping  <c> close",
+            _ => "<Enter> in the URL bar to connect",
+        };
+        Line::raw(hint).centered().render(area, buf);
+    }
+}
+
+impl Widget for &mut WebSocketPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let vertical = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ]);
+        let [status_area, log_area, composer_area, footer_area] = vertical.areas(area);
+
+        self.render_status(status_area, buf);
+        self.render_log(log_area, buf);
+        self.render_composer(composer_area, buf);
+        self.render_footer(footer_area, buf);
+    }
+}
+
+impl Component for WebSocketPane {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        if self.composing {
+            return Ok(self.handle_composer_key(key));
+        }
+        let page = self.log_height.max(1) as isize;
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('i') if matches!(self.state, State::Open(_)) => {
+                self.composing = true;
+                // Typing shouldn't trigger the bindings of the response pane.
+                return Ok(Some(Action::SwitchMode(Mode::Prompt)));
+            }
+            KeyCode::Tab => self.kind = self.kind.next(),
+            KeyCode::Char('p') => self.send(Payload::Ping(vec![])),
+            KeyCode::Char('c') => self.send(Payload::Close(Some((NORMAL_CLOSURE, String::new())))),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.scroll(isize::MAX),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Render {
+            self.poll();
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        frame.render_widget(self, area);
+
+        Ok(())
+    }
+}
+
+/// One line of the log: time, direction, type, size and as much of the content as fits.
+fn log_line(message: &WsMessage) -> Line<'static> {
+    let (arrow, color) = match message.direction {
+        Direction::Sent => ("→", Color::Cyan),
+        Direction::Received => ("←", Color::Green),
+    };
+    let (kind, content) = match &message.payload {
+        Payload::Text(text) => ("text", text.replace('\n', "⏎")),
+        Payload::Binary(data) => ("binary", hex_preview(data)),
+        Payload::Ping(data) => ("ping", hex_preview(data)),
+        Payload::Pong(data) => ("pong", hex_preview(data)),
+        Payload::Close(None) => ("close", "no status".into()),
+        Payload::Close(Some((code, reason))) => {
+            let mut content = format!("{code} {}", close_code_name(*code));
+            if !reason.is_empty() {
+                content.push_str(&format!(": {reason}"));
+            }
+            ("close", content)
+        }
+    };
+    Line::from(vec![
+        format_time(message.time).dark_gray(),
+        format!(" {arrow} ").fg(color).bold(),
+        format!("{kind:<6} ").fg(color),
+        format!("{:>9}  ", format_size(message.payload.size())).dark_gray(),
+        content.into(),
+    ])
+}
+
+fn hex_preview(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats the time of day in UTC with milliseconds, e.g. `07:28:00.125`.
+pub fn format_time(time: SystemTime) -> String {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let seconds = millis / 1000 % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+/// The meaning of a close status code, as registered with IANA.
+pub fn close_code_name(code: u16) -> &'static str {
+    match code {
+        1000 => "Normal closure",
+        1001 => "Going away",
+        1002 => "Protocol error",
+        1003 => "Unsupported data",
+        1005 => "No status received",
+        1006 => "Abnormal closure",
+        1007 => "Invalid payload data",
+        1008 => "Policy violation",
+        1009 => "Message too big",
+        1010 => "Mandatory extension",
+        1011 => "Internal error",
+        1012 => "Service restart",
+        1013 => "Try again later",
+        1014 => "Bad gateway",
+        1015 => "TLS handshake",
+        3000..=3999 => "Registered",
+        4000..=4999 => "Application",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        assert_eq!(
+            MessageKind::Json.payload("{ \"a\": [1, 2] }"),
+            Ok(Payload::Text("{\"a\":[1,2]}".into()))
+        );
+        assert!(MessageKind::Json.payload("{").is_err());
+        assert_eq!(
+            MessageKind::Binary.payload("DE ad\tbe ef"),
+            Ok(Payload::Binary(vec![0xde, 0xad, 0xbe, 0xef]))
+        );
+        assert_eq!(
+            MessageKind::Binary.payload("abc"),
+            Err("Hex needs two digits per byte".into())
+        );
+        assert_eq!(
+            MessageKind::Binary.payload("zz"),
+            Err("Not a hex byte: zz".into())
+        );
+    }
+
+    #[test]
+    fn test_log_line() {
+        let message = WsMessage {
+            direction: Direction::Received,
+            time: UNIX_EPOCH + Duration::from_millis(86400 * 1000 + 3_723_045),
+            payload: Payload::Close(Some((1001, "restarting".into()))),
+        };
+        let text: String = log_line(&message)
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(
+            text,
+            "01:02:03.045 ← close       12 B  1001 Going away: restarting"
+        );
+    }
+}
@@ -11,16 +11,25 @@ use ratatui::style::{Color, Modifier, Style};
 use serde::{de::Deserializer, Deserialize};
 use tracing::error;
 
-use crate::{action::Action, app::Mode};
+use crate::{
+    action::Action,
+    app::Mode,
+    executor::{body::DownloadSettings, dns::DnsSettings, proxy::ProxySettings, tls::TlsSettings},
+};
 
 const CONFIG: &str = include_str!("../.config/config.toml");
 
+const DEFAULT_WORKSPACE: &str = "default";
+
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct AppConfig {
     #[serde(default)]
     pub data_dir: PathBuf,
     #[serde(default)]
     pub config_dir: PathBuf,
+    /// Name of the workspace whose state, such as cookies, is loaded.
+    #[serde(default)]
+    pub workspace: String,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -31,6 +40,22 @@ pub struct Config {
     pub keybindings: KeyBindings,
     #[serde(default)]
     pub styles: Styles,
+    /// TLS settings keyed by host pattern.
+    #[serde(default)]
+    pub tls: HashMap<String, TlsSettings>,
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    #[serde(default)]
+    pub dns: DnsSettings,
+    #[serde(default)]
+    pub downloads: DownloadSettings,
+}
+
+impl AppConfig {
+    /// Directory holding the state of the current workspace.
+    pub fn workspace_dir(&self) -> PathBuf {
+        self.data_dir.join("workspaces").join(&self.workspace)
+    }
 }
 
 lazy_static! {
@@ -52,7 +77,8 @@ impl Config {
         let config_dir = get_config_dir();
         let mut builder = config::Config::builder()
             .set_default("data_dir", data_dir.to_str().unwrap())?
-            .set_default("config_dir", config_dir.to_str().unwrap())?;
+            .set_default("config_dir", config_dir.to_str().unwrap())?
+            .set_default("workspace", DEFAULT_WORKSPACE)?;
 
         let config_files = [
             ("config.json5", config::FileFormat::Json5),
@@ -507,7 +533,7 @@ mod tests {
             c.keybindings
                 .get(&Mode::Home)
                 .unwrap()
-                .get(&parse_key_sequence("<q>").unwrap_or_default())
+                .get(&parse_key_sequence("<Ctrl-d>").unwrap_or_default())
                 .unwrap(),
             &Action::Quit
         );
@@ -595,4 +621,24 @@ mod tests {
             KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT)
         );
     }
+
+    #[test]
+    fn test_tls_settings() -> Result<()> {
+        let source = r#"
+            [tls."*.Internal.example.com"]
+            ca_certs = ["/etc/ssl/internal-ca.pem"]
+            insecure = true
+        "#;
+        let c: Config = config::Config::builder()
+            .add_source(config::File::from_str(source, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize()?;
+        let settings = TlsSettings::for_host(&c.tls, "api.internal.example.com").unwrap();
+        assert_eq!(
+            settings.ca_certs,
+            vec![PathBuf::from("/etc/ssl/internal-ca.pem")]
+        );
+        assert!(settings.insecure);
+        Ok(())
+    }
 }
@@ -0,0 +1,1660 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    client::conn::{http1, http2},
+    header,
+    Request,
+    Response,
+    Uri,
+    Version,
+};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+    sync::mpsc::UnboundedSender,
+    time::{sleep, timeout},
+};
+use tokio_rustls::TlsConnector;
+use tracing::{debug, error};
+use url::Url;
+
+use self::body::{DownloadSettings, Progress, Sink, Spilled};
+use self::compression::{BodyCompression, Decoding, ACCEPT_ENCODING};
+use self::cookies::{now, CookieJar};
+use self::dns::DnsSettings;
+use self::proxy::{Proxy, ProxyKind, ProxySettings};
+use self::tls::{CertificateInfo, TlsSettings};
+
+pub mod body;
+pub mod compression;
+pub mod cookies;
+pub mod dns;
+pub mod grpc;
+pub mod proxy;
+pub mod redirect;
+pub mod retry;
+pub mod sse;
+pub mod tls;
+pub mod unix;
+pub mod websocket;
+
+/// How often the progress of a body is reported while it arrives.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most bytes kept of each side of an exchange for the Raw tab.
+pub const MAX_RAW: usize = 1024 * 1024;
+
+/// A transport the executor can speak HTTP over.
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// An HTTP request as composed in the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub options: RequestOptions,
+}
+
+/// Settings that change how a single request is sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestOptions {
+    /// Attach cookies from the jar and store the ones the response sets.
+    pub cookies: bool,
+    pub follow_redirects: bool,
+    pub max_redirects: u32,
+    /// Keep the method and body when following a 301, 302 or 303 instead of switching to `GET`.
+    /// 307 and 308 always keep them.
+    pub keep_method: bool,
+    /// Keep sending the `Authorization` header when a redirect leads to another host.
+    pub forward_authorization: bool,
+    /// Time allowed in milliseconds for resolving the host and setting up the connection,
+    /// including any proxy and the TLS handshake, or 0 for no limit.
+    pub connect_timeout: u32,
+    /// Time allowed in milliseconds for each wait on the server once connected, or 0 for no
+    /// limit.
+    pub read_timeout: u32,
+    /// Time allowed in milliseconds for the whole exchange, redirects and retries included, or 0
+    /// for no limit.
+    pub total_timeout: u32,
+    /// Attempts at each request before giving up, so 1 never retries.
+    pub max_attempts: u32,
+    /// Milliseconds to wait before the first retry, doubling for each one after it.
+    pub retry_backoff: u32,
+    pub retry_on_connection_error: bool,
+    /// Statuses worth retrying, after the wait given by `Retry-After` if the server sends one.
+    pub retry_statuses: Vec<u16>,
+    /// Path of a Unix socket to send the request over instead of connecting to the host, or
+    /// empty to connect as usual.
+    pub unix_socket: String,
+    pub http_version: HttpVersion,
+    /// How to compress the body before sending it, with a matching `Content-Encoding` header.
+    pub compress_body: BodyCompression,
+}
+
+/// The HTTP version a request is sent with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+pub enum HttpVersion {
+    #[strum(to_string = "HTTP/1.0")]
+    Http10,
+    #[default]
+    #[strum(to_string = "HTTP/1.1")]
+    Http11,
+    /// HTTP/2 if the server agrees to it through ALPN, HTTP/1.1 otherwise. Plain HTTP always
+    /// gets HTTP/1.1, as there is no ALPN without TLS.
+    #[strum(to_string = "HTTP/2")]
+    Http2,
+    /// HTTP/2 from the first byte, which is how h2c servers are reached over plain HTTP.
+    #[strum(to_string = "HTTP/2 with prior knowledge")]
+    Http2PriorKnowledge,
+}
+
+/// Sends requests, keeping the state shared between them such as cookies.
+#[derive(Debug, Default, Clone)]
+pub struct Client {
+    cookies: Arc<Mutex<CookieJar>>,
+    tls: HashMap<String, TlsSettings>,
+    proxy: ProxySettings,
+    dns: DnsSettings,
+    downloads: DownloadSettings,
+}
+
+/// A fully received HTTP response.
+#[derive(Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub reason: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub elapsed: Duration,
+    pub timing: Timing,
+    /// Responses that redirected to this one, oldest first.
+    pub redirects: Vec<HttpResponse>,
+    /// How many times the request was sent before this response stood.
+    #[serde(default)]
+    pub attempts: u32,
+    /// What only HTTP/2 has, if the response came over it.
+    #[serde(default)]
+    pub http2: Option<Http2Details>,
+    /// What went over the connection, if it spoke HTTP/1.x. Not kept in the history.
+    #[serde(skip)]
+    pub raw: Option<RawExchange>,
+    /// The body as it arrived, if the server compressed it and `body` holds it decoded.
+    #[serde(default)]
+    pub decoding: Option<Decoding>,
+    /// Where the body went if it was too large to keep in memory, in which case `body` holds
+    /// only its start. Never saved, as the file goes away with the response.
+    #[serde(skip)]
+    pub spilled: Option<Spilled>,
+}
+
+/// The bytes of an HTTP/1.x exchange exactly as they went over the connection, inside any TLS.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RawExchange {
+    pub request: Vec<u8>,
+    pub response: Vec<u8>,
+    /// Whether either side was cut short at [`MAX_RAW`] bytes.
+    pub truncated: bool,
+}
+
+/// The parts of an HTTP/2 exchange that have no HTTP/1.1 counterpart.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Http2Details {
+    /// The id of the stream the exchange went over, or `None` when it isn't known. hyper keeps
+    /// its streams to itself, so this is `None` for now.
+    #[serde(default)]
+    pub stream_id: Option<u32>,
+    /// The request's `:method`, `:scheme`, `:authority` and `:path`, then the response's
+    /// `:status`.
+    pub pseudo_headers: Vec<(String, String)>,
+}
+
+/// The sending half of a connection, for whichever version it speaks.
+enum Sender {
+    Http1(http1::SendRequest<Full<Bytes>>),
+    Http2(http2::SendRequest<Full<Bytes>>),
+}
+
+/// A response whose head has arrived, with the body still to be read.
+pub struct ResponseStream {
+    /// The response so far, with an empty body.
+    pub head: HttpResponse,
+    body: Incoming,
+    /// Time allowed in milliseconds for each piece of the body to arrive, or 0 for no limit.
+    read_timeout: u32,
+    started: Instant,
+    request_started: Instant,
+    times: Arc<Mutex<IoTimes>>,
+    /// Bytes of the body kept in memory before the rest goes to a file.
+    memory_limit: usize,
+}
+
+/// How long each phase of a request took, along with what the connection ended up being.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    /// Name resolution, or `None` when the host is an IP address.
+    pub dns: Option<Duration>,
+    pub connect: Duration,
+    /// TLS handshake, or `None` for plain HTTP.
+    pub tls: Option<Duration>,
+    pub request_write: Duration,
+    /// Time from the request being written to the first byte of the response.
+    pub first_byte: Duration,
+    pub download: Duration,
+    pub remote_addr: Option<SocketAddr>,
+    /// Whether the request went over a connection left open by an earlier one. Always false
+    /// for now, as each request opens its own connection.
+    #[serde(default)]
+    pub reused: bool,
+    pub tls_version: Option<String>,
+    pub alpn: Option<String>,
+    /// The proxy the request went through, without its credentials.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Whether the server certificate went unverified because of the `insecure` TLS setting.
+    #[serde(default)]
+    pub insecure: bool,
+    /// The Unix socket the request went over, if it didn't connect to the host.
+    #[serde(default)]
+    pub socket: Option<String>,
+    /// The host override or DNS server that resolved the host, if not the system resolver.
+    #[serde(default)]
+    pub resolved_by: Option<String>,
+    /// The chain the server presented, leaf first. Not kept in the history.
+    #[serde(skip)]
+    pub certificates: Vec<CertificateInfo>,
+}
+
+/// When bytes first and last moved over a connection, and the first [`MAX_RAW`] of them each way,
+/// as recorded by [`Timed`].
+#[derive(Debug, Default)]
+struct IoTimes {
+    last_write: Option<Instant>,
+    first_read: Option<Instant>,
+    written: Vec<u8>,
+    read: Vec<u8>,
+    truncated: bool,
+}
+
+/// Wraps a transport to record when the request finished writing and the response started
+/// arriving, and what was sent and received, none of which hyper exposes.
+struct Timed<T> {
+    inner: T,
+    times: Arc<Mutex<IoTimes>>,
+}
+
+impl HttpRequest {
+    pub fn get(url: &str) -> Self {
+        Self {
+            method: "GET".into(),
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+}
+
+impl RequestOptions {
+    /// The Unix socket to send the request over, if one is set.
+    pub fn socket(&self) -> Option<PathBuf> {
+        let socket = self.unix_socket.trim();
+        (!socket.is_empty()).then(|| PathBuf::from(socket))
+    }
+}
+
+impl HttpVersion {
+    /// The version after this one, wrapping around, for cycling through them in the UI.
+    pub fn next(self) -> Self {
+        let versions: Vec<Self> = Self::iter().collect();
+        let i = versions.iter().position(|v| *v == self).unwrap_or_default();
+        versions[(i + 1) % versions.len()]
+    }
+
+    /// The protocols to offer in the TLS handshake.
+    fn alpn(self) -> Vec<Vec<u8>> {
+        match self {
+            Self::Http10 | Self::Http11 => vec![b"http/1.1".to_vec()],
+            Self::Http2 => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            Self::Http2PriorKnowledge => vec![b"h2".to_vec()],
+        }
+    }
+}
+
+impl Sender {
+    async fn send_request(&mut self, request: Request<Full<Bytes>>) -> Result<Response<Incoming>> {
+        Ok(match self {
+            Self::Http1(sender) => sender.send_request(request).await?,
+            Self::Http2(sender) => sender.send_request(request).await?,
+        })
+    }
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self {
+            cookies: true,
+            follow_redirects: true,
+            max_redirects: 10,
+            keep_method: false,
+            forward_authorization: false,
+            connect_timeout: 10_000,
+            read_timeout: 30_000,
+            total_timeout: 0,
+            max_attempts: 1,
+            retry_backoff: 500,
+            retry_on_connection_error: true,
+            retry_statuses: vec![429, 502, 503, 504],
+            unix_socket: String::new(),
+            http_version: HttpVersion::default(),
+            compress_body: BodyCompression::default(),
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Timed<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if buf.filled().len() > filled {
+            let mut times = self.times.lock().unwrap();
+            times.first_read.get_or_insert_with(Instant::now);
+            let IoTimes {
+                read, truncated, ..
+            } = &mut *times;
+            keep(read, &buf.filled()[filled..], truncated);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Timed<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(len)) = poll {
+            let mut times = self.times.lock().unwrap();
+            if times.first_read.is_none() {
+                times.last_write = Some(Instant::now());
+            }
+            let IoTimes {
+                written, truncated, ..
+            } = &mut *times;
+            keep(written, &buf[..len], truncated);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Adds bytes to a record, up to [`MAX_RAW`] of them.
+fn keep(record: &mut Vec<u8>, bytes: &[u8], truncated: &mut bool) {
+    let room = MAX_RAW.saturating_sub(record.len());
+    *truncated |= bytes.len() > room;
+    record.extend_from_slice(&bytes[..bytes.len().min(room)]);
+}
+
+// By hand, so that logging an action carrying a response doesn't print its whole body.
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = |len: usize| format!("{len} bytes");
+        f.debug_struct("HttpResponse")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("reason", &self.reason)
+            .field("version", &self.version)
+            .field("headers", &self.headers)
+            .field("body", &format_args!("{}", bytes(self.body.len())))
+            .field("elapsed", &self.elapsed)
+            .field("timing", &self.timing)
+            .field("redirects", &self.redirects)
+            .field("attempts", &self.attempts)
+            .field("http2", &self.http2)
+            .field(
+                "raw",
+                &self
+                    .raw
+                    .as_ref()
+                    .map(|raw| (bytes(raw.request.len()), bytes(raw.response.len()))),
+            )
+            .field(
+                "decoding",
+                &self.decoding.as_ref().map(|decoding| {
+                    (
+                        &decoding.encodings,
+                        bytes(decoding.encoded.len()),
+                        &decoding.error,
+                    )
+                }),
+            )
+            .field("spilled", &self.spilled)
+            .finish()
+    }
+}
+
+impl HttpResponse {
+    /// Returns the first header with the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The size of the whole body, which may be more than `body` holds.
+    pub fn size(&self) -> u64 {
+        match &self.spilled {
+            Some(spilled) => spilled.len,
+            None => self.body.len() as u64,
+        }
+    }
+
+    /// Returns the media type of the body without any parameters, e.g. `application/json`.
+    pub fn media_type(&self) -> Option<String> {
+        self.header("content-type").map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        })
+    }
+}
+
+impl Client {
+    pub fn new(cookies: CookieJar) -> Self {
+        Self {
+            cookies: Arc::new(Mutex::new(cookies)),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the TLS settings, keyed by host pattern as described on [`TlsSettings`].
+    pub fn tls(mut self, tls: HashMap<String, TlsSettings>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: ProxySettings) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Sets how host names are resolved, with the settings of the open workspace.
+    pub fn dns(mut self, dns: DnsSettings) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    pub fn cookies(&self) -> Arc<Mutex<CookieJar>> {
+        self.cookies.clone()
+    }
+
+    /// Sets how response bodies are received.
+    pub fn downloads(mut self, downloads: DownloadSettings) -> Self {
+        self.downloads = downloads;
+        self
+    }
+
+    /// Sends a request and waits for the complete response, following redirects and retrying if
+    /// the request's options allow.
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let total_timeout = request.options.total_timeout;
+        within(
+            total_timeout,
+            "The request",
+            self.follow_redirects(request, None),
+        )
+        .await
+    }
+
+    /// Sends a request like [`Client::send`], reporting how far along each body is as it
+    /// arrives.
+    pub async fn send_reporting(
+        &self,
+        request: HttpRequest,
+        progress: UnboundedSender<Progress>,
+    ) -> Result<HttpResponse> {
+        let total_timeout = request.options.total_timeout;
+        let sending = self.follow_redirects(request, Some(&progress));
+        within(total_timeout, "The request", sending).await
+    }
+
+    async fn follow_redirects(
+        &self,
+        mut request: HttpRequest,
+        progress: Option<&UnboundedSender<Progress>>,
+    ) -> Result<HttpResponse> {
+        let mut redirects = Vec::new();
+        loop {
+            let mut response = self.send_with_retries(&request, progress).await?;
+            let next = if request.options.follow_redirects {
+                redirect::next_request(&request, &response)?
+            } else {
+                None
+            };
+            let Some(next) = next else {
+                response.redirects = redirects;
+                return Ok(response);
+            };
+            if redirects.len() >= request.options.max_redirects as usize {
+                return Err(eyre!(
+                    "Stopped after {} redirects, the most allowed",
+                    redirects.len()
+                ));
+            }
+            redirects.push(response);
+            request = next;
+        }
+    }
+
+    /// Sends a request until it succeeds or its retry options give up on it.
+    async fn send_with_retries(
+        &self,
+        request: &HttpRequest,
+        progress: Option<&UnboundedSender<Progress>>,
+    ) -> Result<HttpResponse> {
+        let mut attempt = 1;
+        loop {
+            let outcome = self.send_once(request, progress).await;
+            let Some(delay) = retry::delay(&request.options, attempt, outcome.as_ref(), now())
+            else {
+                return outcome.map(|response| HttpResponse {
+                    attempts: attempt,
+                    ..response
+                });
+            };
+            match &outcome {
+                Ok(response) => debug!("Retrying after {} in {delay:?}", response.status),
+                Err(err) => debug!("Retrying after \"{err}\" in {delay:?}"),
+            }
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Sends a single request, without following redirects.
+    async fn send_once(
+        &self,
+        request: &HttpRequest,
+        progress: Option<&UnboundedSender<Progress>>,
+    ) -> Result<HttpResponse> {
+        self.start(request).await?.collect(progress).await
+    }
+
+    /// Sends a single request and waits for the head of the response, leaving the body to be
+    /// read as it arrives.
+    async fn start(&self, request: &HttpRequest) -> Result<ResponseStream> {
+        let started = Instant::now();
+        let (socket, url) = match unix::split_url(&request.url) {
+            Some(split) => split.map(|(socket, url)| (Some(socket), url))?,
+            None => (request.options.socket(), parse_url(&request.url)?),
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| eyre!("URL has no host: {url}"))?
+            .to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| eyre!("URL has no port: {url}"))?;
+
+        let mut timing = Timing::default();
+        let settings = TlsSettings::for_host(&self.tls, &host)
+            .cloned()
+            .unwrap_or_default();
+        let proxy = match socket {
+            Some(_) => None,
+            None => self.proxy.proxy_for(&url)?,
+        };
+        let options = &request.options;
+        let stream = match &socket {
+            Some(_) if url.scheme() == "https" => {
+                return Err(eyre!("HTTPS isn't supported over Unix sockets"))
+            }
+            Some(socket) => {
+                within(
+                    options.connect_timeout,
+                    "Connecting",
+                    unix::connect(socket, &mut timing),
+                )
+                .await?
+            }
+            None => {
+                within(
+                    options.connect_timeout,
+                    "Connecting",
+                    connect(
+                        &url,
+                        &host,
+                        port,
+                        &settings,
+                        &self.dns,
+                        proxy.as_ref(),
+                        options.http_version.alpn(),
+                        &mut timing,
+                    ),
+                )
+                .await?
+            }
+        };
+        let times = Arc::new(Mutex::new(IoTimes::default()));
+        let stream = Timed {
+            inner: stream,
+            times: times.clone(),
+        };
+        // A plain HTTP proxy needs the full URL to know where to forward the request.
+        let forward = proxy
+            .as_ref()
+            .filter(|proxy| proxy.kind == ProxyKind::Http && url.scheme() == "http");
+        let http2 = match options.http_version {
+            HttpVersion::Http2PriorKnowledge => true,
+            HttpVersion::Http2 => timing.alpn.as_deref() == Some("h2"),
+            HttpVersion::Http10 | HttpVersion::Http11 => false,
+        };
+        if http2 && forward.is_some() {
+            return Err(eyre!(
+                "HTTP/2 with prior knowledge can't go through a plain HTTP proxy"
+            ));
+        }
+        let mut sender = if http2 {
+            let (sender, connection) =
+                http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    debug!("Connection closed: {err}");
+                }
+            });
+            Sender::Http2(sender)
+        } else {
+            let (sender, connection) = http1::handshake(TokioIo::new(stream)).await?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    debug!("Connection closed: {err}");
+                }
+            });
+            Sender::Http1(sender)
+        };
+
+        // HTTP/2 takes the scheme and authority from the full URL in place of a Host header.
+        let target = match forward {
+            Some(_) => absolute_form(&url)?,
+            None if http2 => absolute_form(&url)?,
+            None => origin_form(&url)?,
+        };
+        let mut builder = Request::builder()
+            .method(request.method.as_str())
+            .uri(target)
+            .version(match options.http_version {
+                _ if http2 => Version::HTTP_2,
+                HttpVersion::Http10 => Version::HTTP_10,
+                _ => Version::HTTP_11,
+            });
+        if !http2 {
+            builder = builder.header(header::HOST, host_header(&url, &host));
+        }
+        builder = builder
+            .header(
+                header::USER_AGENT,
+                concat!("papirus/", env!("CARGO_PKG_VERSION")),
+            )
+            .header(header::ACCEPT, "*/*");
+        let typed = |name: &header::HeaderName| {
+            request
+                .headers
+                .iter()
+                .any(|(typed, _)| typed.eq_ignore_ascii_case(name.as_str()))
+        };
+        // Offer every coding the response can be decoded from, unless the user asked for some.
+        if !typed(&header::ACCEPT_ENCODING) {
+            builder = builder.header(header::ACCEPT_ENCODING, ACCEPT_ENCODING);
+        }
+        let compress = options
+            .compress_body
+            .coding()
+            .filter(|_| !request.body.is_empty());
+        if let Some(coding) = compress {
+            builder = builder.header(header::CONTENT_ENCODING, coding);
+        }
+        let mut cookie = request
+            .options
+            .cookies
+            .then(|| self.cookies.lock().unwrap().header(&url))
+            .flatten();
+        for (name, value) in &request.headers {
+            // RFC 6265 allows a single Cookie header, so cookies typed in join the jar's.
+            if name.eq_ignore_ascii_case("cookie") {
+                cookie = Some(match cookie {
+                    Some(jar) => format!("{value}; {jar}"),
+                    None => value.clone(),
+                });
+                continue;
+            }
+            // The compression option sets Content-Encoding to match the body it sends.
+            if compress.is_some() && name.eq_ignore_ascii_case("content-encoding") {
+                continue;
+            }
+            if http2 && is_connection_specific(name) {
+                debug!("Leaving out {name}, which HTTP/2 doesn't allow");
+                continue;
+            }
+            builder = builder.header(name, value);
+        }
+        if let Some(cookie) = cookie {
+            builder = builder.header(header::COOKIE, cookie);
+        }
+        if let Some(authorization) = forward.and_then(Proxy::authorization) {
+            builder = builder.header(header::PROXY_AUTHORIZATION, authorization);
+        }
+        let request_started = Instant::now();
+        let body = match compress {
+            Some(_) => options.compress_body.encode(&request.body)?,
+            None => request.body.clone(),
+        };
+        let request_body = builder.body(Full::new(Bytes::from(body)))?;
+        let pseudo_headers = http2.then(|| {
+            let uri = request_body.uri();
+            vec![
+                (":method".to_string(), request.method.clone()),
+                (
+                    ":scheme".to_string(),
+                    uri.scheme_str().unwrap_or_default().into(),
+                ),
+                (
+                    ":authority".to_string(),
+                    uri.authority().map(ToString::to_string).unwrap_or_default(),
+                ),
+                (
+                    ":path".to_string(),
+                    uri.path_and_query()
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                ),
+            ]
+        });
+        let response = within(
+            options.read_timeout,
+            "Waiting for the response",
+            sender.send_request(request_body),
+        )
+        .await?;
+
+        let status = response.status();
+        let version = format!("{:?}", response.version());
+        let headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        if request.options.cookies {
+            self.store_cookies(&headers, &url);
+        }
+        let head = HttpResponse {
+            method: request.method.clone(),
+            url: match &socket {
+                Some(socket) if unix::is_unix(&request.url) => unix::join_url(socket, &url),
+                _ => url.to_string(),
+            },
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or_default().to_string(),
+            version,
+            headers,
+            body: Vec::new(),
+            elapsed: started.elapsed(),
+            timing,
+            redirects: Vec::new(),
+            attempts: 1,
+            http2: pseudo_headers.map(|mut pseudo_headers| {
+                pseudo_headers.push((":status".into(), status.as_u16().to_string()));
+                Http2Details {
+                    stream_id: None,
+                    pseudo_headers,
+                }
+            }),
+            raw: None,
+            decoding: None,
+            spilled: None,
+        };
+        Ok(ResponseStream {
+            head,
+            body: response.into_body(),
+            read_timeout: options.read_timeout,
+            started,
+            request_started,
+            times,
+            memory_limit: self.downloads.memory_limit,
+        })
+    }
+
+    fn store_cookies(&self, headers: &[(String, String)], url: &Url) {
+        let set_cookies = headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .map(|(_, value)| value.as_str());
+        let mut jar = self.cookies.lock().unwrap();
+        if jar.store(set_cookies, url) {
+            if let Err(err) = jar.save() {
+                error!("Failed to save cookies: {err}");
+            }
+        }
+    }
+}
+
+impl ResponseStream {
+    /// Waits for the next piece of the body, or `None` once all of it has arrived.
+    pub async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            let body = &mut self.body;
+            let frame = within(self.read_timeout, "Reading the response", async {
+                Ok(body.frame().await.transpose()?)
+            })
+            .await?;
+            match frame.map(|frame| frame.into_data()) {
+                None => return Ok(None),
+                Some(Ok(data)) => return Ok(Some(data)),
+                // Trailers carry no body.
+                Some(Err(_)) => {}
+            }
+        }
+    }
+
+    /// Decodes the body by its `Content-Encoding`, keeping it as it arrived alongside.
+    fn decode(&mut self) {
+        let head = &mut self.head;
+        let Some(encodings) = head.header("content-encoding").map(compression::encodings) else {
+            return;
+        };
+        if encodings.is_empty() || head.body.is_empty() {
+            return;
+        }
+        let decoded = match head.spilled {
+            Some(_) => Err(eyre!(
+                "Not decoded, as the body is too large to keep in memory"
+            )),
+            None => compression::decode(&head.body, &encodings, self.memory_limit),
+        };
+        let (body, error) = match decoded {
+            Ok(decoded) => (std::mem::replace(&mut head.body, decoded), None),
+            Err(err) => (head.body.clone(), Some(err.to_string())),
+        };
+        head.decoding = Some(Decoding {
+            encodings,
+            encoded: body,
+            error,
+        });
+    }
+
+    /// Reads the rest of the body into the response, moving it to a temporary file if it outgrows
+    /// memory, and telling `progress` how far along it is.
+    pub async fn collect(
+        mut self,
+        progress: Option<&UnboundedSender<Progress>>,
+    ) -> Result<HttpResponse> {
+        let total = self
+            .head
+            .header("content-length")
+            .and_then(|len| len.parse().ok());
+        let body_started = Instant::now();
+        let mut reported = body_started;
+        let mut sink = Sink::new(self.memory_limit);
+        // How much of what is kept in memory has gone out with a report.
+        let mut shown = 0;
+        while let Some(data) = self.next_chunk().await? {
+            sink.write(&data).await?;
+            let Some(progress) = progress.filter(|_| reported.elapsed() >= PROGRESS_INTERVAL)
+            else {
+                continue;
+            };
+            reported = Instant::now();
+            let _ = progress.send(Progress {
+                received: sink.len(),
+                total,
+                elapsed: body_started.elapsed(),
+                data: sink.kept()[shown..].to_vec(),
+            });
+            shown = sink.kept().len();
+        }
+        (self.head.body, self.head.spilled) = sink.finish().await?;
+
+        let finished = Instant::now();
+        let mut times = self.times.lock().unwrap();
+        let written = times.last_write.unwrap_or(self.request_started);
+        let first_read = times.first_read.unwrap_or(finished);
+        let timing = &mut self.head.timing;
+        timing.request_write = written.saturating_duration_since(self.request_started);
+        timing.first_byte = first_read.saturating_duration_since(written);
+        timing.download = finished.saturating_duration_since(first_read);
+        self.head.elapsed = self.started.elapsed();
+        // HTTP/2 frames are binary, with headers compressed, so there is nothing to read in them.
+        if self.head.http2.is_none() {
+            self.head.raw = Some(RawExchange {
+                request: std::mem::take(&mut times.written),
+                response: std::mem::take(&mut times.read),
+                truncated: times.truncated,
+            });
+        }
+        drop(times);
+        self.decode();
+        Ok(self.head)
+    }
+}
+
+/// Parses a URL as typed by the user, assuming `http://` when no scheme is given.
+pub fn parse_url(raw: &str) -> Result<Url> {
+    let raw = raw.trim();
+    let url = if raw.contains("://") {
+        Url::parse(raw)?
+    } else {
+        Url::parse(&format!("http://{raw}"))?
+    };
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(eyre!("Unsupported scheme: {scheme}")),
+    }
+}
+
+/// Runs `future` for at most `ms` milliseconds, or without a limit if `ms` is 0. Running out of
+/// time fails with an I/O error, so that the request counts as having lost its connection.
+async fn within<T>(ms: u32, doing: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+    if ms == 0 {
+        return future.await;
+    }
+    match timeout(Duration::from_millis(ms.into()), future).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("{doing} timed out after {ms} ms"),
+        )
+        .into()),
+    }
+}
+
+/// Opens a connection to the host, directly or through a proxy, timing each phase of setting it
+/// up. `alpn` lists the protocols offered if the connection uses TLS, most preferred first.
+#[allow(clippy::too_many_arguments)]
+async fn connect(
+    url: &Url,
+    host: &str,
+    port: u16,
+    settings: &TlsSettings,
+    dns: &DnsSettings,
+    proxy: Option<&Proxy>,
+    alpn: Vec<Vec<u8>>,
+    timing: &mut Timing,
+) -> Result<Box<dyn Io>> {
+    // IPv6 literals keep their brackets in URLs but not in socket addresses.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let tcp = match proxy {
+        None => open_tcp(host, port, dns, timing).await?,
+        Some(proxy) => {
+            let mut tcp = open_tcp(&proxy.host, proxy.port, dns, timing).await?;
+            timing.proxy = Some(proxy.to_string());
+            let started = Instant::now();
+            match proxy.kind {
+                // Anything but plain HTTP goes through a tunnel, WebSockets included.
+                ProxyKind::Http if url.scheme() != "http" => {
+                    proxy.tunnel(&mut tcp, host, port).await?
+                }
+                // Plain HTTP goes to the proxy as is, with the full URL as the target.
+                ProxyKind::Http => {}
+                ProxyKind::Socks5 | ProxyKind::Socks5h => {
+                    proxy.socks5(&mut tcp, host, port).await?
+                }
+            }
+            timing.connect += started.elapsed();
+            tcp
+        }
+    };
+    if !matches!(url.scheme(), "https" | "wss" | "grpcs") {
+        return Ok(Box::new(tcp));
+    }
+
+    let started = Instant::now();
+    let mut config = settings.client_config()?;
+    config.alpn_protocols = alpn;
+    let tls = TlsConnector::from(Arc::new(config))
+        .connect(settings.server_name(host)?, tcp)
+        .await?;
+    timing.tls = Some(started.elapsed());
+    timing.insecure = settings.insecure;
+
+    let (_, session) = tls.get_ref();
+    timing.tls_version = session.protocol_version().map(|version| {
+        format!("{version:?}")
+            .replace("TLSv", "TLS ")
+            .replace('_', ".")
+    });
+    timing.alpn = session
+        .alpn_protocol()
+        .map(|alpn| String::from_utf8_lossy(alpn).into_owned());
+    timing.certificates = session
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|cert| CertificateInfo::parse(cert))
+        .collect();
+    Ok(Box::new(tls))
+}
+
+/// Resolves a host and connects to the first of its addresses that accepts.
+async fn open_tcp(
+    host: &str,
+    port: u16,
+    dns: &DnsSettings,
+    timing: &mut Timing,
+) -> Result<TcpStream> {
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => {
+            let started = Instant::now();
+            let (addrs, resolved_by) = dns.resolve(host, port).await?;
+            timing.dns = Some(started.elapsed());
+            timing.resolved_by = resolved_by;
+            addrs
+        }
+    };
+
+    let started = Instant::now();
+    let mut last_err = None;
+    let mut tcp = None;
+    for addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => {
+                tcp = Some(stream);
+                break;
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    let tcp = match (tcp, last_err) {
+        (Some(tcp), _) => tcp,
+        (None, Some(err)) => return Err(err.into()),
+        (None, None) => return Err(eyre!("No addresses found for {host}")),
+    };
+    timing.connect = started.elapsed();
+    timing.remote_addr = tcp.peer_addr().ok();
+    tcp.set_nodelay(true)?;
+    Ok(tcp)
+}
+
+fn origin_form(url: &Url) -> Result<Uri> {
+    let target = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    Ok(target.parse()?)
+}
+
+fn absolute_form(url: &Url) -> Result<Uri> {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    Ok(url.as_str().parse()?)
+}
+
+/// Whether a header only makes sense for a single HTTP/1.1 connection, which HTTP/2 forbids.
+fn is_connection_specific(name: &str) -> bool {
+    [
+        "connection",
+        "keep-alive",
+        "proxy-connection",
+        "transfer-encoding",
+        "upgrade",
+        "host",
+    ]
+    .iter()
+    .any(|header| name.eq_ignore_ascii_case(header))
+}
+
+fn host_header(url: &Url, host: &str) -> String {
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc,
+    };
+    use tokio_rustls::{
+        rustls::{
+            pki_types::PrivateKeyDer,
+            server::WebPkiClientVerifier,
+            RootCertStore,
+            ServerConfig,
+        },
+        TlsAcceptor,
+    };
+
+    use super::*;
+
+    /// Serves a single canned response on a local port and returns the URL to reach it.
+    async fn serve_once(response: &'static str) -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        Ok(format!("http://{addr}/items?page=1"))
+    }
+
+    #[test]
+    fn test_debug_leaves_out_body() {
+        let response = HttpResponse {
+            body: b"secret".to_vec(),
+            ..HttpResponse::default()
+        };
+        let debug = format!("{response:?}");
+        assert!(debug.contains("body: 6 bytes"), "{debug}");
+        assert!(!debug.contains("115"), "{debug}");
+    }
+
+    #[tokio::test]
+    async fn test_send() -> Result<()> {
+        let url = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: \
+             2\r\n\r\n{}",
+        )
+        .await?;
+        let response = Client::default().send(HttpRequest::get(&url)).await?;
+        assert_eq!(response.method, "GET");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.media_type().as_deref(), Some("application/json"));
+        assert_eq!(response.body, b"{}");
+
+        let raw = response.raw.unwrap();
+        let request = String::from_utf8(raw.request)?;
+        assert!(
+            request.starts_with("GET /items?page=1 HTTP/1.1\r\n"),
+            "{request}"
+        );
+        assert!(
+            request.ends_with("accept: */*\r\naccept-encoding: gzip, deflate, br, zstd\r\n\r\n"),
+            "{request}"
+        );
+        assert_eq!(
+            raw.response,
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: \
+              2\r\n\r\n{}"
+        );
+        assert!(!raw.truncated);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_uses_cookie_jar() -> Result<()> {
+        let client = Client::default();
+        let url =
+            serve_once("HTTP/1.1 204 No Content\r\nSet-Cookie: sid=abc; Path=/\r\n\r\n").await?;
+        client.send(HttpRequest::get(&url)).await?;
+
+        // Echo the request back so its headers can be inspected.
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let len = socket.read(&mut buf).await.unwrap();
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {len}\r\n\r\n");
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..len]).await.unwrap();
+        });
+        let mut request = HttpRequest::get(&format!("http://{addr}/"));
+        request.headers.push(("Cookie".into(), "typed=1".into()));
+        let response = client.send(request).await?;
+        let echoed = String::from_utf8(response.body)?;
+        assert!(echoed.contains("cookie: typed=1; sid=abc\r\n"), "{echoed}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_follows_redirects() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let responses = [
+                "HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+                "HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        let url = format!("http://{addr}/start");
+        let response = Client::default().send(HttpRequest::get(&url)).await?;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.url, format!("http://{addr}/next"));
+        let hops: Vec<(u16, &str)> = response
+            .redirects
+            .iter()
+            .map(|hop| (hop.status, hop.url.as_str()))
+            .collect();
+        assert_eq!(hops, vec![(302, url.as_str())]);
+
+        let mut request = HttpRequest::get(&url);
+        request.options.follow_redirects = false;
+        let response = Client::default().send(request).await?;
+        assert_eq!(response.status, 302);
+        assert!(response.redirects.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_retries() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let responses = [
+                "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        let mut request = HttpRequest::get(&format!("http://{addr}/"));
+        request.options.max_attempts = 3;
+        let response = Client::default().send(request).await?;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.attempts, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_times_out() -> Result<()> {
+        // Accept the connection but never answer.
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+        let mut request = HttpRequest::get(&format!("http://{addr}/"));
+        request.options.read_timeout = 50;
+        let err = Client::default().send(request).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Waiting for the response timed out after 50 ms"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_records_timing() -> Result<()> {
+        let url = serve_once("HTTP/1.1 204 No Content\r\n\r\n").await?;
+        let response = Client::default().send(HttpRequest::get(&url)).await?;
+        let timing = response.timing;
+        assert_eq!(timing.dns, None);
+        assert_eq!(timing.tls, None);
+        assert!(timing
+            .remote_addr
+            .is_some_and(|addr| addr.ip().is_loopback()));
+        assert!(timing.connect + timing.request_write + timing.first_byte <= response.elapsed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_with_private_ca_and_client_certificate() -> Result<()> {
+        let ca_key = KeyPair::generate()?;
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Test CA");
+        let ca = params.self_signed(&ca_key)?;
+        let issue = |name: &str| -> Result<(rcgen::Certificate, KeyPair)> {
+            let key = KeyPair::generate()?;
+            let mut params = CertificateParams::new(vec![name.to_string()])?;
+            params.distinguished_name.push(DnType::CommonName, name);
+            Ok((params.signed_by(&key, &ca, &ca_key)?, key))
+        };
+        let (server_cert, server_key) = issue("api.internal")?;
+        let (client_cert, client_key) = issue("client")?;
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.der().clone())?;
+        let server_config = ServerConfig::builder()
+            .with_client_cert_verifier(WebPkiClientVerifier::builder(Arc::new(roots)).build()?)
+            .with_single_cert(
+                vec![server_cert.der().clone(), ca.der().clone()],
+                PrivateKeyDer::Pkcs8(server_key.serialize_der().into()),
+            )?;
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let Ok(mut tls) = acceptor.accept(socket).await else {
+                    continue;
+                };
+                let mut buf = [0; 4096];
+                let _ = tls.read(&mut buf).await.unwrap();
+                tls.write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                    .await
+                    .unwrap();
+                tls.flush().await.unwrap();
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("papirus-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("ca.pem"), ca.pem())?;
+        std::fs::write(dir.join("client.pem"), client_cert.pem())?;
+        std::fs::write(dir.join("client.key"), client_key.serialize_pem())?;
+        let settings = TlsSettings {
+            ca_certs: vec![dir.join("ca.pem")],
+            client_cert: Some(dir.join("client.pem")),
+            client_key: Some(dir.join("client.key")),
+            sni: Some("api.internal".into()),
+            ..TlsSettings::default()
+        };
+        let url = format!("https://{addr}/");
+        let client = Client::default().tls(HashMap::from([("127.0.0.1".into(), settings)]));
+        let response = client.send(HttpRequest::get(&url)).await;
+        std::fs::remove_dir_all(dir)?;
+
+        let timing = response?.timing;
+        let subjects: Vec<&str> = timing
+            .certificates
+            .iter()
+            .map(|cert| cert.subject.as_str())
+            .collect();
+        assert_eq!(subjects, vec!["CN=api.internal", "CN=Test CA"]);
+        assert!(!timing.insecure);
+
+        // Without the CA the same server is rejected.
+        let err = Client::default()
+            .send(HttpRequest::get(&url))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("UnknownIssuer"), "{err}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_through_http_proxy() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let len = socket.read(&mut buf).await.unwrap();
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {len}\r\n\r\n");
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..len]).await.unwrap();
+        });
+        let client = Client::default().proxy(ProxySettings {
+            http: Some(format!("http://user:secret@{addr}")),
+            use_env: false,
+            ..ProxySettings::default()
+        });
+        let response = client
+            .send(HttpRequest::get("http://example.invalid/items?page=1"))
+            .await?;
+        let echoed = String::from_utf8(response.body)?;
+        assert!(echoed.starts_with("GET http://example.invalid/items?page=1 HTTP/1.1\r\n"));
+        assert!(echoed.contains("proxy-authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+        assert_eq!(response.timing.proxy, Some(format!("http://{addr}")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_through_socks5_proxy() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut greeting = [0; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            socket.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0; 5];
+            socket.read_exact(&mut request).await.unwrap();
+            let mut target = vec![0; request[4] as usize + 2];
+            socket.read_exact(&mut target).await.unwrap();
+            socket
+                .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            let mut buf = [0; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            (greeting, request, target)
+        });
+        let client = Client::default().proxy(ProxySettings {
+            http: Some(format!("socks5h://{addr}")),
+            use_env: false,
+            ..ProxySettings::default()
+        });
+        let response = client
+            .send(HttpRequest::get("http://example.invalid:8080/"))
+            .await?;
+        assert_eq!(response.status, 204);
+
+        let (greeting, request, target) = server.await?;
+        assert_eq!(greeting, [5, 1, 0]);
+        assert_eq!(request, [5, 1, 0, 3, 15]);
+        assert_eq!(target, b"example.invalid\x1f\x90");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_with_host_override() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let len = socket.read(&mut buf).await.unwrap();
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {len}\r\n\r\n");
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..len]).await.unwrap();
+        });
+        let key = format!("api.example.invalid:{}", addr.port());
+        let client = Client::default().dns(DnsSettings {
+            overrides: HashMap::from([(key.clone(), vec![addr.ip()])]),
+            ..DnsSettings::default()
+        });
+        let response = client
+            .send(HttpRequest::get(&format!("http://{key}/")))
+            .await?;
+        let echoed = String::from_utf8(response.body)?;
+        assert!(echoed.contains(&format!("host: {key}\r\n")), "{echoed}");
+        assert_eq!(response.timing.remote_addr, Some(addr));
+        assert_eq!(
+            response.timing.resolved_by,
+            Some(format!("override {key} → 127.0.0.1"))
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_send_over_unix_socket() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-unix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let socket = dir.join("api.sock");
+        let listener = tokio::net::UnixListener::bind(&socket)?;
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let len = socket.read(&mut buf).await.unwrap();
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {len}\r\n\r\n");
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&buf[..len]).await.unwrap();
+            }
+        });
+
+        let url = format!("unix://{}:/v1.43/containers/json?all=1", socket.display());
+        let response = Client::default().send(HttpRequest::get(&url)).await;
+        let mut request = HttpRequest::get("http://docker/_ping");
+        request.options.unix_socket = socket.display().to_string();
+        let pinged = Client::default().send(request).await;
+        std::fs::remove_dir_all(dir)?;
+
+        let response = response?;
+        assert_eq!(response.url, url);
+        assert_eq!(response.timing.socket, Some(socket.display().to_string()));
+        let echoed = String::from_utf8(response.body)?;
+        assert!(echoed.starts_with("GET /v1.43/containers/json?all=1 HTTP/1.1\r\n"));
+        assert!(echoed.contains("host: localhost\r\n"), "{echoed}");
+
+        let echoed = String::from_utf8(pinged?.body)?;
+        assert!(echoed.starts_with("GET /_ping HTTP/1.1\r\n"));
+        assert!(echoed.contains("host: docker\r\n"), "{echoed}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_compressed_bodies() -> Result<()> {
+        // Echo the request back gzipped.
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let len = socket.read(&mut buf).await.unwrap();
+            let body = BodyCompression::Gzip.encode(&buf[..len]).unwrap();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+        let mut request = HttpRequest {
+            method: "POST".into(),
+            url: format!("http://{addr}/"),
+            headers: vec![("Content-Encoding".into(), "br".into())],
+            body: b"hello ".repeat(20),
+            ..HttpRequest::default()
+        };
+        request.options.compress_body = BodyCompression::Zstd;
+        let response = Client::default().send(request).await?;
+
+        let decoding = response.decoding.unwrap();
+        assert_eq!(decoding.encodings, ["gzip"]);
+        assert_eq!(decoding.error, None);
+        assert_eq!(
+            compression::decode(&decoding.encoded, &decoding.encodings, usize::MAX)?,
+            response.body
+        );
+        let echoed = response.body;
+        let split = echoed.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&echoed[..split]);
+        assert!(head.contains("content-encoding: zstd\r\n"), "{head}");
+        assert!(!head.contains("content-encoding: br"), "{head}");
+        assert_eq!(
+            compression::decode(&echoed[split + 4..], &["zstd".into()], usize::MAX)?,
+            b"hello ".repeat(20)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_spills_large_bodies() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let head = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            tokio::time::sleep(PROGRESS_INTERVAL * 2).await;
+            socket.write_all(b"world").await.unwrap();
+        });
+        let client = Client::default().downloads(DownloadSettings { memory_limit: 4 });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let request = HttpRequest::get(&format!("http://{addr}/"));
+        let response = client.send_reporting(request, tx).await?;
+
+        // Progress is throttled, so the first report comes with the second chunk.
+        let progress = rx.recv().await.unwrap();
+        assert_eq!(progress.received, 10);
+        assert_eq!(progress.total, Some(10));
+        assert_eq!(progress.data, b"hell");
+        assert_eq!(response.size(), 10);
+        assert_eq!(response.body, b"hell");
+        let path = response.spilled.as_ref().unwrap().path().to_path_buf();
+        assert_eq!(tokio::fs::read(&path).await?, b"helloworld");
+        drop(response);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_with_http_versions() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let service = hyper::service::service_fn(|request: Request<Incoming>| async move {
+                let path = request.uri().path().to_string();
+                Ok::<_, hyper::Error>(Response::new(Full::new(Bytes::from(path))))
+            });
+            hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(socket), service)
+                .await
+                .unwrap();
+        });
+        let mut request = HttpRequest::get(&format!("http://{addr}/items?page=1"));
+        request.options.http_version = HttpVersion::Http2PriorKnowledge;
+        request.headers = vec![("Connection".into(), "keep-alive".into())];
+        let response = Client::default().send(request).await?;
+        assert_eq!(response.version, "HTTP/2.0");
+        assert_eq!(response.body, b"/items");
+        assert_eq!(response.raw, None);
+        let http2 = response.http2.unwrap();
+        assert_eq!(http2.stream_id, None);
+        assert_eq!(
+            http2.pseudo_headers,
+            [
+                (":method".to_string(), "GET".to_string()),
+                (":scheme".into(), "http".into()),
+                (":authority".into(), addr.to_string()),
+                (":path".into(), "/items?page=1".into()),
+                (":status".into(), "200".into()),
+            ]
+        );
+
+        let url = serve_once("HTTP/1.0 204 No Content\r\n\r\n").await?;
+        let mut request = HttpRequest::get(&url);
+        request.options.http_version = HttpVersion::Http10;
+        let response = Client::default().send(request).await?;
+        assert_eq!(response.version, "HTTP/1.0");
+        assert_eq!(response.http2, None);
+        let request = response.raw.unwrap().request;
+        assert!(request.starts_with(b"GET /items?page=1 HTTP/1.0\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("example.com/a").unwrap().as_str(),
+            "http://example.com/a"
+        );
+        assert!(parse_url("ftp://example.com").is_err());
+    }
+}
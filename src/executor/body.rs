@@ -0,0 +1,235 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+/// Bodies larger than this many bytes go to a temporary file unless configured otherwise.
+const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Tells apart the temporary files of one run.
+static SPILLED: AtomicUsize = AtomicUsize::new(0);
+
+/// How response bodies are received, from the `[downloads]` table of the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// Bytes of a body kept in memory. Past this, the whole body is written to a temporary file
+    /// and only its first `memory_limit` bytes are kept for display. A compressed body that would
+    /// decode to more than this is kept as it arrived.
+    pub memory_limit: usize,
+}
+
+/// How far along the body of a response is.
+#[derive(Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub received: u64,
+    /// The size of the body from `Content-Length`, if the server sent it.
+    pub total: Option<u64>,
+    /// Time since the body started arriving.
+    pub elapsed: Duration,
+    /// What arrived since the last report, as far as it is kept in memory, for showing the body
+    /// before it is complete.
+    pub data: Vec<u8>,
+}
+
+/// A body too large to keep in memory, written to a temporary file instead. The file is removed
+/// once the last copy of this is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spilled {
+    file: Arc<TempFile>,
+    pub len: u64,
+}
+
+/// A file removed when dropped.
+#[derive(Debug, PartialEq, Eq)]
+struct TempFile(PathBuf);
+
+/// Where the pieces of a body go as they arrive: memory at first, then a file once there are too
+/// many of them.
+pub(super) struct Sink {
+    memory: Vec<u8>,
+    limit: usize,
+    file: Option<(File, PathBuf)>,
+    len: u64,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+        }
+    }
+}
+
+// By hand, so that logging a report doesn't print every byte it carries.
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("received", &self.received)
+            .field("total", &self.total)
+            .field("elapsed", &self.elapsed)
+            .field("data", &format_args!("{} bytes", self.data.len()))
+            .finish()
+    }
+}
+
+impl Progress {
+    /// Bytes received per second so far.
+    pub fn rate(&self) -> u64 {
+        if self.elapsed.is_zero() {
+            return 0;
+        }
+        (self.received as f64 / self.elapsed.as_secs_f64()) as u64
+    }
+}
+
+impl Spilled {
+    pub fn path(&self) -> &Path {
+        &self.file.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl Sink {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            memory: Vec::new(),
+            limit,
+            file: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// The start of the body kept in memory so far.
+    pub fn kept(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.len += data.len() as u64;
+        if self.file.is_none() && self.memory.len() + data.len() <= self.limit {
+            self.memory.extend_from_slice(data);
+            return Ok(());
+        }
+        let (file, _) = match &mut self.file {
+            Some(file) => file,
+            None => {
+                let n = SPILLED.fetch_add(1, Ordering::Relaxed);
+                let path =
+                    std::env::temp_dir().join(format!("papirus-{}-{n}.body", std::process::id()));
+                let mut file = File::create(&path).await?;
+                file.write_all(&self.memory).await?;
+                self.file.insert((file, path))
+            }
+        };
+        // Fill up what is kept in memory, so there is as much as allowed to show.
+        let room = self.limit.saturating_sub(self.memory.len()).min(data.len());
+        self.memory.extend_from_slice(&data[..room]);
+        file.write_all(data).await?;
+        Ok(())
+    }
+
+    /// The body if it fit in memory, or else its start and the file holding all of it.
+    pub async fn finish(self) -> Result<(Vec<u8>, Option<Spilled>)> {
+        let Some((mut file, path)) = self.file else {
+            return Ok((self.memory, None));
+        };
+        file.flush().await?;
+        let spilled = Spilled {
+            file: Arc::new(TempFile(path)),
+            len: self.len,
+        };
+        Ok((self.memory, Some(spilled)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sink_keeps_small_bodies_in_memory() -> Result<()> {
+        let mut sink = Sink::new(4);
+        sink.write(b"ab").await?;
+        sink.write(b"cd").await?;
+        assert_eq!(sink.finish().await?, (b"abcd".to_vec(), None));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sink_spills_past_limit() -> Result<()> {
+        let mut sink = Sink::new(4);
+        sink.write(b"abc").await?;
+        sink.write(b"def").await?;
+        sink.write(b"gh").await?;
+        assert_eq!(sink.len(), 8);
+        let (body, spilled) = sink.finish().await?;
+        assert_eq!(body, b"abcd");
+        let spilled = spilled.unwrap();
+        assert_eq!(spilled.len, 8);
+        let path = spilled.path().to_path_buf();
+        assert_eq!(tokio::fs::read(&path).await?, b"abcdefgh");
+
+        let copy = spilled.clone();
+        drop(spilled);
+        assert!(path.exists());
+        drop(copy);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sink_keeps_start_of_large_first_chunk() -> Result<()> {
+        let mut sink = Sink::new(4);
+        sink.write(b"abcdefgh").await?;
+        assert_eq!(sink.kept(), b"abcd");
+        let (body, spilled) = sink.finish().await?;
+        assert_eq!(body, b"abcd");
+        assert_eq!(spilled.unwrap().len, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_leaves_out_data() {
+        let progress = Progress {
+            received: 3,
+            data: b"abc".to_vec(),
+            ..Progress::default()
+        };
+        assert_eq!(
+            format!("{progress:?}"),
+            "Progress { received: 3, total: None, elapsed: 0ns, data: 3 bytes }"
+        );
+    }
+
+    #[test]
+    fn test_rate() {
+        let progress = Progress {
+            received: 3000,
+            elapsed: Duration::from_millis(1500),
+            ..Progress::default()
+        };
+        assert_eq!(progress.rate(), 2000);
+        assert_eq!(Progress::default().rate(), 0);
+    }
+}
@@ -0,0 +1,187 @@
+use std::io::{self, Read, Write};
+
+use color_eyre::{eyre::eyre, Result};
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+/// Codings offered in `Accept-Encoding`, all of which [`decode`] understands.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+/// A content coding a request body can be compressed with before it is sent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+pub enum BodyCompression {
+    #[default]
+    #[strum(to_string = "none")]
+    None,
+    #[strum(to_string = "gzip")]
+    Gzip,
+    #[strum(to_string = "deflate")]
+    Deflate,
+    #[strum(to_string = "br")]
+    Brotli,
+    #[strum(to_string = "zstd")]
+    Zstd,
+}
+
+/// A body that arrived compressed, as it was before decoding.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decoding {
+    /// The codings named by `Content-Encoding`, in the order the server applied them.
+    pub encodings: Vec<String>,
+    /// The body as it arrived.
+    pub encoded: Vec<u8>,
+    /// Why the body couldn't be decoded, in which case the response keeps it as it arrived.
+    pub error: Option<String>,
+}
+
+impl BodyCompression {
+    /// The coding after this one, wrapping around, for cycling through them in the UI.
+    pub fn next(self) -> Self {
+        let codings: Vec<Self> = Self::iter().collect();
+        let i = codings.iter().position(|c| *c == self).unwrap_or_default();
+        codings[(i + 1) % codings.len()]
+    }
+
+    /// The value of `Content-Encoding` for bodies compressed this way, or `None` if they aren't.
+    pub fn coding(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Deflate => Some("deflate"),
+            Self::Brotli => Some("br"),
+            Self::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Compresses a body, or returns it as it is for [`BodyCompression::None`].
+    pub fn encode(self, body: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::None => body.to_vec(),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+            Self::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+            Self::Brotli => {
+                let mut encoded = Vec::new();
+                let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 5, 22);
+                encoder.write_all(body)?;
+                drop(encoder);
+                encoded
+            }
+            Self::Zstd => zstd::encode_all(body, 0)?,
+        })
+    }
+}
+
+/// Splits a `Content-Encoding` header into its codings, leaving out `identity`.
+pub fn encodings(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .collect()
+}
+
+/// Undoes the codings of a body, the last one applied first.
+///
+/// Decoding stops at `limit` bytes, so that a small body can't expand to fill memory; a body that
+/// would decode to more than that is reported as an error instead.
+pub fn decode(body: &[u8], encodings: &[String], limit: usize) -> Result<Vec<u8>> {
+    let mut body = body.to_vec();
+    for coding in encodings.iter().rev() {
+        let decoded = match coding.as_str() {
+            "gzip" | "x-gzip" => read_limited(GzDecoder::new(&body[..]), limit),
+            // Meant to be zlib, but some servers send raw deflate.
+            "deflate" => read_limited(ZlibDecoder::new(&body[..]), limit)
+                .or_else(|_| read_limited(DeflateDecoder::new(&body[..]), limit)),
+            "br" => read_limited(brotli::Decompressor::new(&body[..], 4096), limit),
+            "zstd" => zstd::Decoder::new(&body[..]).and_then(|d| read_limited(d, limit)),
+            other => return Err(eyre!("Unsupported Content-Encoding: {other}")),
+        }
+        .map_err(|err| eyre!("Decoding {coding}: {err}"))?;
+        if decoded.len() > limit {
+            return Err(eyre!(
+                "Not decoded, as the body would be larger than {limit} bytes"
+            ));
+        }
+        body = decoded;
+    }
+    Ok(body)
+}
+
+/// Reads up to one byte past `limit`, which is enough to tell whether there is more.
+fn read_limited(reader: impl Read, limit: usize) -> io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    reader
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_and_decode() {
+        let body = br#"{"items":[1,2,3],"items_again":[1,2,3]}"#;
+        for compression in BodyCompression::iter().skip(1) {
+            let encoded = compression.encode(body).unwrap();
+            assert_ne!(encoded, body);
+            let coding = compression.coding().unwrap();
+            assert_eq!(
+                decode(&encoded, &encodings(coding), usize::MAX).unwrap(),
+                body
+            );
+        }
+        assert_eq!(BodyCompression::None.encode(body).unwrap(), body);
+    }
+
+    #[test]
+    fn test_decode_chain() {
+        let gzipped = BodyCompression::Gzip.encode(b"hello").unwrap();
+        let both = BodyCompression::Brotli.encode(&gzipped).unwrap();
+        let encodings = encodings("gzip, identity, BR");
+        assert_eq!(encodings, ["gzip", "br"]);
+        assert_eq!(decode(&both, &encodings, usize::MAX).unwrap(), b"hello");
+
+        let mut raw = flate2::write::DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(b"hello").unwrap();
+        let raw = raw.finish().unwrap();
+        assert_eq!(
+            decode(&raw, &["deflate".into()], usize::MAX).unwrap(),
+            b"hello"
+        );
+
+        assert!(decode(b"hello", &["gzip".into()], usize::MAX).is_err());
+        assert!(decode(b"hello", &["compress".into()], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_decode_limit() {
+        let body = vec![0; 10_000];
+        for compression in BodyCompression::iter().skip(1) {
+            let encoded = compression.encode(&body).unwrap();
+            let encodings = encodings(compression.coding().unwrap());
+            assert_eq!(decode(&encoded, &encodings, 10_000).unwrap(), body);
+            let err = decode(&encoded, &encodings, 9_999).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Not decoded, as the body would be larger than 9999 bytes"
+            );
+        }
+    }
+}
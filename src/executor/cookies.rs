@@ -0,0 +1,508 @@
+use std::{
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// A cookie as stored by the jar, following the storage model of RFC 6265 section 5.3.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Only sent to the exact host that set it, because no `Domain` attribute was given.
+    pub host_only: bool,
+    pub path: String,
+    /// Expiry in seconds since the Unix epoch, or `None` for a session cookie.
+    pub expires: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<String>,
+}
+
+/// Cookies received from servers, persisted to a file per workspace.
+///
+/// Session cookies are persisted too: a session lasts as long as the workspace, not the process.
+/// `SameSite` is recorded but not enforced, since every request sent from here is first-party.
+#[derive(Debug, Default, Clone)]
+pub struct CookieJar {
+    file: Option<PathBuf>,
+    cookies: Vec<Cookie>,
+}
+
+impl Cookie {
+    /// Parses a `Set-Cookie` header received in response to a request for `url`, following
+    /// RFC 6265 section 5.2. Returns `None` for cookies a user agent must ignore.
+    pub fn parse(header: &str, url: &Url, now: u64) -> Option<Self> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let (pair, attributes) = header.split_once(';').unwrap_or((header, ""));
+        let (name, value) = pair.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Self {
+            name: name.into(),
+            value: value.trim().into(),
+            domain: String::new(),
+            host_only: true,
+            path: default_path(url.path()),
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        };
+        let mut max_age = None;
+        let mut expires = None;
+        let mut domain = None;
+        for attribute in attributes.split(';') {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "expires" => expires = parse_date(value).or(expires),
+                "max-age" => {
+                    if let Ok(delta) = value.parse::<i64>() {
+                        max_age = Some(if delta <= 0 {
+                            0
+                        } else {
+                            now.saturating_add(delta as u64)
+                        });
+                    }
+                }
+                "domain" if !value.is_empty() => {
+                    domain = Some(value.trim_start_matches('.').to_ascii_lowercase());
+                }
+                "path" if value.starts_with('/') => cookie.path = value.into(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                        "strict" => Some("Strict".into()),
+                        "lax" => Some("Lax".into()),
+                        "none" => Some("None".into()),
+                        _ => None,
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Max-Age wins over Expires regardless of order.
+        cookie.expires = max_age.or(expires);
+
+        match domain {
+            Some(domain) => {
+                // A bare TLD would let a server set cookies for every site under it.
+                if !domain_matches(&host, &domain) || (!domain.contains('.') && domain != host) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            }
+            None => cookie.domain = host,
+        }
+        Some(cookie)
+    }
+
+    /// Writes the cookie back out as a `Set-Cookie` header value, e.g. for editing.
+    pub fn to_set_cookie(&self) -> String {
+        let mut header = format!("{}={}", self.name, self.value);
+        if !self.host_only {
+            header.push_str(&format!("; Domain={}", self.domain));
+        }
+        header.push_str(&format!("; Path={}", self.path));
+        if let Some(expires) = self.expires {
+            header.push_str(&format!("; Expires={}", format_date(expires)));
+        }
+        if self.secure {
+            header.push_str("; Secure");
+        }
+        if self.http_only {
+            header.push_str("; HttpOnly");
+        }
+        if let Some(same_site) = &self.same_site {
+            header.push_str(&format!("; SameSite={same_site}"));
+        }
+        header
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, url: &Url, now: u64) -> bool {
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return false;
+        };
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        domain_ok
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && !self.is_expired(now)
+    }
+
+    fn same_key(&self, other: &Self) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+impl CookieJar {
+    pub fn load(file: PathBuf) -> Self {
+        let now = now();
+        let mut cookies: Vec<Cookie> = fs::read_to_string(&file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+        Self {
+            file: Some(file),
+            cookies,
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(file, serde_json::to_string_pretty(&self.cookies)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    /// Stores a cookie, replacing one with the same name, domain and path. An expired cookie
+    /// removes the stored one instead, which is how servers delete cookies.
+    pub fn insert(&mut self, cookie: Cookie) {
+        let existing = self.cookies.iter().position(|c| c.same_key(&cookie));
+        match existing {
+            _ if cookie.is_expired(now()) => {
+                if let Some(i) = existing {
+                    self.cookies.remove(i);
+                }
+            }
+            Some(i) => self.cookies[i] = cookie,
+            None => self.cookies.push(cookie),
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.cookies.len() {
+            self.cookies.remove(index);
+        }
+    }
+
+    /// Stores the cookies from a response's `Set-Cookie` headers, returning whether any were.
+    pub fn store<'a>(&mut self, headers: impl Iterator<Item = &'a str>, url: &Url) -> bool {
+        let now = now();
+        let mut stored = false;
+        for header in headers {
+            if let Some(cookie) = Cookie::parse(header, url, now) {
+                self.insert(cookie);
+                stored = true;
+            }
+        }
+        stored
+    }
+
+    /// The `Cookie` header to send with a request, with longer paths first as RFC 6265 asks.
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let now = now();
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(url, now))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The path a cookie applies to when it doesn't say: the "directory" of the request path.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".into(),
+        Some(i) => path[..i].into(),
+    }
+}
+
+/// Parses a cookie date with the lenient algorithm of RFC 6265 section 5.1.1, which copes with
+/// the many formats servers send. Returns seconds since the Unix epoch.
+pub fn parse_date(value: &str) -> Option<u64> {
+    let is_delimiter = |c: char| matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~');
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+    for token in value.split(is_delimiter).filter(|t| !t.is_empty()) {
+        if time.is_none() {
+            if let Some(parsed) = parse_time(token) {
+                time = Some(parsed);
+                continue;
+            }
+        }
+        if day.is_none() {
+            if let Some((d, _)) = leading_digits(token, 1, 2) {
+                day = Some(d);
+                continue;
+            }
+        }
+        if month.is_none() {
+            // Not sliced, as the third byte may fall inside a character of a non-ASCII token.
+            let prefix = token.get(..3).map(str::to_ascii_lowercase);
+            if let Some(m) = MONTHS.iter().position(|m| Some(*m) == prefix.as_deref()) {
+                month = Some(m as u32 + 1);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some((y, _)) = leading_digits(token, 2, 4) {
+                year = Some(y);
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let (day, month) = (day?, month?);
+    let year = match year? {
+        y @ 70..=99 => y + 1900,
+        y @ 0..=69 => y + 2000,
+        y => y,
+    };
+    if !(1..=31).contains(&day) || year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let days = days_from_civil(year as i64, month as i64, day as i64);
+    let seconds = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+    Some(seconds.max(0) as u64)
+}
+
+/// Formats seconds since the Unix epoch as an HTTP date, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+pub fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}, {day:02} {} {year} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days + 4).rem_euclid(7) as usize],
+        capitalize(MONTHS[month as usize - 1]),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// Parses `hh:mm:ss`, allowing one or two digits per field and trailing non-digits.
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = token.splitn(3, ':');
+    let (hour, rest) = leading_digits(parts.next()?, 1, 2)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let (minute, rest) = leading_digits(parts.next()?, 1, 2)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let (second, _) = leading_digits(parts.next()?, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+/// Parses between `min` and `max` leading digits, which must not be followed by another digit.
+fn leading_digits(token: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let len = token.bytes().take_while(u8::is_ascii_digit).count();
+    if len < min || len > max {
+        return None;
+    }
+    Some((token[..len].parse().ok()?, &token[len..]))
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The proleptic Gregorian date of a number of days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let expected = Some(1445412480);
+        assert_eq!(parse_date("Wed, 21 Oct 2015 07:28:00 GMT"), expected);
+        assert_eq!(parse_date("Wednesday, 21-Oct-15 07:28:00 GMT"), expected);
+        assert_eq!(parse_date("Wed Oct 21 07:28:00 2015"), expected);
+        assert_eq!(parse_date("21 Oct 2015"), None);
+        assert_eq!(parse_date("ab€ 21 Oct 2015 07:28:00"), expected);
+        assert_eq!(parse_date("Wed, 21 Ok€ 2015 07:28:00 GMT"), None);
+        assert_eq!(format_date(1445412480), "Wed, 21 Oct 2015 07:28:00 GMT");
+    }
+
+    #[test]
+    fn test_parse() {
+        let cookie = Cookie::parse(
+            "sid=abc; Domain=.Example.com; Path=/api; Max-Age=60; Expires=Wed, 21 Oct 2015 \
+             07:28:00 GMT; Secure; HttpOnly; SameSite=lax",
+            &url("https://www.example.com/login"),
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            cookie,
+            Cookie {
+                name: "sid".into(),
+                value: "abc".into(),
+                domain: "example.com".into(),
+                host_only: false,
+                path: "/api".into(),
+                expires: Some(1060),
+                secure: true,
+                http_only: true,
+                same_site: Some("Lax".into()),
+            }
+        );
+
+        let cookie = Cookie::parse("a=1", &url("http://example.com/a/b/c"), 0).unwrap();
+        assert!(cookie.host_only);
+        assert_eq!(cookie.path, "/a/b");
+    }
+
+    #[test]
+    fn test_parse_rejects_foreign_domains() {
+        let origin = url("https://www.example.com/");
+        assert_eq!(Cookie::parse("a=1; Domain=other.com", &origin, 0), None);
+        assert_eq!(Cookie::parse("a=1; Domain=com", &origin, 0), None);
+        assert_eq!(Cookie::parse("no-equals-sign", &origin, 0), None);
+    }
+
+    #[test]
+    fn test_header() {
+        let mut jar = CookieJar::default();
+        let origin = url("https://api.example.com/v1/users");
+        jar.store(
+            [
+                "root=1; Path=/; Domain=example.com",
+                "v1=2; Path=/v1",
+                "secure=3; Path=/; Secure",
+                "other=4; Path=/v2",
+            ]
+            .into_iter(),
+            &origin,
+        );
+        assert_eq!(
+            jar.header(&url("https://api.example.com/v1/items"))
+                .as_deref(),
+            Some("v1=2; root=1; secure=3")
+        );
+        assert_eq!(
+            jar.header(&url("http://www.example.com/v1")).as_deref(),
+            Some("root=1")
+        );
+        assert_eq!(jar.header(&url("https://example.org/")), None);
+    }
+
+    #[test]
+    fn test_expired_cookie_deletes() {
+        let mut jar = CookieJar::default();
+        let origin = url("http://example.com/");
+        jar.store(["a=1", "b=2"].into_iter(), &origin);
+        jar.store(["a=; Max-Age=0"].into_iter(), &origin);
+        assert_eq!(jar.header(&origin).as_deref(), Some("b=2"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let origin = url("https://example.com/");
+        let cookie = Cookie::parse(
+            "a=1; Domain=example.com; Path=/x; Expires=Wed, 21 Oct 2015 07:28:00 GMT; HttpOnly",
+            &origin,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            Cookie::parse(&cookie.to_set_cookie(), &origin, 0),
+            Some(cookie)
+        );
+    }
+}
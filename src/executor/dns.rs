@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    TokioAsyncResolver,
+};
+use serde::{Deserialize, Serialize};
+use tokio::net::lookup_host;
+
+/// Port DNS servers listen on unless told otherwise.
+const DNS_PORT: u16 = 53;
+
+/// How host names are resolved, from the `[dns]` table of the configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsSettings {
+    /// DNS server to ask instead of the system resolver, e.g. `10.0.0.2` or `10.0.0.2:5353`.
+    pub resolver: Option<String>,
+    /// Addresses to connect to in place of resolving a host, like curl's `--resolve`. Keys are
+    /// `host:port`, or `host` for every port.
+    pub overrides: HashMap<String, Vec<IpAddr>>,
+    /// Settings for a workspace, added to the ones above when that workspace is open.
+    pub workspaces: HashMap<String, DnsSettings>,
+}
+
+impl DnsSettings {
+    /// The settings in effect in a workspace, whose own resolver and overrides win.
+    pub fn for_workspace(&self, workspace: &str) -> Self {
+        let mut settings = Self {
+            resolver: self.resolver.clone(),
+            overrides: self.overrides.clone(),
+            workspaces: HashMap::new(),
+        };
+        if let Some(own) = self.workspaces.get(workspace) {
+            if own.resolver.is_some() {
+                settings.resolver = own.resolver.clone();
+            }
+            settings.overrides.extend(own.overrides.clone());
+        }
+        settings
+    }
+
+    /// The override for a host and port, by the key it was configured under, preferring one for
+    /// that port over one for the whole host.
+    pub fn override_for(&self, host: &str, port: u16) -> Option<(String, &[IpAddr])> {
+        let host = host.to_ascii_lowercase();
+        [format!("{host}:{port}"), host]
+            .into_iter()
+            .find_map(|key| {
+                let addrs = self
+                    .overrides
+                    .iter()
+                    .find(|(pattern, _)| pattern.eq_ignore_ascii_case(&key))
+                    .map(|(_, addrs)| addrs.as_slice())?;
+                Some((key, addrs))
+            })
+    }
+
+    /// Where the configured DNS server listens, if one is configured.
+    fn resolver_addr(&self) -> Result<Option<SocketAddr>> {
+        let Some(resolver) = self.resolver.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        if let Ok(ip) = resolver.parse::<IpAddr>() {
+            return Ok(Some(SocketAddr::new(ip, DNS_PORT)));
+        }
+        resolver
+            .parse()
+            .map(Some)
+            .map_err(|_| eyre!("Not a DNS server address: {resolver}"))
+    }
+
+    /// Resolves a host to the addresses to try, along with what resolved it when it wasn't the
+    /// system resolver.
+    pub async fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> Result<(Vec<SocketAddr>, Option<String>)> {
+        if let Some((key, ips)) = self.override_for(host, port) {
+            let addrs = ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect();
+            let ips: Vec<String> = ips.iter().map(IpAddr::to_string).collect();
+            return Ok((addrs, Some(format!("override {key} → {}", ips.join(", ")))));
+        }
+        let Some(server) = self.resolver_addr()? else {
+            return Ok((lookup_host((host, port)).await?.collect(), None));
+        };
+        let servers = NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true);
+        let resolver = TokioAsyncResolver::tokio(
+            ResolverConfig::from_parts(None, vec![], servers),
+            ResolverOpts::default(),
+        );
+        let addrs = resolver
+            .lookup_ip(host)
+            .await
+            .map_err(|err| eyre!("Resolving {host} with {server}: {err}"))?
+            .iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect();
+        Ok((addrs, Some(format!("DNS server {server}"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn test_for_workspace_and_override_for() {
+        let settings = DnsSettings {
+            resolver: Some("10.0.0.2".into()),
+            overrides: HashMap::from([
+                ("api.example.com".into(), vec![ip("10.1.0.1")]),
+                ("api.example.com:8443".into(), vec![ip("10.1.0.2")]),
+            ]),
+            workspaces: HashMap::from([(
+                "staging".into(),
+                DnsSettings {
+                    overrides: HashMap::from([("api.example.com".into(), vec![ip("10.2.0.1")])]),
+                    ..DnsSettings::default()
+                },
+            )]),
+        };
+        assert_eq!(
+            settings.override_for("API.example.com", 8443),
+            Some(("api.example.com:8443".into(), &[ip("10.1.0.2")][..]))
+        );
+        assert_eq!(
+            settings.override_for("api.example.com", 443),
+            Some(("api.example.com".into(), &[ip("10.1.0.1")][..]))
+        );
+        assert_eq!(settings.override_for("example.com", 443), None);
+
+        let staging = settings.for_workspace("staging");
+        assert_eq!(staging.resolver.as_deref(), Some("10.0.0.2"));
+        assert_eq!(
+            staging.override_for("api.example.com", 443),
+            Some(("api.example.com".into(), &[ip("10.2.0.1")][..]))
+        );
+        assert!(staging.workspaces.is_empty());
+        assert_eq!(
+            settings.for_workspace("default").overrides,
+            settings.overrides
+        );
+    }
+
+    #[test]
+    fn test_resolver_addr() {
+        let settings = |resolver: &str| DnsSettings {
+            resolver: Some(resolver.into()),
+            ..DnsSettings::default()
+        };
+        assert_eq!(
+            settings("10.0.0.2").resolver_addr().unwrap(),
+            Some("10.0.0.2:53".parse().unwrap())
+        );
+        assert_eq!(
+            settings("[::1]:5353").resolver_addr().unwrap(),
+            Some("[::1]:5353".parse().unwrap())
+        );
+        assert!(settings("dns.example.com").resolver_addr().is_err());
+        assert_eq!(DnsSettings::default().resolver_addr().unwrap(), None);
+    }
+}
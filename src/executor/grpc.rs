@@ -0,0 +1,789 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::{eyre::eyre, Result};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::client::conn::http2;
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue};
+use hyper::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use percent_encoding::percent_decode_str;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor, SerializeOptions};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use url::Url;
+
+use self::reflection::{
+    MessageRequest,
+    MessageResponse,
+    ServerReflectionRequest,
+    ServerReflectionResponse,
+};
+use super::tls::TlsSettings;
+use super::{connect, within, Client, HttpRequest, Timing};
+
+pub mod reflection;
+
+/// Directory in the data dir where service definitions are kept, one file per server.
+const DESCRIPTORS_DIR: &str = "grpc";
+
+/// The reflection services to ask for descriptors, newest first.
+const REFLECTION_SERVICES: [&str; 2] = [
+    "grpc.reflection.v1.ServerReflection",
+    "grpc.reflection.v1alpha.ServerReflection",
+];
+
+/// The status code of a call that the server doesn't implement.
+const UNIMPLEMENTED: i32 = 12;
+
+/// How a call ended, as told by the `grpc-status` and `grpc-message` trailers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub code: i32,
+    pub message: String,
+    pub trailers: Vec<(String, String)>,
+}
+
+/// A message of the response, as JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcMessage {
+    pub time: SystemTime,
+    pub json: String,
+}
+
+/// What happened during a call, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrpcEvent {
+    /// The response headers, sent before any message.
+    Headers(Vec<(String, String)>),
+    Message(GrpcMessage),
+    Finished(Status, Duration),
+    Failed(String),
+}
+
+/// A call run by a background task, which ends when this is dropped.
+#[derive(Debug)]
+pub struct GrpcCall {
+    events: UnboundedReceiver<GrpcEvent>,
+}
+
+/// Service definitions loaded for each server, kept on disk so they outlive the session.
+#[derive(Debug, Default)]
+pub struct DescriptorCache {
+    dir: Option<PathBuf>,
+}
+
+/// A call as far as its response headers, with the messages still to read.
+struct Call {
+    headers: HeaderMap,
+    body: Incoming,
+    buffer: Vec<u8>,
+    trailers: Option<HeaderMap>,
+    read_timeout: u32,
+}
+
+/// Whether a URL as typed in asks for a gRPC call rather than an HTTP request.
+pub fn is_grpc(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("grpc://") || url.starts_with("grpcs://")
+}
+
+/// The method a URL calls, from its path, e.g. `helloworld.Greeter/SayHello`.
+pub fn method_path(url: &str) -> Option<&str> {
+    let (_, rest) = url.trim().split_once("://")?;
+    let (_, path) = rest.split_once('/')?;
+    Some(path).filter(|path| path.contains('/'))
+}
+
+/// The name of a status code, e.g. `NOT_FOUND` for 5.
+pub fn code_name(code: i32) -> &'static str {
+    match code {
+        0 => "OK",
+        1 => "CANCELLED",
+        2 => "UNKNOWN",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        6 => "ALREADY_EXISTS",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        9 => "FAILED_PRECONDITION",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => "UNAUTHENTICATED",
+        _ => "",
+    }
+}
+
+/// Compiles `.proto` files, looking for their imports next to them.
+pub fn load_protos(files: &[PathBuf]) -> Result<DescriptorPool> {
+    let mut includes: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.parent())
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                dir.to_path_buf()
+            }
+        })
+        .collect();
+    includes.dedup();
+    let set = protox::Compiler::new(includes)?
+        .include_imports(true)
+        .open_files(files)?
+        .file_descriptor_set();
+    Ok(DescriptorPool::from_file_descriptor_set(set)?)
+}
+
+/// Every method of every service, in the order they were defined.
+pub fn methods(pool: &DescriptorPool) -> Vec<MethodDescriptor> {
+    pool.services()
+        .filter(|service| !service.full_name().starts_with("grpc.reflection."))
+        .flat_map(|service| service.methods().collect::<Vec<_>>())
+        .collect()
+}
+
+/// Finds the method a URL calls.
+pub fn find_method(pool: &DescriptorPool, url: &str) -> Option<MethodDescriptor> {
+    let (service, method) = method_path(url)?.split_once('/')?;
+    pool.get_service_by_name(service)?
+        .methods()
+        .find(|m| m.name() == method)
+}
+
+/// The request message of a method, read from JSON.
+pub fn encode_message(method: &MethodDescriptor, json: &str) -> Result<Vec<u8>> {
+    let json = if json.trim().is_empty() { "{}" } else { json };
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(method.input(), &mut deserializer)?;
+    deserializer.end()?;
+    Ok(message.encode_to_vec())
+}
+
+/// The request message of a method with every field at its default, as a starting point.
+pub fn template(method: &MethodDescriptor) -> String {
+    let message = DynamicMessage::new(method.input());
+    let options = SerializeOptions::new().skip_default_fields(false);
+    let mut json = Vec::new();
+    let mut serializer = serde_json::Serializer::pretty(&mut json);
+    match message.serialize_with_options(&mut serializer, &options) {
+        Ok(()) => String::from_utf8_lossy(&json).into_owned(),
+        Err(_) => "{}".into(),
+    }
+}
+
+/// Asks the server for the descriptors of its services through the reflection service.
+pub async fn reflect(client: &Client, request: &HttpRequest) -> Result<DescriptorPool> {
+    let mut last_err = None;
+    for service in REFLECTION_SERVICES {
+        let path = format!("{service}/ServerReflectionInfo");
+        match reflect_with(client, request, &path).await {
+            Err(err) if err.downcast_ref::<Status>().map(|s| s.code) == Some(UNIMPLEMENTED) => {
+                last_err = Some(err);
+            }
+            result => return result,
+        }
+    }
+    Err(last_err.unwrap_or_else(|| eyre!("The server has no reflection service")))
+}
+
+async fn reflect_with(
+    client: &Client,
+    request: &HttpRequest,
+    path: &str,
+) -> Result<DescriptorPool> {
+    let services: Vec<String> = ask(
+        client,
+        request,
+        path,
+        vec![MessageRequest::ListServices(String::new())],
+    )
+    .await?
+    .into_iter()
+    .filter_map(|response| match response {
+        MessageResponse::ListServicesResponse(list) => Some(list.service),
+        _ => None,
+    })
+    .flatten()
+    .map(|service| service.name)
+    .filter(|name| !name.starts_with("grpc.reflection."))
+    .collect();
+
+    // Servers usually send the imports of a file along with it, but only have to on request.
+    let mut files = BTreeMap::new();
+    let mut questions: Vec<MessageRequest> = services
+        .into_iter()
+        .map(MessageRequest::FileContainingSymbol)
+        .collect();
+    let mut asked = HashSet::new();
+    while !questions.is_empty() {
+        for response in ask(client, request, path, questions).await? {
+            match response {
+                MessageResponse::FileDescriptorResponse(response) => {
+                    for bytes in response.file_descriptor_proto {
+                        let file = FileDescriptorProto::decode(bytes.as_slice())?;
+                        files.insert(file.name().to_string(), file);
+                    }
+                }
+                MessageResponse::ErrorResponse(err) => {
+                    return Err(eyre!("Reflection failed: {}", err.error_message));
+                }
+                MessageResponse::ListServicesResponse(_) => {}
+            }
+        }
+        questions = files
+            .values()
+            .flat_map(|file| file.dependency.iter())
+            .filter(|name| !files.contains_key(*name) && asked.insert(name.to_string()))
+            .map(|name| MessageRequest::FileByFilename(name.clone()))
+            .collect();
+    }
+    let set = FileDescriptorSet {
+        file: files.into_values().collect(),
+    };
+    Ok(DescriptorPool::from_file_descriptor_set(set)?)
+}
+
+/// Sends requests to the reflection service on a single stream, returning its answers.
+async fn ask(
+    client: &Client,
+    request: &HttpRequest,
+    path: &str,
+    questions: Vec<MessageRequest>,
+) -> Result<Vec<MessageResponse>> {
+    let messages = questions
+        .into_iter()
+        .map(|question| {
+            ServerReflectionRequest {
+                host: String::new(),
+                message_request: Some(question),
+            }
+            .encode_to_vec()
+        })
+        .collect();
+    let mut call = Call::start(client, request, path, messages).await?;
+    call.read_timeout = request.options.read_timeout;
+    let mut answers = Vec::new();
+    while let Some(message) = call.next_message().await? {
+        let response = ServerReflectionResponse::decode(message.as_slice())?;
+        answers.extend(response.message_response);
+    }
+    match call.status() {
+        status if status.code == 0 => Ok(answers),
+        status => Err(status.into()),
+    }
+}
+
+impl DescriptorCache {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            dir: Some(data_dir.join(DESCRIPTORS_DIR)),
+        }
+    }
+
+    pub fn load(&self, server: &str) -> Option<DescriptorPool> {
+        DescriptorPool::decode(fs::read(self.file(server)?).ok()?.as_slice()).ok()
+    }
+
+    pub fn save(&self, server: &str, pool: &DescriptorPool) -> Result<()> {
+        let (Some(dir), Some(file)) = (&self.dir, self.file(server)) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+        fs::write(file, pool.encode_to_vec())?;
+        Ok(())
+    }
+
+    /// A file named after the server, e.g. `localhost_50051.pb` for `grpc://localhost:50051`.
+    fn file(&self, server: &str) -> Option<PathBuf> {
+        let server = server.split_once("://").map_or(server, |(_, rest)| rest);
+        let server = server.split('/').next().unwrap_or_default();
+        let name: String = server
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Some(self.dir.as_ref()?.join(format!("{name}.pb")))
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, code_name(self.code))?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Status {}
+
+impl GrpcCall {
+    /// Makes the call in the background, reporting progress through [`GrpcCall::poll`].
+    pub fn open(
+        client: Client,
+        request: HttpRequest,
+        method: MethodDescriptor,
+        message: Vec<u8>,
+    ) -> Self {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = events_tx.closed() => {}
+                result = run(&client, &request, &method, message, &events_tx) => {
+                    if let Err(err) = result {
+                        let _ = events_tx.send(GrpcEvent::Failed(err.to_string()));
+                    }
+                }
+            }
+        });
+        Self { events: events_rx }
+    }
+
+    /// Takes the next event, if one has happened.
+    pub fn poll(&mut self) -> Option<GrpcEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+async fn run(
+    client: &Client,
+    request: &HttpRequest,
+    method: &MethodDescriptor,
+    message: Vec<u8>,
+    events: &UnboundedSender<GrpcEvent>,
+) -> Result<()> {
+    let started = Instant::now();
+    let path = format!("{}/{}", method.parent_service().full_name(), method.name());
+    let mut call = Call::start(client, request, &path, vec![message]).await?;
+    // A stream may go quiet for as long as the server likes.
+    if !method.is_server_streaming() {
+        call.read_timeout = request.options.read_timeout;
+    }
+    let _ = events.send(GrpcEvent::Headers(pairs(&call.headers)));
+    while let Some(message) = call.next_message().await? {
+        let message = DynamicMessage::decode(method.output(), message.as_slice())?;
+        let _ = events.send(GrpcEvent::Message(GrpcMessage {
+            time: SystemTime::now(),
+            json: serde_json::to_string_pretty(&message)?,
+        }));
+    }
+    let _ = events.send(GrpcEvent::Finished(call.status(), started.elapsed()));
+    Ok(())
+}
+
+impl Call {
+    /// Sends the messages over a new HTTP/2 connection and waits for the response headers.
+    async fn start(
+        client: &Client,
+        request: &HttpRequest,
+        path: &str,
+        messages: Vec<Vec<u8>>,
+    ) -> Result<Self> {
+        let url = Url::parse(request.url.trim())?;
+        if !matches!(url.scheme(), "grpc" | "grpcs") {
+            return Err(eyre!("Not a gRPC URL: {url}"));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| eyre!("URL has no host: {url}"))?
+            .to_string();
+        let port = url
+            .port()
+            .unwrap_or(if url.scheme() == "grpcs" { 443 } else { 80 });
+
+        let options = &request.options;
+        let settings = TlsSettings::for_host(&client.tls, &host)
+            .cloned()
+            .unwrap_or_default();
+        let proxy = client.proxy.proxy_for(&url)?;
+        let mut timing = Timing::default();
+        let stream = within(
+            options.connect_timeout,
+            "Connecting",
+            connect(
+                &url,
+                &host,
+                port,
+                &settings,
+                &client.dns,
+                proxy.as_ref(),
+                // gRPC only runs over HTTP/2.
+                vec![b"h2".to_vec()],
+                &mut timing,
+            ),
+        )
+        .await?;
+        let (mut sender, connection) =
+            http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await?;
+        tokio::spawn(connection);
+
+        let scheme = if url.scheme() == "grpcs" {
+            "https"
+        } else {
+            "http"
+        };
+        let mut builder = Request::post(format!("{scheme}://{host}:{port}/{path}"))
+            .header(header::CONTENT_TYPE, "application/grpc")
+            .header(header::TE, "trailers")
+            .header(
+                header::USER_AGENT,
+                concat!("papirus/", env!("CARGO_PKG_VERSION")),
+            );
+        // Headers go along as metadata.
+        for (name, value) in &request.headers {
+            builder = builder.header(
+                HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        let mut body = Vec::new();
+        for message in messages {
+            body.push(0); // Not compressed
+            body.extend((message.len() as u32).to_be_bytes());
+            body.extend(message);
+        }
+        let response = within(options.read_timeout, "Waiting for the response", async {
+            Ok(sender
+                .send_request(builder.body(Full::new(Bytes::from(body)))?)
+                .await?)
+        })
+        .await?;
+        let (parts, body) = response.into_parts();
+        if parts.status != hyper::StatusCode::OK {
+            return Err(eyre!(
+                "The server answered with HTTP status {}",
+                parts.status
+            ));
+        }
+        // A call that fails straight away has its status in the headers.
+        let trailers = parts
+            .headers
+            .contains_key("grpc-status")
+            .then(|| parts.headers.clone());
+        Ok(Self {
+            headers: parts.headers,
+            body,
+            buffer: Vec::new(),
+            trailers,
+            read_timeout: 0,
+        })
+    }
+
+    /// Reads the next message of the response, or `None` once the trailers have come.
+    async fn next_message(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            if self.buffer.len() >= 5 {
+                let len = u32::from_be_bytes(self.buffer[1..5].try_into()?) as usize;
+                if self.buffer.len() >= 5 + len {
+                    if self.buffer[0] != 0 {
+                        return Err(eyre!("Compressed messages aren't supported"));
+                    }
+                    let message = self.buffer[5..5 + len].to_vec();
+                    self.buffer.drain(..5 + len);
+                    return Ok(Some(message));
+                }
+            }
+            if self.trailers.is_some() {
+                return match self.buffer.is_empty() {
+                    true => Ok(None),
+                    false => Err(eyre!("The response ended in the middle of a message")),
+                };
+            }
+            let body = &mut self.body;
+            let frame = within(self.read_timeout, "Reading the response", async {
+                Ok(body.frame().await.transpose()?)
+            })
+            .await?;
+            match frame {
+                None => self.trailers = Some(HeaderMap::new()),
+                Some(frame) => match frame.into_data() {
+                    Ok(data) => self.buffer.extend_from_slice(&data),
+                    Err(frame) => self.trailers = frame.into_trailers().ok(),
+                },
+            }
+        }
+    }
+
+    /// The status the call ended with, once all its messages have been read.
+    fn status(self) -> Status {
+        let trailers = self.trailers.unwrap_or_default();
+        let get = |name: &str| trailers.get(name).and_then(|value| value.to_str().ok());
+        let message = get("grpc-message").unwrap_or_default();
+        Status {
+            code: get("grpc-status")
+                .and_then(|code| code.parse().ok())
+                .unwrap_or(2),
+            message: percent_decode_str(message).decode_utf8_lossy().into_owned(),
+            trailers: pairs(&trailers),
+        }
+    }
+}
+
+fn pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::fs;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::stream::{self, Stream};
+    use pretty_assertions::assert_eq;
+    use tonic::body::Body;
+    use tonic::codegen::{http, Service};
+    use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+    use tonic_prost::ProstCodec;
+
+    use super::*;
+
+    const PROTO: &str = r#"syntax = "proto3";
+package greet;
+
+service Greeter {
+  rpc SayHello(HelloRequest) returns (HelloReply);
+  rpc Count(HelloRequest) returns (stream HelloReply);
+}
+
+message HelloRequest {
+  string name = 1;
+  int32 times = 2;
+}
+
+message HelloReply {
+  string message = 1;
+}
+"#;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct HelloRequest {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(int32, tag = "2")]
+        times: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct HelloReply {
+        #[prost(string, tag = "1")]
+        message: String,
+    }
+
+    type Replies = Pin<Box<dyn Stream<Item = Result<HelloReply, tonic::Status>> + Send>>;
+    type Answer<T> =
+        Pin<Box<dyn Future<Output = Result<tonic::Response<T>, tonic::Status>> + Send>>;
+
+    struct SayHello;
+    struct Count;
+
+    impl UnaryService<HelloRequest> for SayHello {
+        type Future = Answer<HelloReply>;
+        type Response = HelloReply;
+
+        fn call(&mut self, request: tonic::Request<HelloRequest>) -> Self::Future {
+            let name = request.into_inner().name;
+            Box::pin(async move {
+                if name.is_empty() {
+                    return Err(tonic::Status::invalid_argument("name is required"));
+                }
+                let mut response = tonic::Response::new(HelloReply {
+                    message: format!("Hello, {name}"),
+                });
+                response
+                    .metadata_mut()
+                    .insert("x-greeter", "test".parse().unwrap());
+                Ok(response)
+            })
+        }
+    }
+
+    impl ServerStreamingService<HelloRequest> for Count {
+        type Future = Answer<Replies>;
+        type Response = HelloReply;
+        type ResponseStream = Replies;
+
+        fn call(&mut self, request: tonic::Request<HelloRequest>) -> Self::Future {
+            let request = request.into_inner();
+            let replies = (1..=request.times).map(move |n| {
+                Ok(HelloReply {
+                    message: format!("{n} {}", request.name),
+                })
+            });
+            Box::pin(async move { Ok(tonic::Response::new(Box::pin(stream::iter(replies)) as _)) })
+        }
+    }
+
+    /// What tonic generates for the service above, written out.
+    #[derive(Clone)]
+    struct Greeter;
+
+    impl NamedService for Greeter {
+        const NAME: &'static str = "greet.Greeter";
+    }
+
+    impl Service<http::Request<Body>> for Greeter {
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+        type Response = http::Response<Body>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+            Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(match request.uri().path() {
+                    "/greet.Greeter/SayHello" => grpc.unary(SayHello, request).await,
+                    "/greet.Greeter/Count" => grpc.server_streaming(Count, request).await,
+                    _ => tonic::Status::unimplemented("").into_http(),
+                })
+            })
+        }
+    }
+
+    async fn call(client: &Client, request: &HttpRequest, json: &str) -> Vec<GrpcEvent> {
+        let pool = reflect(client, request).await.unwrap();
+        let method = find_method(&pool, &request.url).unwrap();
+        let message = encode_message(&method, json).unwrap();
+        let mut call = GrpcCall::open(client.clone(), request.clone(), method, message);
+        let mut events = Vec::new();
+        while let Some(event) = call.events.recv().await {
+            let done = matches!(event, GrpcEvent::Finished(..) | GrpcEvent::Failed(_));
+            events.push(event);
+            if done {
+                break;
+            }
+        }
+        events
+    }
+
+    fn messages(events: &[GrpcEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                GrpcEvent::Message(message) => Some(message.json.replace(char::is_whitespace, "")),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn status(events: &[GrpcEvent]) -> Status {
+        match events.last() {
+            Some(GrpcEvent::Finished(status, _)) => status.clone(),
+            event => panic!("The call didn't finish: {event:?}"),
+        }
+    }
+
+    #[test]
+    fn test_load_protos() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-grpc-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let file = dir.join("greet.proto");
+        fs::write(&file, PROTO)?;
+        let pool = load_protos(&[file]);
+        fs::remove_dir_all(&dir)?;
+
+        let pool = pool?;
+        let names: Vec<String> = methods(&pool)
+            .iter()
+            .map(|method| method.full_name().to_string())
+            .collect();
+        assert_eq!(names, ["greet.Greeter.SayHello", "greet.Greeter.Count"]);
+        let method = find_method(&pool, "grpc://localhost:50051/greet.Greeter/Count").unwrap();
+        assert!(method.is_server_streaming());
+        assert!(encode_message(&method, r#"{"name": "Ann", "times": 2}"#).is_ok());
+        assert!(encode_message(&method, r#"{"nmae": "Ann"}"#).is_err());
+        assert_eq!(
+            template(&method).replace(char::is_whitespace, ""),
+            r#"{"name":"","times":0}"#
+        );
+
+        let cache = DescriptorCache::new(dir.clone());
+        assert_eq!(
+            cache.file("grpcs://api.example.com:443/greet.Greeter/Count"),
+            Some(dir.join("grpc/api.example.com_443.pb"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_calls_with_reflection() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-grpc-call-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("greet.proto"), PROTO)?;
+        let descriptors = protox::compile(["greet.proto"], [&dir]);
+        fs::remove_dir_all(&dir)?;
+
+        let reflection = tonic_reflection::server::Builder::configure()
+            .register_file_descriptor_set(descriptors?)
+            .build_v1alpha()?;
+        let incoming = TcpIncoming::bind("127.0.0.1:0".parse()?)?;
+        let addr = incoming.local_addr()?;
+        tokio::spawn(
+            Server::builder()
+                .add_service(reflection)
+                .add_service(Greeter)
+                .serve_with_incoming(incoming),
+        );
+
+        let client = Client::default();
+        let request = HttpRequest::get(&format!("grpc://{addr}/greet.Greeter/SayHello"));
+        let events = call(&client, &request, r#"{"name": "Ann"}"#).await;
+        let GrpcEvent::Headers(headers) = &events[0] else {
+            panic!("No headers: {events:?}");
+        };
+        assert!(headers.contains(&("x-greeter".into(), "test".into())));
+        assert_eq!(messages(&events), [r#"{"message":"Hello,Ann"}"#]);
+        assert_eq!(status(&events).code, 0);
+
+        let events = call(&client, &request, "{}").await;
+        assert_eq!(messages(&events), Vec::<String>::new());
+        assert_eq!(
+            status(&events).to_string(),
+            "3 INVALID_ARGUMENT: name is required"
+        );
+
+        let request = HttpRequest::get(&format!("grpc://{addr}/greet.Greeter/Count"));
+        let events = call(&client, &request, r#"{"name": "sheep", "times": 3}"#).await;
+        assert_eq!(
+            messages(&events),
+            [
+                r#"{"message":"1sheep"}"#,
+                r#"{"message":"2sheep"}"#,
+                r#"{"message":"3sheep"}"#
+            ]
+        );
+        assert_eq!(status(&events).code, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+// The messages of the gRPC server reflection service, which both versions of it share.
+
+/// A request on the `ServerReflectionInfo` stream.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerReflectionRequest {
+    #[prost(string, tag = "1")]
+    pub host: String,
+    #[prost(oneof = "MessageRequest", tags = "3, 4, 7")]
+    pub message_request: Option<MessageRequest>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum MessageRequest {
+    #[prost(string, tag = "3")]
+    FileByFilename(String),
+    #[prost(string, tag = "4")]
+    FileContainingSymbol(String),
+    #[prost(string, tag = "7")]
+    ListServices(String),
+}
+
+/// The answer to one [`ServerReflectionRequest`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerReflectionResponse {
+    #[prost(oneof = "MessageResponse", tags = "4, 6, 7")]
+    pub message_response: Option<MessageResponse>,
+}
+
+// Named as in the `.proto` file.
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum MessageResponse {
+    #[prost(message, tag = "4")]
+    FileDescriptorResponse(FileDescriptorResponse),
+    #[prost(message, tag = "6")]
+    ListServicesResponse(ListServiceResponse),
+    #[prost(message, tag = "7")]
+    ErrorResponse(ErrorResponse),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FileDescriptorResponse {
+    /// Serialized `FileDescriptorProto`s.
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListServiceResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub service: Vec<ServiceResponse>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServiceResponse {
+    #[prost(string, tag = "1")]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ErrorResponse {
+    #[prost(int32, tag = "1")]
+    pub error_code: i32,
+    #[prost(string, tag = "2")]
+    pub error_message: String,
+}
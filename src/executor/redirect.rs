@@ -0,0 +1,150 @@
+use color_eyre::Result;
+use url::Url;
+
+use super::{unix, HttpRequest, HttpResponse};
+
+/// Headers describing a body, dropped along with it when a redirect switches to `GET`.
+const BODY_HEADERS: [&str; 4] = [
+    "content-type",
+    "content-length",
+    "content-encoding",
+    "transfer-encoding",
+];
+
+/// Builds the request that follows a redirect, or `None` if the response isn't one.
+pub fn next_request(request: &HttpRequest, response: &HttpResponse) -> Result<Option<HttpRequest>> {
+    if !matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+        return Ok(None);
+    }
+    let Some(location) = response.header("location") else {
+        return Ok(None);
+    };
+    let (socket, from) = match unix::split_url(&response.url) {
+        Some(split) => split.map(|(socket, url)| (Some(socket), url))?,
+        None => (None, Url::parse(&response.url)?),
+    };
+    let to = from.join(location.trim())?;
+
+    let mut next = request.clone();
+    next.url = match &socket {
+        // Staying on the server behind the socket.
+        Some(socket) if to.origin() == from.origin() => unix::join_url(socket, &to),
+        _ => to.to_string(),
+    };
+    // Browsers turn a POST into a GET on 301 and 302 too, and servers have come to rely on it.
+    let to_get = match response.status {
+        303 => request.method != "HEAD",
+        301 | 302 => request.method == "POST",
+        _ => false,
+    };
+    if to_get && !request.options.keep_method {
+        next.method = "GET".into();
+        next.body.clear();
+        next.headers
+            .retain(|(name, _)| !BODY_HEADERS.contains(&name.to_ascii_lowercase().as_str()));
+    }
+    if from.origin() != to.origin() && !request.options.forward_authorization {
+        next.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("authorization"));
+    }
+    Ok(Some(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn redirect(status: u16, url: &str, location: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            url: url.into(),
+            headers: vec![("Location".into(), location.into())],
+            ..HttpResponse::default()
+        }
+    }
+
+    fn post() -> HttpRequest {
+        HttpRequest {
+            method: "POST".into(),
+            url: "https://api.example.com/login".into(),
+            headers: vec![
+                ("Authorization".into(), "Bearer token".into()),
+                ("Content-Type".into(), "application/json".into()),
+            ],
+            body: b"{}".to_vec(),
+            ..HttpRequest::default()
+        }
+    }
+
+    #[test]
+    fn test_relative_location() -> Result<()> {
+        let next = next_request(
+            &post(),
+            &redirect(307, "https://api.example.com/a/b", "../c"),
+        )?;
+        let next = next.unwrap();
+        assert_eq!(next.url, "https://api.example.com/c");
+        assert_eq!(next.method, "POST");
+        assert_eq!(next.body, b"{}");
+        assert_eq!(next.headers, post().headers);
+        Ok(())
+    }
+
+    #[test]
+    fn test_see_other_switches_to_get() -> Result<()> {
+        let next = next_request(&post(), &redirect(303, "https://api.example.com/", "/done"))?;
+        let next = next.unwrap();
+        assert_eq!(next.method, "GET");
+        assert!(next.body.is_empty());
+        assert_eq!(
+            next.headers,
+            vec![("Authorization".into(), "Bearer token".into())]
+        );
+
+        let mut request = post();
+        request.options.keep_method = true;
+        let next = next_request(
+            &request,
+            &redirect(302, "https://api.example.com/", "/done"),
+        )?;
+        assert_eq!(next.unwrap().method, "POST");
+        Ok(())
+    }
+
+    #[test]
+    fn test_authorization_stays_on_origin() -> Result<()> {
+        let response = redirect(308, "https://api.example.com/", "https://cdn.example.com/");
+        let next = next_request(&post(), &response)?.unwrap();
+        assert!(next.headers.iter().all(|(name, _)| name != "Authorization"));
+
+        let mut request = post();
+        request.options.forward_authorization = true;
+        let next = next_request(&request, &response)?.unwrap();
+        assert_eq!(next.headers, post().headers);
+        Ok(())
+    }
+
+    #[test]
+    fn test_redirect_stays_on_unix_socket() -> Result<()> {
+        let socket = "unix:///var/run/docker.sock:/v1.43/a?b=1";
+        let next = next_request(&post(), &redirect(307, socket, "c"))?.unwrap();
+        assert_eq!(next.url, "unix:///var/run/docker.sock:/v1.43/c");
+        let next = next_request(&post(), &redirect(307, socket, "https://a/"))?.unwrap();
+        assert_eq!(next.url, "https://a/");
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_a_redirect() -> Result<()> {
+        assert_eq!(
+            next_request(&post(), &redirect(200, "https://a/", "/b"))?,
+            None
+        );
+        let mut response = redirect(302, "https://a/", "/b");
+        response.headers.clear();
+        assert_eq!(next_request(&post(), &response)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    time::Duration,
+};
+
+use color_eyre::Report;
+
+use super::cookies::parse_date;
+use super::{HttpResponse, RequestOptions};
+
+/// Longest wait between attempts, however many there have been or however long the server asks
+/// for.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long to wait before repeating a request, or `None` if the outcome of attempt number
+/// `attempt` should stand.
+pub fn delay(
+    options: &RequestOptions,
+    attempt: u32,
+    outcome: Result<&HttpResponse, &Report>,
+    now: u64,
+) -> Option<Duration> {
+    if attempt >= options.max_attempts {
+        return None;
+    }
+    match outcome {
+        // A server asking for a longer wait than that gets its response shown instead, rather
+        // than the tab waiting on it for hours.
+        Ok(response) if options.retry_statuses.contains(&response.status) => {
+            match retry_after(response, now) {
+                Some(wait) => (wait <= MAX_BACKOFF).then_some(wait),
+                None => Some(backoff(options, attempt)),
+            }
+        }
+        Err(err) if options.retry_on_connection_error && is_connection_error(err) => {
+            Some(backoff(options, attempt))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a request failed because the connection did, rather than because it can never work,
+/// e.g. a malformed URL or a certificate the TLS handshake rejected.
+pub fn is_connection_error(err: &Report) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<hyper::Error>().is_some()
+            || cause
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() != io::ErrorKind::InvalidData)
+    })
+}
+
+/// Exponential backoff with jitter: the initial delay doubles with each attempt, and a random
+/// amount of up to half of it is taken off so that clients retrying together spread out.
+fn backoff(options: &RequestOptions, attempt: u32) -> Duration {
+    let delay = Duration::from_millis(options.retry_backoff.into())
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF);
+    let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.mul_f64(1.0 - jitter / 2.0)
+}
+
+/// The wait asked for by a `Retry-After` header, in seconds or as a date.
+fn retry_after(response: &HttpResponse, now: u64) -> Option<Duration> {
+    let value = response.header("retry-after")?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => parse_date(value).map(|date| Duration::from_secs(date.saturating_sub(now))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn options() -> RequestOptions {
+        RequestOptions {
+            max_attempts: 3,
+            retry_backoff: 100,
+            ..RequestOptions::default()
+        }
+    }
+
+    fn response(status: u16, retry_after: Option<&str>) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: retry_after
+                .map(|value| ("Retry-After".to_string(), value.to_string()))
+                .into_iter()
+                .collect(),
+            ..HttpResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_delay_for_status() {
+        let options = options();
+        let now = 1445412480;
+        assert_eq!(delay(&options, 1, Ok(&response(200, None)), now), None);
+        assert_eq!(
+            delay(&options, 1, Ok(&response(503, Some("2"))), now),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            delay(
+                &options,
+                1,
+                Ok(&response(429, Some("Wed, 21 Oct 2015 07:29:00 GMT"))),
+                now
+            ),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(delay(&options, 3, Ok(&response(503, None)), now), None);
+    }
+
+    #[test]
+    fn test_delay_too_long() {
+        let options = options();
+        let now = 1445412480;
+        assert_eq!(
+            delay(&options, 1, Ok(&response(503, Some("86400"))), now),
+            None
+        );
+        assert_eq!(
+            delay(
+                &options,
+                1,
+                Ok(&response(429, Some("Wed, 21 Oct 2099 07:28:00 GMT"))),
+                now
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_delay_for_error() {
+        let options = options();
+        let refused = Report::new(io::Error::from(io::ErrorKind::ConnectionRefused));
+        let delay = |err: &Report| super::delay(&options, 2, Err(err), 0);
+        let waited = delay(&refused).unwrap();
+        assert!(
+            (Duration::from_millis(100)..=Duration::from_millis(200)).contains(&waited),
+            "{waited:?}"
+        );
+        assert_eq!(delay(&eyre!("Unsupported scheme: ftp")), None);
+        assert_eq!(
+            delay(&Report::new(io::Error::from(io::ErrorKind::InvalidData))),
+            None
+        );
+    }
+}
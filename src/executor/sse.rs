@@ -0,0 +1,336 @@
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Result;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::sleep;
+
+use super::{Client, HttpRequest, HttpResponse};
+
+/// Wait before reconnecting until the server asks for another with a `retry:` field.
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// How a connection to the stream ended, short of breaking.
+enum Outcome {
+    /// The server closed the stream, which is reopened like a broken one.
+    Ended,
+    /// The server answered with something other than a stream, so it isn't reopened.
+    Refused(String),
+}
+
+/// An event received from a server-sent event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The last `id:` the stream has seen, which this event carries even without its own.
+    pub id: Option<String>,
+    /// The `event:` type, `message` if none was given.
+    pub event: String,
+    pub data: String,
+    pub time: SystemTime,
+}
+
+/// What happened on a stream, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SseEvent {
+    /// The stream opened, with the head of the response that carries it.
+    Connected(Box<HttpResponse>),
+    Event(Event),
+    /// The stream ended or broke, and will be reopened after the delay.
+    Reconnecting(Duration, String),
+    /// The stream can't be reopened, e.g. because the server answered with an error.
+    Failed(String),
+}
+
+/// Splits a `text/event-stream` body into events as it arrives.
+#[derive(Debug, Default)]
+pub struct Parser {
+    buffer: Vec<u8>,
+    /// Whether the previous chunk ended in a `\r`, whose `\n` may start the next one.
+    after_cr: bool,
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    last_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+/// A server-sent event stream read by a background task, which ends when this is dropped.
+#[derive(Debug)]
+pub struct EventStream {
+    events: UnboundedReceiver<SseEvent>,
+}
+
+/// Whether a request asks for a server-sent event stream, as an `EventSource` does.
+pub fn is_event_stream(request: &HttpRequest) -> bool {
+    request.headers.iter().any(|(name, value)| {
+        name.eq_ignore_ascii_case("accept")
+            && value.to_ascii_lowercase().contains("text/event-stream")
+    })
+}
+
+impl Parser {
+    /// Parses the next piece of the stream, returning the events it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            let after_cr = std::mem::replace(&mut self.after_cr, byte == b'\r');
+            match byte {
+                b'\n' if after_cr => {}
+                b'\n' | b'\r' => {
+                    let line = std::mem::take(&mut self.buffer);
+                    events.extend(self.line(&line));
+                }
+                _ => self.buffer.push(byte),
+            }
+        }
+        events
+    }
+
+    /// The id to resume from with `Last-Event-ID`.
+    pub fn last_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// The reconnection delay the server asked for.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Forgets a partly received event, as when the connection drops.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.after_cr = false;
+        self.started = false;
+        self.event.clear();
+        self.data.clear();
+        self.has_data = false;
+    }
+
+    fn line(&mut self, line: &[u8]) -> Option<Event> {
+        let mut line = String::from_utf8_lossy(line).into_owned();
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string();
+            }
+        }
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok().map(Duration::from_millis);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = std::mem::take(&mut self.event);
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+        Some(Event {
+            id: self.last_id.clone(),
+            event: if event.is_empty() {
+                "message".into()
+            } else {
+                event
+            },
+            data: std::mem::take(&mut self.data),
+            time: SystemTime::now(),
+        })
+    }
+}
+
+impl EventStream {
+    /// Opens the stream in the background, reporting progress through [`EventStream::poll`].
+    pub fn open(client: Client, request: HttpRequest) -> Self {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = events_tx.closed() => {}
+                reason = run(&client, request, &events_tx) => {
+                    let _ = events_tx.send(SseEvent::Failed(reason));
+                }
+            }
+        });
+        Self { events: events_rx }
+    }
+
+    /// Takes the next event, if one has happened.
+    pub fn poll(&mut self) -> Option<SseEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+/// Reads the stream, reopening it whenever it ends until the server refuses it. Only returns
+/// with the reason it gave up.
+async fn run(
+    client: &Client,
+    mut request: HttpRequest,
+    events: &UnboundedSender<SseEvent>,
+) -> String {
+    // Events are read as they arrive, which a compressed stream would get in the way of.
+    if !request
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("accept-encoding"))
+    {
+        request
+            .headers
+            .push(("Accept-Encoding".into(), "identity".into()));
+    }
+    let mut parser = Parser::default();
+    loop {
+        let reason = match read(client, &request, &mut parser, events).await {
+            Ok(Outcome::Ended) => "The server ended the stream".to_string(),
+            Ok(Outcome::Refused(reason)) => return reason,
+            Err(err) => err.to_string(),
+        };
+        parser.reset();
+        let delay = parser.retry().unwrap_or(DEFAULT_RETRY);
+        let _ = events.send(SseEvent::Reconnecting(delay, reason));
+        sleep(delay).await;
+
+        if let Some(id) = parser.last_id() {
+            request
+                .headers
+                .retain(|(name, _)| !name.eq_ignore_ascii_case("last-event-id"));
+            request.headers.push(("Last-Event-ID".into(), id.into()));
+        }
+    }
+}
+
+async fn read(
+    client: &Client,
+    request: &HttpRequest,
+    parser: &mut Parser,
+    events: &UnboundedSender<SseEvent>,
+) -> Result<Outcome> {
+    let mut stream = client.start(request).await?;
+    // Events arrive as they are sent, however long the server stays quiet in between.
+    stream.read_timeout = 0;
+
+    let head = &stream.head;
+    if head.status != 200 {
+        return Ok(Outcome::Refused(format!(
+            "The server answered {} {}",
+            head.status, head.reason
+        )));
+    }
+    if head.media_type().as_deref() != Some("text/event-stream") {
+        let media_type = head
+            .media_type()
+            .unwrap_or_else(|| "no content type".into());
+        return Ok(Outcome::Refused(format!(
+            "Not an event stream: {media_type}"
+        )));
+    }
+    let _ = events.send(SseEvent::Connected(Box::new(stream.head.clone())));
+
+    while let Some(chunk) = stream.next_chunk().await? {
+        for event in parser.feed(&chunk) {
+            let _ = events.send(SseEvent::Event(event));
+        }
+    }
+    Ok(Outcome::Ended)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    fn fields(events: Vec<Event>) -> Vec<(Option<String>, String, String)> {
+        events
+            .into_iter()
+            .map(|event| (event.id, event.event, event.data))
+            .collect()
+    }
+
+    #[test]
+    fn test_parser() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            fields(parser.feed(b"\xef\xbb\xbfdata: one\r\n\r\n: comment\ndata:two\nda")),
+            [(None, "message".into(), "one".into())]
+        );
+        assert_eq!(fields(parser.feed(b"ta:  three\nevent: tick\nid: 7\r")), []);
+        assert_eq!(
+            fields(parser.feed(b"\n\nretry: 250\nevent: empty\n\ndata\n\n")),
+            [
+                (Some("7".into()), "tick".into(), "two\n three".into()),
+                (Some("7".into()), "message".into(), "".into()),
+            ]
+        );
+        assert_eq!(parser.retry(), Some(Duration::from_millis(250)));
+        assert_eq!(parser.last_id(), Some("7"));
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_with_last_event_id() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let (requests_tx, mut requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for body in ["retry: 10\nid: 1\ndata: first\n\n", "data: second\n\n"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests_tx
+                    .send(String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase())
+                    .unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: \
+                     {}\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let request = HttpRequest {
+            headers: vec![("Accept".into(), "text/event-stream".into())],
+            ..HttpRequest::get(&format!("http://{addr}/events"))
+        };
+        assert!(is_event_stream(&request));
+        let mut stream = EventStream::open(Client::default(), request);
+        let mut data = Vec::new();
+        while data.len() < 2 {
+            match stream.events.recv().await.unwrap() {
+                SseEvent::Event(event) => data.push(event.data),
+                SseEvent::Failed(err) => panic!("{err}"),
+                _ => {}
+            }
+        }
+        assert_eq!(data, ["first", "second"]);
+        assert!(!requests.recv().await.unwrap().contains("last-event-id"));
+        assert!(requests
+            .recv()
+            .await
+            .unwrap()
+            .contains("last-event-id: 1\r\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::time::Instant;
+
+use color_eyre::{eyre::eyre, Result};
+use url::Url;
+
+use super::{Io, Timing};
+
+/// Host the request names in its `Host` header when it goes over a Unix socket.
+const HOST: &str = "localhost";
+
+/// Whether a URL as typed in names a Unix socket, e.g.
+/// `unix:///var/run/docker.sock:/v1.43/containers/json`.
+pub fn is_unix(url: &str) -> bool {
+    url.trim().to_ascii_lowercase().starts_with("unix://")
+}
+
+/// Splits a `unix://` URL into the path of the socket, which ends at the first `:`, and the
+/// `http://localhost` URL of what is asked of the server behind it. Returns `None` for any other
+/// URL.
+pub fn split_url(raw: &str) -> Option<Result<(PathBuf, Url)>> {
+    if !is_unix(raw) {
+        return None;
+    }
+    let rest = &raw.trim()["unix://".len()..];
+    let (socket, target) = rest.split_once(':').unwrap_or((rest, "/"));
+    if socket.is_empty() {
+        return Some(Err(eyre!("URL has no socket path: {raw}")));
+    }
+    if !target.starts_with('/') {
+        return Some(Err(eyre!(
+            "The path after the socket must start with `/`: {raw}"
+        )));
+    }
+    let url = Url::parse(&format!("http://{HOST}{target}")).map_err(Into::into);
+    Some(url.map(|url| (PathBuf::from(socket), url)))
+}
+
+/// Puts a `unix://` URL back together from the socket and the `http://localhost` URL.
+pub fn join_url(socket: &Path, url: &Url) -> String {
+    let mut joined = format!("unix://{}:{}", socket.display(), url.path());
+    if let Some(query) = url.query() {
+        joined.push('?');
+        joined.push_str(query);
+    }
+    joined
+}
+
+/// Connects to the server listening on a Unix socket.
+#[cfg(unix)]
+pub(super) async fn connect(socket: &Path, timing: &mut Timing) -> Result<Box<dyn Io>> {
+    let started = Instant::now();
+    let stream = tokio::net::UnixStream::connect(socket)
+        .await
+        .map_err(|err| eyre!("Connecting to {}: {err}", socket.display()))?;
+    timing.connect = started.elapsed();
+    timing.socket = Some(socket.display().to_string());
+    Ok(Box::new(stream))
+}
+
+#[cfg(not(unix))]
+pub(super) async fn connect(socket: &Path, _timing: &mut Timing) -> Result<Box<dyn Io>> {
+    Err(eyre!(
+        "Unix sockets aren't supported on this platform: {}",
+        socket.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_split_and_join_url() {
+        let (socket, url) = split_url("unix:///var/run/docker.sock:/v1.43/containers/json?all=1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(socket, PathBuf::from("/var/run/docker.sock"));
+        assert_eq!(url.as_str(), "http://localhost/v1.43/containers/json?all=1");
+        assert_eq!(
+            join_url(&socket, &url),
+            "unix:///var/run/docker.sock:/v1.43/containers/json?all=1"
+        );
+
+        let (socket, url) = split_url("UNIX:///tmp/app.sock").unwrap().unwrap();
+        assert_eq!(socket, PathBuf::from("/tmp/app.sock"));
+        assert_eq!(url.as_str(), "http://localhost/");
+
+        assert!(split_url("unix://:/a").unwrap().is_err());
+        assert!(split_url("unix:///tmp/app.sock:a").unwrap().is_err());
+        assert!(split_url("http://localhost/").is_none());
+    }
+}
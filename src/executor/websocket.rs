@@ -0,0 +1,396 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::{eyre::eyre, Result};
+use futures::{SinkExt, StreamExt};
+use hyper::header::{self, HeaderName, HeaderValue};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::client_async;
+use tokio_tungstenite::tungstenite::{
+    client::IntoClientRequest,
+    protocol::{frame::coding::CloseCode, CloseFrame},
+    Message,
+};
+use url::Url;
+
+use super::tls::TlsSettings;
+use super::{connect, within, Client, HttpRequest, Timing};
+
+/// Which way a message went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// The content of a WebSocket message, as sent or received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// A close frame with its status code and reason, if it had one.
+    Close(Option<(u16, String)>),
+}
+
+/// A message in the log of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsMessage {
+    pub direction: Direction,
+    pub time: SystemTime,
+    pub payload: Payload,
+}
+
+/// The server's answer to the opening handshake.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Handshake {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The subprotocol the server picked from those offered in `Sec-WebSocket-Protocol`.
+    pub protocol: Option<String>,
+    pub elapsed: Duration,
+}
+
+/// What happened on a connection, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsEvent {
+    Connected(Handshake),
+    Message(WsMessage),
+    Closed,
+    Failed(String),
+}
+
+/// A WebSocket connection run by a background task, which ends when this is dropped.
+#[derive(Debug)]
+pub struct WebSocket {
+    outgoing: UnboundedSender<Payload>,
+    events: UnboundedReceiver<WsEvent>,
+}
+
+/// Whether a URL as typed in asks for a WebSocket rather than an HTTP request.
+pub fn is_websocket(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+impl Payload {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Text(text) => text.len(),
+            Self::Binary(data) | Self::Ping(data) | Self::Pong(data) => data.len(),
+            Self::Close(frame) => frame.as_ref().map_or(0, |(_, reason)| 2 + reason.len()),
+        }
+    }
+
+    fn into_message(self) -> Message {
+        match self {
+            Self::Text(text) => Message::text(text),
+            Self::Binary(data) => Message::binary(data),
+            Self::Ping(data) => Message::Ping(data.into()),
+            Self::Pong(data) => Message::Pong(data.into()),
+            Self::Close(frame) => Message::Close(frame.map(|(code, reason)| CloseFrame {
+                code: CloseCode::from(code),
+                reason: reason.into(),
+            })),
+        }
+    }
+
+    fn from_message(message: Message) -> Option<Self> {
+        Some(match message {
+            Message::Text(text) => Self::Text(text.to_string()),
+            Message::Binary(data) => Self::Binary(data.to_vec()),
+            Message::Ping(data) => Self::Ping(data.to_vec()),
+            Message::Pong(data) => Self::Pong(data.to_vec()),
+            Message::Close(frame) => {
+                Self::Close(frame.map(|frame| (frame.code.into(), frame.reason.to_string())))
+            }
+            Message::Frame(_) => return None,
+        })
+    }
+}
+
+impl WebSocket {
+    /// Connects in the background, reporting progress through [`WebSocket::poll`].
+    pub fn open(client: Client, request: HttpRequest) -> Self {
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let event = match run(&client, &request, outgoing_rx, &events_tx).await {
+                Ok(()) => WsEvent::Closed,
+                Err(err) => WsEvent::Failed(err.to_string()),
+            };
+            let _ = events_tx.send(event);
+        });
+        Self {
+            outgoing: outgoing_tx,
+            events: events_rx,
+        }
+    }
+
+    pub fn send(&self, payload: Payload) {
+        let _ = self.outgoing.send(payload);
+    }
+
+    /// Takes the next event, if one has happened.
+    pub fn poll(&mut self) -> Option<WsEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+async fn run(
+    client: &Client,
+    request: &HttpRequest,
+    mut outgoing: UnboundedReceiver<Payload>,
+    events: &UnboundedSender<WsEvent>,
+) -> Result<()> {
+    let url = Url::parse(request.url.trim())?;
+    if !matches!(url.scheme(), "ws" | "wss") {
+        return Err(eyre!("Not a WebSocket URL: {url}"));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| eyre!("URL has no host: {url}"))?
+        .to_string();
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| eyre!("URL has no port: {url}"))?;
+
+    let options = &request.options;
+    let settings = TlsSettings::for_host(&client.tls, &host)
+        .cloned()
+        .unwrap_or_default();
+    let proxy = client.proxy.proxy_for(&url)?;
+    let mut timing = Timing::default();
+    let stream = within(
+        options.connect_timeout,
+        "Connecting",
+        connect(
+            &url,
+            &host,
+            port,
+            &settings,
+            &client.dns,
+            proxy.as_ref(),
+            vec![b"http/1.1".to_vec()],
+            &mut timing,
+        ),
+    )
+    .await?;
+
+    let mut handshake = url.as_str().into_client_request()?;
+    let headers = handshake.headers_mut();
+    headers.insert(
+        header::USER_AGENT,
+        HeaderValue::from_static(concat!("papirus/", env!("CARGO_PKG_VERSION"))),
+    );
+    // Cookies are matched as if the connection were the HTTP request it starts out as.
+    let mut http_url = url.clone();
+    let _ = http_url.set_scheme(if url.scheme() == "wss" {
+        "https"
+    } else {
+        "http"
+    });
+    let mut cookie = options
+        .cookies
+        .then(|| client.cookies.lock().unwrap().header(&http_url))
+        .flatten();
+    for (name, value) in &request.headers {
+        // RFC 6265 allows a single Cookie header, so cookies typed in join the jar's.
+        if name.eq_ignore_ascii_case("cookie") {
+            cookie = Some(match cookie {
+                Some(jar) => format!("{value}; {jar}"),
+                None => value.clone(),
+            });
+            continue;
+        }
+        headers.append(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    if let Some(cookie) = cookie {
+        headers.insert(header::COOKIE, HeaderValue::from_str(&cookie)?);
+    }
+
+    let started = Instant::now();
+    let (mut socket, response) = within(options.read_timeout, "The handshake", async {
+        Ok(client_async(handshake, stream).await?)
+    })
+    .await?;
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    if options.cookies {
+        client.store_cookies(&headers, &http_url);
+    }
+    let protocol = response
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let _ = events.send(WsEvent::Connected(Handshake {
+        status: response.status().as_u16(),
+        headers,
+        protocol,
+        elapsed: started.elapsed(),
+    }));
+
+    let log = |direction, payload| {
+        let _ = events.send(WsEvent::Message(WsMessage {
+            direction,
+            time: SystemTime::now(),
+            payload,
+        }));
+    };
+    loop {
+        tokio::select! {
+            payload = outgoing.recv() => match payload {
+                Some(payload) => {
+                    socket.send(payload.clone().into_message()).await?;
+                    log(Direction::Sent, payload);
+                }
+                // Dropped without closing first, e.g. with its tab.
+                None => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+            },
+            message = socket.next() => match message {
+                Some(message) => {
+                    if let Some(payload) = Payload::from_message(message?) {
+                        log(Direction::Received, payload);
+                    }
+                }
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::{io::AsyncReadExt, net::TcpListener, sync::oneshot};
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+
+    /// Waits for the next event, skipping none.
+    async fn next(socket: &mut WebSocket) -> WsEvent {
+        socket.events.recv().await.unwrap()
+    }
+
+    fn payload(event: WsEvent) -> (Direction, Payload) {
+        match event {
+            WsEvent::Message(message) => (message.direction, message.payload),
+            event => panic!("Expected a message, got {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_echo() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = accept_async(stream).await.unwrap();
+            while let Some(Ok(message)) = socket.next().await {
+                if message.is_text() || message.is_binary() {
+                    socket.send(message).await.unwrap();
+                }
+            }
+        });
+
+        let mut socket = WebSocket::open(
+            Client::default(),
+            HttpRequest::get(&format!("ws://{addr}/echo")),
+        );
+        let WsEvent::Connected(handshake) = next(&mut socket).await else {
+            panic!("Expected the handshake");
+        };
+        assert_eq!(handshake.status, 101);
+
+        socket.send(Payload::Text("hello".into()));
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (Direction::Sent, Payload::Text("hello".into()))
+        );
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (Direction::Received, Payload::Text("hello".into()))
+        );
+
+        socket.send(Payload::Ping(b"hi".to_vec()));
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (Direction::Sent, Payload::Ping(b"hi".to_vec()))
+        );
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (Direction::Received, Payload::Pong(b"hi".to_vec()))
+        );
+
+        socket.send(Payload::Close(Some((1000, "bye".into()))));
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (Direction::Sent, Payload::Close(Some((1000, "bye".into()))))
+        );
+        assert_eq!(
+            payload(next(&mut socket).await),
+            (
+                Direction::Received,
+                Payload::Close(Some((1000, "bye".into())))
+            )
+        );
+        assert_eq!(next(&mut socket).await, WsEvent::Closed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_joins_cookies() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let (cookies_tx, cookies_rx) = oneshot::channel();
+        // Only the handshake request matters, so it is read as it arrived and not answered.
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(stream.read_u8().await.unwrap());
+            }
+            let cookies: Vec<String> = String::from_utf8(head)
+                .unwrap()
+                .lines()
+                .filter_map(|line| line.strip_prefix("cookie: "))
+                .map(str::to_string)
+                .collect();
+            let _ = cookies_tx.send(cookies);
+        });
+
+        let client = Client::default();
+        let http_url = Url::parse(&format!("http://{addr}/"))?;
+        client
+            .cookies
+            .lock()
+            .unwrap()
+            .store(["sid=abc; Path=/"].into_iter(), &http_url);
+        let mut request = HttpRequest::get(&format!("ws://{addr}/"));
+        request.headers.push(("Cookie".into(), "typed=1".into()));
+        let _socket = WebSocket::open(client, request);
+        assert_eq!(cookies_rx.await?, ["typed=1; sid=abc"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_websocket() {
+        assert!(is_websocket(" WSS://example.com/socket"));
+        assert!(!is_websocket("https://example.com/"));
+    }
+}
@@ -0,0 +1,476 @@
+use std::collections::HashSet;
+
+use graphql_parser::query::{
+    parse_query,
+    Definition,
+    Document,
+    OperationDefinition,
+    Selection,
+    SelectionSet,
+    TypeCondition,
+};
+use graphql_parser::Pos;
+use serde_json::{json, Map, Value};
+
+use self::schema::{InputValue, Schema, TypeDef};
+use crate::components::request::completion::Completion;
+
+pub mod schema;
+
+/// Keywords that can start a definition in a document.
+const DEFINITION_KEYWORDS: [&str; 4] = ["query", "mutation", "subscription", "fragment"];
+
+/// An operation defined in a document, `None` being an anonymous one.
+pub type Operation = Option<String>;
+
+/// Builds the JSON body of a GraphQL request over HTTP.
+pub fn request_body(
+    query: &str,
+    variables: &str,
+    operation: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let mut body = Map::new();
+    body.insert("query".into(), json!(query));
+    if let Some(operation) = operation {
+        body.insert("operationName".into(), json!(operation));
+    }
+    if !variables.trim().is_empty() {
+        let variables: Value = serde_json::from_str(variables)
+            .map_err(|err| format!("Variables aren't valid JSON: {err}"))?;
+        if !variables.is_object() {
+            return Err("Variables must be a JSON object".into());
+        }
+        body.insert("variables".into(), variables);
+    }
+    Ok(serde_json::to_vec(&Value::Object(body)).unwrap_or_default())
+}
+
+/// Splits a GraphQL request body back into its query, variables and operation name.
+pub fn parse_request_body(body: &[u8]) -> Option<(String, String, Operation)> {
+    let body: Value = serde_json::from_slice(body).ok()?;
+    let query = body.get("query")?.as_str()?.to_string();
+    let variables = body
+        .get("variables")
+        .filter(|variables| !variables.is_null())
+        .and_then(|variables| serde_json::to_string_pretty(variables).ok())
+        .unwrap_or_default();
+    let operation = body
+        .get("operationName")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Some((query, variables, operation))
+}
+
+/// The operations defined in a document, in order, or none if it doesn't parse.
+pub fn operations(document: &str) -> Vec<Operation> {
+    let Ok(document) = parse_query::<String>(document) else {
+        return vec![];
+    };
+    document
+        .definitions
+        .into_iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(OperationDefinition::SelectionSet(_)) => Some(None),
+            Definition::Operation(OperationDefinition::Query(query)) => Some(query.name),
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => Some(mutation.name),
+            Definition::Operation(OperationDefinition::Subscription(subscription)) => {
+                Some(subscription.name)
+            }
+            Definition::Fragment(_) => None,
+        })
+        .collect()
+}
+
+/// Checks that a document parses and, given a schema, that it only asks for what the schema
+/// has. Returns the first problem found.
+pub fn validate(document: &str, schema: Option<&Schema>) -> Result<(), String> {
+    let parsed = parse_query::<String>(document).map_err(|err| {
+        let err = err.to_string();
+        let err = err.lines().next().unwrap_or_default();
+        format!(
+            "Syntax error: {}",
+            err.trim_start_matches("query parse error: ")
+        )
+    })?;
+    let Some(schema) = schema else {
+        return Ok(());
+    };
+    let validator = Validator::new(&parsed, schema);
+    for definition in &parsed.definitions {
+        let (kind, selection_set) = match definition {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => ("query", set),
+            Definition::Operation(OperationDefinition::Query(query)) => {
+                ("query", &query.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+                ("mutation", &mutation.selection_set)
+            }
+            Definition::Operation(OperationDefinition::Subscription(subscription)) => {
+                ("subscription", &subscription.selection_set)
+            }
+            Definition::Fragment(fragment) => {
+                let TypeCondition::On(on) = &fragment.type_condition;
+                let ty = validator.named(on, fragment.position)?;
+                validator.selection_set(ty, &fragment.selection_set)?;
+                continue;
+            }
+        };
+        let root = schema
+            .root(kind)
+            .ok_or_else(|| format!("The schema has no {kind} type"))?;
+        validator.selection_set(root, selection_set)?;
+    }
+    Ok(())
+}
+
+/// Walks selections, checking each against the type it selects from.
+struct Validator<'a> {
+    schema: &'a Schema,
+    /// Names of the fragments the document defines.
+    fragments: HashSet<&'a str>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(document: &'a Document<'a, String>, schema: &'a Schema) -> Self {
+        let fragments = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Fragment(fragment) => Some(fragment.name.as_str()),
+                Definition::Operation(_) => None,
+            })
+            .collect();
+        Self { schema, fragments }
+    }
+
+    fn named(&self, name: &str, position: Pos) -> Result<&'a TypeDef, String> {
+        self.schema
+            .get(name)
+            .ok_or_else(|| format!("Line {}: unknown type `{name}`", position.line))
+    }
+
+    fn selection_set(&self, ty: &TypeDef, set: &SelectionSet<'a, String>) -> Result<(), String> {
+        for selection in &set.items {
+            match selection {
+                Selection::Field(field) => {
+                    let line = field.position.line;
+                    if field.name == "__typename" {
+                        continue;
+                    }
+                    let definition = ty.field(&field.name).ok_or_else(|| {
+                        format!("Line {line}: `{}` has no field `{}`", ty.name, field.name)
+                    })?;
+                    for (name, _) in &field.arguments {
+                        if !definition.args.iter().any(|arg| &arg.name == name) {
+                            return Err(format!(
+                                "Line {line}: `{}` has no argument `{name}`",
+                                field.name
+                            ));
+                        }
+                    }
+                    let field_type = self.named(definition.ty.named(), field.position)?;
+                    match (
+                        field_type.is_composite(),
+                        field.selection_set.items.is_empty(),
+                    ) {
+                        (true, true) => {
+                            return Err(format!(
+                                "Line {line}: `{}` of type `{}` needs a selection of fields",
+                                field.name, definition.ty
+                            ))
+                        }
+                        (false, false) => {
+                            return Err(format!(
+                                "Line {line}: `{}` of type `{}` has no fields to select",
+                                field.name, definition.ty
+                            ))
+                        }
+                        (true, false) => self.selection_set(field_type, &field.selection_set)?,
+                        (false, true) => {}
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let ty = match &fragment.type_condition {
+                        Some(TypeCondition::On(on)) => self.named(on, fragment.position)?,
+                        None => ty,
+                    };
+                    self.selection_set(ty, &fragment.selection_set)?;
+                }
+                Selection::FragmentSpread(spread) => {
+                    if !self.fragments.contains(spread.fragment_name.as_str()) {
+                        return Err(format!(
+                            "Line {}: unknown fragment `{}`",
+                            spread.position.line, spread.fragment_name
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Name(&'a str),
+    Punct(char),
+}
+
+/// What an open bracket in the document encloses.
+#[derive(Debug, Clone)]
+enum Scope<'a> {
+    /// A selection of fields from a type, unknown if the schema doesn't have it.
+    Selection(Option<&'a TypeDef>),
+    /// The arguments of a field.
+    Arguments(&'a [InputValue]),
+    /// Anything else, e.g. a list value or variable definitions.
+    Other,
+}
+
+/// Splits the text before the cursor into the tokens that matter for completion, skipping
+/// strings, comments and numbers.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '"' => {
+                let block = text[start..].starts_with("\"\"\"");
+                if block {
+                    chars.nth(1);
+                    while chars.next().is_some() {
+                        if text[..chars.peek().map_or(text.len(), |&(i, _)| i)].ends_with("\"\"\"")
+                        {
+                            break;
+                        }
+                    }
+                } else {
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' | '\n' => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some((i, _)) =
+                    chars.next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    end = i + 1;
+                }
+                tokens.push(Token::Name(&text[start..end]));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                while chars
+                    .next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '.')
+                    .is_some()
+                {}
+            }
+            c if c.is_whitespace() || c == ',' => {}
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+    tokens
+}
+
+/// What could be typed at the end of `before_cursor`, given the word being typed there.
+pub fn completions(before_cursor: &str, schema: &Schema) -> Vec<Completion> {
+    let prefix_len = before_cursor
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .count();
+    let (text, prefix) = before_cursor.split_at(before_cursor.len() - prefix_len);
+    let tokens = tokenize(text);
+
+    let mut scopes: Vec<Scope> = Vec::new();
+    // The type the next selection opened selects from.
+    let mut pending: Option<&TypeDef> = None;
+    // The field last named in the innermost selection, whose arguments may follow.
+    let mut field: Option<&schema::Field> = None;
+    let mut previous: Option<&Token> = None;
+    for token in &tokens {
+        match (token, scopes.last()) {
+            // `fragment F on Type {` and `... on Type {` select from the type they name.
+            (Token::Name(name), None | Some(Scope::Selection(_)))
+                if previous == Some(&Token::Name("on")) =>
+            {
+                pending = schema.get(name);
+                field = None;
+            }
+            (Token::Name(name @ ("query" | "mutation" | "subscription")), None) => {
+                pending = schema.root(name);
+            }
+            (Token::Name(name), Some(Scope::Selection(ty))) => {
+                field = ty.and_then(|ty| ty.field(name));
+                pending = field.and_then(|field| schema.get(field.ty.named()));
+            }
+            (Token::Punct('{'), None) => {
+                scopes.push(Scope::Selection(pending.or_else(|| schema.root("query"))));
+                pending = None;
+            }
+            (Token::Punct('{'), Some(Scope::Selection(_))) => {
+                scopes.push(Scope::Selection(pending.take()));
+                field = None;
+            }
+            (Token::Punct('('), Some(Scope::Selection(_))) => match field {
+                Some(field) => scopes.push(Scope::Arguments(&field.args)),
+                None => scopes.push(Scope::Other),
+            },
+            (Token::Punct('{' | '(' | '['), _) => scopes.push(Scope::Other),
+            // Arguments and variables come before the selection they belong to.
+            (Token::Punct(')' | ']'), _) => {
+                scopes.pop();
+            }
+            (Token::Punct('}'), _) => {
+                scopes.pop();
+                pending = None;
+                field = None;
+            }
+            _ => {}
+        }
+        previous = Some(token);
+    }
+
+    let candidates: Vec<Completion> = match (scopes.last(), previous) {
+        (_, Some(Token::Name("on"))) => schema
+            .types
+            .iter()
+            .filter(|ty| ty.is_composite() && !ty.name.starts_with("__"))
+            .map(|ty| Completion {
+                label: ty.name.clone(),
+                detail: ty.kind.to_lowercase(),
+            })
+            .collect(),
+        (None, _) => DEFINITION_KEYWORDS
+            .iter()
+            .map(|keyword| Completion {
+                label: keyword.to_string(),
+                detail: "keyword".into(),
+            })
+            .collect(),
+        (Some(Scope::Selection(Some(ty))), _) => ty
+            .fields
+            .iter()
+            .map(|field| {
+                let args = if field.args.is_empty() {
+                    String::new()
+                } else {
+                    let args: Vec<_> = field
+                        .args
+                        .iter()
+                        .map(|arg| format!("{}: {}", arg.name, arg.ty))
+                        .collect();
+                    format!("({})", args.join(", "))
+                };
+                Completion {
+                    label: field.name.clone(),
+                    detail: format!("{args}: {}", field.ty),
+                }
+            })
+            .chain(std::iter::once(Completion {
+                label: "__typename".into(),
+                detail: ": String!".into(),
+            }))
+            .collect(),
+        // Argument names, but not where a value is expected.
+        (Some(Scope::Arguments(args)), previous) if previous != Some(&Token::Punct(':')) => args
+            .iter()
+            .map(|arg| Completion {
+                label: arg.name.clone(),
+                detail: format!(": {}", arg.ty),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.label.starts_with(prefix) && candidate.label != prefix)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::schema::tests::schema;
+    use super::*;
+
+    fn labels(before_cursor: &str) -> Vec<String> {
+        completions(before_cursor, &schema())
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_completions() {
+        assert_eq!(labels("que"), ["query"]);
+        assert_eq!(labels("{ us"), ["user", "users"]);
+        assert_eq!(
+            labels("query Q { user(id: \"1\") { "),
+            ["id", "name", "posts", "__typename"]
+        );
+        assert_eq!(labels("{ user(i"), ["id"]);
+        assert_eq!(labels("{ user(id: "), Vec::<String>::new());
+        assert_eq!(labels("{ users { posts(first: 2) { t"), ["title"]);
+        assert_eq!(labels("{ users { id } us"), ["user", "users"]);
+        assert_eq!(labels("fragment F on U"), ["User"]);
+        assert_eq!(labels("fragment F on User { na"), ["name"]);
+        assert_eq!(labels("{ user(id: 1) { ... on User { p"), ["posts"]);
+        assert_eq!(labels("# { us\n{ users { \"}\" n"), ["name"]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = schema();
+        let validate = |document| validate(document, Some(&schema));
+        assert_eq!(validate("{ users { id posts { title } } }"), Ok(()));
+        assert_eq!(
+            validate("{ users { id email } }"),
+            Err("Line 1: `User` has no field `email`".into())
+        );
+        assert_eq!(
+            validate("query {\n  user(name: \"a\") { id }\n}"),
+            Err("Line 2: `user` has no argument `name`".into())
+        );
+        assert_eq!(
+            validate("{ users }"),
+            Err("Line 1: `users` of type `[User!]!` needs a selection of fields".into())
+        );
+        assert_eq!(
+            validate("{ users { id { x } } }"),
+            Err("Line 1: `id` of type `ID!` has no fields to select".into())
+        );
+        assert_eq!(
+            validate("{ users { ...F } } fragment F on User { name }"),
+            Ok(())
+        );
+        assert!(validate("{ users {")
+            .unwrap_err()
+            .starts_with("Syntax error"));
+    }
+
+    #[test]
+    fn test_operations_and_body() {
+        assert_eq!(
+            operations("query A { a } mutation { b } fragment F on T { c }"),
+            [Some("A".to_string()), None]
+        );
+        let body = request_body("{ a }", "{\"id\": 1}", Some("A")).unwrap();
+        assert_eq!(
+            parse_request_body(&body),
+            Some(("{ a }".into(), "{\n  \"id\": 1\n}".into(), Some("A".into())))
+        );
+        assert_eq!(
+            request_body("{ a }", "[]", None),
+            Err("Variables must be a JSON object".into())
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Directory in the data dir where fetched schemas are kept, one file per endpoint.
+const SCHEMAS_DIR: &str = "graphql";
+
+/// Asks a server to describe its schema, following `ofType` deep enough for any type in practice.
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      kind
+      name
+      description
+      fields(includeDeprecated: true) {
+        name
+        description
+        args { ...InputValue }
+        type { ...TypeRef }
+      }
+      inputFields { ...InputValue }
+      enumValues(includeDeprecated: true) { name }
+      possibleTypes { name }
+    }
+  }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+}"#;
+
+/// The types a GraphQL endpoint serves, as told by introspection.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+    pub query_type: Option<String>,
+    pub mutation_type: Option<String>,
+    pub subscription_type: Option<String>,
+    pub types: Vec<TypeDef>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeDef {
+    /// `OBJECT`, `INTERFACE`, `UNION`, `SCALAR`, `ENUM` or `INPUT_OBJECT`.
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: Vec<Field>,
+    pub input_fields: Vec<InputValue>,
+    pub enum_values: Vec<String>,
+    /// The object types an interface or union may turn out to be.
+    pub possible_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    pub name: String,
+    pub description: Option<String>,
+    pub args: Vec<InputValue>,
+    pub ty: TypeRef,
+}
+
+/// An argument or a field of an input object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputValue {
+    pub name: String,
+    pub description: Option<String>,
+    pub ty: TypeRef,
+    pub default_value: Option<String>,
+}
+
+/// A reference to a type, wrapped in lists and non-null markers, e.g. `[User!]!`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeRef {
+    Named(String),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>),
+}
+
+/// Schemas fetched from each endpoint, kept on disk so they outlive the session.
+#[derive(Debug, Default)]
+pub struct SchemaCache {
+    dir: Option<PathBuf>,
+}
+
+impl Schema {
+    /// Reads the answer to [`INTROSPECTION_QUERY`], with or without its `data` envelope.
+    pub fn from_introspection(response: &Value) -> Result<Self> {
+        if let Some(message) = response
+            .pointer("/errors/0/message")
+            .and_then(Value::as_str)
+        {
+            return Err(eyre!("Introspection failed: {message}"));
+        }
+        let schema = response
+            .pointer("/data/__schema")
+            .or_else(|| response.get("__schema"))
+            .ok_or_else(|| eyre!("The response has no __schema"))?;
+        let root = |name: &str| {
+            schema
+                .pointer(&format!("/{name}/name"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let types = schema
+            .get("types")
+            .and_then(Value::as_array)
+            .ok_or_else(|| eyre!("The schema has no types"))?
+            .iter()
+            .map(TypeDef::from_introspection)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            query_type: root("queryType"),
+            mutation_type: root("mutationType"),
+            subscription_type: root("subscriptionType"),
+            types,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|ty| ty.name == name)
+    }
+
+    /// The type an operation starts from, e.g. `Query` for `query`.
+    pub fn root(&self, operation: &str) -> Option<&TypeDef> {
+        let name = match operation {
+            "mutation" => self.mutation_type.as_deref(),
+            "subscription" => self.subscription_type.as_deref(),
+            _ => self.query_type.as_deref(),
+        };
+        self.get(name?)
+    }
+}
+
+impl TypeDef {
+    fn from_introspection(value: &Value) -> Result<Self> {
+        let list = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let names = |key: &str| {
+            list(key)
+                .iter()
+                .filter_map(|v| v.get("name").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        };
+        Ok(Self {
+            kind: string(value, "kind").unwrap_or_default(),
+            name: string(value, "name").ok_or_else(|| eyre!("A type has no name"))?,
+            description: string(value, "description"),
+            fields: list("fields")
+                .iter()
+                .map(|field| {
+                    Ok(Field {
+                        name: string(field, "name").unwrap_or_default(),
+                        description: string(field, "description"),
+                        args: list_of(field, "args")?,
+                        ty: TypeRef::from_introspection(&field["type"])?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            input_fields: list_of(value, "inputFields")?,
+            enum_values: names("enumValues"),
+            possible_types: names("possibleTypes"),
+        })
+    }
+
+    /// Whether a selection of fields can, or must, follow a field of this type.
+    pub fn is_composite(&self) -> bool {
+        matches!(self.kind.as_str(), "OBJECT" | "INTERFACE" | "UNION")
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+impl TypeRef {
+    fn from_introspection(value: &Value) -> Result<Self> {
+        let of_type = || Self::from_introspection(&value["ofType"]).map(Box::new);
+        match value.get("kind").and_then(Value::as_str) {
+            Some("NON_NULL") => Ok(Self::NonNull(of_type()?)),
+            Some("LIST") => Ok(Self::List(of_type()?)),
+            Some(_) => Ok(Self::Named(
+                string(value, "name").ok_or_else(|| eyre!("A type reference has no name"))?,
+            )),
+            None => Err(eyre!("A type reference has no kind")),
+        }
+    }
+
+    /// The type inside any list and non-null wrappers.
+    pub fn named(&self) -> &str {
+        match self {
+            Self::Named(name) => name,
+            Self::List(inner) | Self::NonNull(inner) => inner.named(),
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, "{name}"),
+            Self::List(inner) => write!(f, "[{inner}]"),
+            Self::NonNull(inner) => write!(f, "{inner}!"),
+        }
+    }
+}
+
+impl SchemaCache {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            dir: Some(data_dir.join(SCHEMAS_DIR)),
+        }
+    }
+
+    pub fn load(&self, endpoint: &str) -> Option<Schema> {
+        let contents = fs::read_to_string(self.file(endpoint)?).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save(&self, endpoint: &str, schema: &Schema) -> Result<()> {
+        let (Some(dir), Some(file)) = (&self.dir, self.file(endpoint)) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+        fs::write(file, serde_json::to_string(schema)?)?;
+        Ok(())
+    }
+
+    /// A file named after the endpoint, e.g. `api.example.com_graphql.json`.
+    fn file(&self, endpoint: &str) -> Option<PathBuf> {
+        let endpoint = endpoint.trim();
+        let endpoint = endpoint
+            .split_once("://")
+            .map_or(endpoint, |(_, rest)| rest);
+        let name: String = endpoint
+            .trim_end_matches('/')
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Some(self.dir.as_ref()?.join(format!("{name}.json")))
+    }
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn list_of(value: &Value, key: &str) -> Result<Vec<InputValue>> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|input| {
+            Ok(InputValue {
+                name: string(input, "name").unwrap_or_default(),
+                description: string(input, "description"),
+                ty: TypeRef::from_introspection(&input["type"])?,
+                default_value: string(input, "defaultValue"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({"kind": kind, "name": name})
+    }
+
+    fn list(inner: Value) -> Value {
+        json!({"kind": "LIST", "name": null, "ofType": inner})
+    }
+
+    fn non_null(inner: Value) -> Value {
+        json!({"kind": "NON_NULL", "name": null, "ofType": inner})
+    }
+
+    /// A small schema with users and their posts, shared with the document tests.
+    pub fn schema() -> Schema {
+        let response = json!({"data": {"__schema": {
+            "queryType": {"name": "Query"},
+            "mutationType": null,
+            "subscriptionType": null,
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "fields": [
+                    {"name": "user", "args": [
+                        {"name": "id", "type": non_null(named("SCALAR", "ID"))}
+                    ], "type": named("OBJECT", "User")},
+                    {"name": "users", "args": [], "type": non_null(list(non_null(named("OBJECT", "User"))))},
+                ]},
+                {"kind": "OBJECT", "name": "User", "description": "Someone with an account", "fields": [
+                    {"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))},
+                    {"name": "name", "args": [], "type": named("SCALAR", "String")},
+                    {"name": "posts", "args": [
+                        {"name": "first", "type": named("SCALAR", "Int"), "defaultValue": "10"}
+                    ], "type": list(named("OBJECT", "Post"))},
+                ]},
+                {"kind": "OBJECT", "name": "Post", "fields": [
+                    {"name": "title", "args": [], "type": named("SCALAR", "String")},
+                ]},
+                {"kind": "SCALAR", "name": "ID"},
+                {"kind": "SCALAR", "name": "String"},
+                {"kind": "SCALAR", "name": "Int"},
+            ],
+        }}});
+        Schema::from_introspection(&response).unwrap()
+    }
+
+    #[test]
+    fn test_from_introspection() {
+        let schema = schema();
+        let users = schema.root("query").unwrap().field("users").unwrap();
+        assert_eq!(users.ty.to_string(), "[User!]!");
+        assert_eq!(users.ty.named(), "User");
+        let posts = schema.get("User").unwrap().field("posts").unwrap();
+        assert_eq!(posts.args[0].default_value.as_deref(), Some("10"));
+        assert!(schema.get("Post").unwrap().is_composite());
+        assert!(!schema.get("ID").unwrap().is_composite());
+
+        let error = json!({"errors": [{"message": "Introspection is disabled"}]});
+        assert_eq!(
+            Schema::from_introspection(&error).unwrap_err().to_string(),
+            "Introspection failed: Introspection is disabled"
+        );
+    }
+
+    #[test]
+    fn test_cache_file() {
+        let cache = SchemaCache::new(PathBuf::from("/data"));
+        assert_eq!(
+            cache.file("https://api.example.com/graphql/"),
+            Some(PathBuf::from("/data/graphql/api.example.com_graphql.json"))
+        );
+    }
+}
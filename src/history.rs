@@ -0,0 +1,148 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::executor::{HttpResponse, Timing};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Number of most recent entries kept in memory; older ones only live on disk.
+const MAX_ENTRIES: usize = 1000;
+
+/// A request that was sent, with enough of its response to compare runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub size: usize,
+    pub elapsed: Duration,
+    pub timing: Timing,
+}
+
+/// Sent requests, appended to a JSON Lines file in the data directory.
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    file: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+}
+
+impl HistoryEntry {
+    pub fn new(response: &HttpResponse) -> Self {
+        Self {
+            timestamp: now(),
+            method: response.method.clone(),
+            url: response.url.clone(),
+            status: response.status,
+            size: response.size() as usize,
+            elapsed: response.elapsed,
+            timing: response.timing.clone(),
+        }
+    }
+
+    /// Seconds since the request was sent.
+    pub fn age(&self) -> u64 {
+        now().saturating_sub(self.timestamp)
+    }
+}
+
+impl History {
+    pub fn load(data_dir: PathBuf) -> Self {
+        let file = data_dir.join(HISTORY_FILE);
+        let mut entries: Vec<HistoryEntry> = fs::read_to_string(&file)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        entries.drain(..entries.len().saturating_sub(MAX_ENTRIES));
+        Self {
+            file: Some(file),
+            entries,
+        }
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<()> {
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(file)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Runs of the same request, newest first.
+    pub fn runs<'a>(
+        &'a self,
+        method: &'a str,
+        url: &'a str,
+    ) -> impl Iterator<Item = &'a HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |entry| entry.method == method && entry.url == url)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn response(url: &str, status: u16) -> HttpResponse {
+        HttpResponse {
+            method: "GET".into(),
+            url: url.into(),
+            status,
+            ..HttpResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_runs_newest_first() -> Result<()> {
+        let mut history = History::default();
+        history.record(HistoryEntry::new(&response("http://a/", 200)))?;
+        history.record(HistoryEntry::new(&response("http://b/", 200)))?;
+        history.record(HistoryEntry::new(&response("http://a/", 500)))?;
+        let statuses: Vec<u16> = history
+            .runs("GET", "http://a/")
+            .map(|entry| entry.status)
+            .collect();
+        assert_eq!(statuses, vec![500, 200]);
+        assert_eq!(history.runs("POST", "http://a/").count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-history-{}", std::process::id()));
+        let mut history = History::load(dir.clone());
+        history.record(HistoryEntry::new(&response("http://a/", 204)))?;
+
+        let loaded = History::load(dir.clone());
+        assert_eq!(loaded.entries, history.entries);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
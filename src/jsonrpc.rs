@@ -0,0 +1,266 @@
+use serde_json::{json, Map, Value};
+
+/// A call to make in a JSON-RPC 2.0 request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub method: String,
+    /// The params as typed, a JSON array or object, or blank to leave them out.
+    pub params: String,
+    /// `None` for a notification, which the server doesn't answer.
+    pub id: Option<Value>,
+}
+
+/// What became of a call, or of a response that answers none of the calls.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Result(Value),
+    Error {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    /// The server didn't answer the call.
+    Missing,
+    /// A notification, which gets no answer.
+    Notified,
+}
+
+/// A call paired with the response to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    /// `None` for responses whose id matches no call, e.g. to a request that didn't parse.
+    pub method: Option<String>,
+    pub id: Option<Value>,
+    pub outcome: Outcome,
+}
+
+/// Parses the params of a call, which may be left out.
+pub fn parse_params(params: &str) -> Result<Option<Value>, String> {
+    if params.trim().is_empty() {
+        return Ok(None);
+    }
+    let params: Value =
+        serde_json::from_str(params).map_err(|err| format!("Params aren't valid JSON: {err}"))?;
+    if !params.is_array() && !params.is_object() {
+        return Err("Params must be a JSON array or object".into());
+    }
+    Ok(Some(params))
+}
+
+fn call_object(call: &Call) -> Result<Value, String> {
+    if call.method.trim().is_empty() {
+        return Err("Every call needs a method".into());
+    }
+    let mut object = Map::new();
+    object.insert("jsonrpc".into(), json!("2.0"));
+    object.insert("method".into(), json!(call.method.trim()));
+    if let Some(params) = parse_params(&call.params)? {
+        object.insert("params".into(), params);
+    }
+    if let Some(id) = &call.id {
+        object.insert("id".into(), id.clone());
+    }
+    Ok(Value::Object(object))
+}
+
+/// Builds the body of a JSON-RPC request, a batch when there are several calls.
+pub fn request_body(calls: &[Call]) -> Result<Vec<u8>, String> {
+    let mut objects = calls
+        .iter()
+        .map(call_object)
+        .collect::<Result<Vec<_>, _>>()?;
+    let body = match objects.len() {
+        0 => return Err("There are no calls to make".into()),
+        1 => objects.remove(0),
+        _ => Value::Array(objects),
+    };
+    Ok(serde_json::to_vec(&body).unwrap_or_default())
+}
+
+/// Splits a JSON-RPC request body back into its calls, with the params pretty-printed.
+pub fn parse_request_body(body: &[u8]) -> Option<Vec<Call>> {
+    let call = |value: &Value| {
+        if value.get("jsonrpc")? != "2.0" {
+            return None;
+        }
+        Some(Call {
+            method: value.get("method")?.as_str()?.to_string(),
+            params: value
+                .get("params")
+                .and_then(|params| serde_json::to_string_pretty(params).ok())
+                .unwrap_or_default(),
+            id: value.get("id").cloned(),
+        })
+    };
+    match serde_json::from_slice(body).ok()? {
+        Value::Array(values) if !values.is_empty() => values.iter().map(call).collect(),
+        value => call(&value).map(|call| vec![call]),
+    }
+}
+
+fn outcome(response: &Value) -> Option<Outcome> {
+    if let Some(result) = response.get("result") {
+        return Some(Outcome::Result(result.clone()));
+    }
+    let error = response.get("error")?;
+    Some(Outcome::Error {
+        code: error
+            .get("code")
+            .and_then(Value::as_i64)
+            .unwrap_or_default(),
+        message: error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        data: error.get("data").cloned(),
+    })
+}
+
+/// Pairs the responses in a body with the calls of the request by id, in the order the calls
+/// were made, followed by responses that answer none of them.
+///
+/// Returns `None` when the body isn't a JSON-RPC response.
+pub fn pair(calls: &[Call], body: &Value) -> Option<Vec<Exchange>> {
+    let responses = match body {
+        Value::Array(responses) => responses.iter().collect(),
+        response => vec![response],
+    };
+    let mut responses = responses
+        .into_iter()
+        .map(|response| {
+            if response.get("jsonrpc")? != "2.0" {
+                return None;
+            }
+            let id = response.get("id").cloned().unwrap_or(Value::Null);
+            Some((id, outcome(response)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if responses.is_empty() {
+        return None;
+    }
+
+    let mut exchanges: Vec<Exchange> = calls
+        .iter()
+        .map(|call| {
+            let outcome = match &call.id {
+                None => Outcome::Notified,
+                Some(id) => match responses.iter().position(|(other, _)| other == id) {
+                    Some(n) => responses.remove(n).1,
+                    None => Outcome::Missing,
+                },
+            };
+            Exchange {
+                method: Some(call.method.clone()),
+                id: call.id.clone(),
+                outcome,
+            }
+        })
+        .collect();
+    exchanges.extend(responses.into_iter().map(|(id, outcome)| Exchange {
+        method: None,
+        id: (!id.is_null()).then_some(id),
+        outcome,
+    }));
+    Some(exchanges)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn call(method: &str, params: &str, id: Option<u64>) -> Call {
+        Call {
+            method: method.into(),
+            params: params.into(),
+            id: id.map(Value::from),
+        }
+    }
+
+    #[test]
+    fn test_request_body() {
+        let calls = [call("sum", "[1, 2]", Some(1)), call("ping", "", None)];
+        let body = request_body(&calls).unwrap();
+        assert_eq!(
+            String::from_utf8(body.clone()).unwrap(),
+            "[{\"jsonrpc\":\"2.0\",\"method\":\"sum\",\"params\":[1,2],\"id\":1},{\"jsonrpc\":\"2.\
+             0\",\"method\":\"ping\"}]"
+        );
+        assert_eq!(
+            parse_request_body(&body),
+            Some(vec![
+                call("sum", "[\n  1,\n  2\n]", Some(1)),
+                call("ping", "", None)
+            ])
+        );
+
+        let body = request_body(&calls[..1]).unwrap();
+        assert!(body.starts_with(b"{"));
+        assert_eq!(
+            request_body(&[call("sum", "1", Some(1))]),
+            Err("Params must be a JSON array or object".into())
+        );
+        assert_eq!(
+            request_body(&[call(" ", "", Some(1))]),
+            Err("Every call needs a method".into())
+        );
+        assert_eq!(parse_request_body(b"{\"query\": \"{ a }\"}"), None);
+    }
+
+    #[test]
+    fn test_pair() {
+        let calls = [
+            call("sum", "[1, 2]", Some(1)),
+            call("ping", "", None),
+            call("divide", "[1, 0]", Some(2)),
+            call("slow", "", Some(3)),
+        ];
+        let body = json!([
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32000, "message": "Division by zero"}},
+            {"jsonrpc": "2.0", "id": 1, "result": 3},
+            {"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "Parse error"}},
+        ]);
+        assert_eq!(
+            pair(&calls, &body),
+            Some(vec![
+                Exchange {
+                    method: Some("sum".into()),
+                    id: Some(json!(1)),
+                    outcome: Outcome::Result(json!(3)),
+                },
+                Exchange {
+                    method: Some("ping".into()),
+                    id: None,
+                    outcome: Outcome::Notified,
+                },
+                Exchange {
+                    method: Some("divide".into()),
+                    id: Some(json!(2)),
+                    outcome: Outcome::Error {
+                        code: -32000,
+                        message: "Division by zero".into(),
+                        data: None,
+                    },
+                },
+                Exchange {
+                    method: Some("slow".into()),
+                    id: Some(json!(3)),
+                    outcome: Outcome::Missing,
+                },
+                Exchange {
+                    method: None,
+                    id: None,
+                    outcome: Outcome::Error {
+                        code: -32700,
+                        message: "Parse error".into(),
+                        data: None,
+                    },
+                },
+            ])
+        );
+        assert_eq!(pair(&calls, &json!({"id": 1, "result": 3})), None);
+        assert_eq!(pair(&calls, &json!([])), None);
+    }
+}
@@ -10,8 +10,14 @@ mod cli;
 mod components;
 mod config;
 mod errors;
+mod executor;
+mod graphql;
+mod history;
+mod jsonrpc;
 mod logging;
+mod session;
 mod tui;
+mod variables;
 
 pub const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
@@ -21,7 +27,7 @@ async fn main() -> Result<()> {
     logging::init()?;
 
     let args = Cli::parse();
-    let mut app = App::new(args.tick_rate, args.frame_rate)?;
+    let mut app = App::new(args.tick_rate, args.frame_rate, args.workspace)?;
     app.run().await?;
     Ok(())
 }
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::app::Mode;
+use crate::components::response::viewer::BodyView;
+use crate::components::response::ResponseTab;
+use crate::executor::{HttpRequest, HttpResponse};
+
+const SESSION_FILE: &str = "session.json";
+
+/// How often the session is written out while running, which bounds what a crash can lose.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Responses with larger bodies are left out of the session rather than written out every few
+/// seconds.
+pub const MAX_SAVED_BODY: usize = 1024 * 1024;
+
+/// The latest snapshot and where it belongs, for the panic hook to write out.
+static LATEST: Mutex<Option<(PathBuf, Session)>> = Mutex::new(None);
+
+/// The open tabs and where the user was in them, restored on the next launch.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub tabs: Vec<TabState>,
+    pub current: usize,
+    pub mode: Mode,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TabState {
+    /// The request as composed, including edits that were never sent.
+    pub request: HttpRequest,
+    /// The request as last sent, to tell whether the tab is dirty.
+    pub sent: HttpRequest,
+    pub response: Option<ResponseState>,
+}
+
+/// A response and how far into it the user had scrolled.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseState {
+    /// The redirects followed by the response, as shown in the Redirects tab.
+    pub chain: Vec<HttpResponse>,
+    pub hop: usize,
+    pub tab: ResponseTab,
+    pub view: BodyView,
+    pub body_offsets: HashMap<BodyView, usize>,
+    pub headers_offset: usize,
+    pub timing_offset: usize,
+    pub redirects_offset: usize,
+}
+
+/// Writes the session of a workspace out every few seconds and on quitting.
+#[derive(Debug, Default)]
+pub struct Autosave {
+    file: Option<PathBuf>,
+    /// When a snapshot was last taken, whether or not it had to be written out.
+    last_snapshot: Option<Instant>,
+    written: Option<Session>,
+}
+
+impl Autosave {
+    pub fn new(workspace_dir: PathBuf) -> Self {
+        Self {
+            file: Some(workspace_dir.join(SESSION_FILE)),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the session saved last time, if there is one that can still be read.
+    pub fn load(&self) -> Option<Session> {
+        let data = fs::read_to_string(self.file.as_ref()?).ok()?;
+        serde_json::from_str(&data)
+            .inspect_err(|err| error!("Failed to read the saved session: {err}"))
+            .ok()
+    }
+
+    /// Whether enough time has passed since the last snapshot to take another, which is worth
+    /// asking before building one as it copies every open response.
+    pub fn is_due(&self) -> bool {
+        self.file.is_some()
+            && self
+                .last_snapshot
+                .is_none_or(|last| last.elapsed() >= AUTOSAVE_INTERVAL)
+    }
+
+    /// Keeps a snapshot for the panic hook, writing it out if it changed since the last write.
+    pub fn update(&mut self, session: Session) {
+        let Some(file) = self.file.clone() else {
+            return;
+        };
+        if let Ok(mut latest) = LATEST.lock() {
+            *latest = Some((file, session.clone()));
+        }
+        self.last_snapshot = Some(Instant::now());
+        if self.written.as_ref() != Some(&session) {
+            self.save(session);
+        }
+    }
+
+    pub fn save(&mut self, session: Session) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Err(err) = write(file, &session) {
+            error!("Failed to save the session: {err}");
+        }
+        self.last_snapshot = Some(Instant::now());
+        self.written = Some(session);
+    }
+}
+
+/// Writes out the latest snapshot, for when the application panics and can't save its state.
+pub fn save_on_panic() {
+    // The lock may be held by the thread that panicked, in which case the autosave has to do.
+    let Ok(latest) = LATEST.try_lock() else {
+        return;
+    };
+    if let Some((file, session)) = latest.as_ref() {
+        let _ = write(file, session);
+    }
+}
+
+fn write(file: &PathBuf, session: &Session) -> Result<()> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write next to the file and move it into place, so a crash midway keeps the old session.
+    let temp = file.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_vec(session)?)?;
+    fs::rename(temp, file)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("papirus-session-{}", std::process::id()));
+        let mut autosave = Autosave::new(dir.clone());
+        assert_eq!(autosave.load(), None);
+
+        let session = Session {
+            tabs: vec![TabState {
+                request: HttpRequest::get("https://example.com/edited"),
+                sent: HttpRequest::get("https://example.com/"),
+                response: Some(ResponseState {
+                    chain: vec![HttpResponse {
+                        status: 200,
+                        ..HttpResponse::default()
+                    }],
+                    tab: ResponseTab::Headers,
+                    headers_offset: 3,
+                    ..ResponseState::default()
+                }),
+            }],
+            current: 0,
+            mode: Mode::Response,
+        };
+        assert!(autosave.is_due());
+        autosave.update(session.clone());
+        assert!(!autosave.is_due());
+        assert_eq!(autosave.load(), Some(session));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use color_eyre::Result;
+
+use crate::executor::HttpRequest;
+
+const VARIABLES_FILE: &str = "variables.json";
+
+/// Values captured from responses, persisted per workspace and written into requests wherever
+/// `{{name}}` appears.
+#[derive(Debug, Default, Clone)]
+pub struct Variables {
+    file: Option<PathBuf>,
+    values: BTreeMap<String, String>,
+}
+
+impl Variables {
+    pub fn load(workspace_dir: PathBuf) -> Self {
+        let file = workspace_dir.join(VARIABLES_FILE);
+        let values = fs::read_to_string(&file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            file: Some(file),
+            values,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Sets a variable and saves them all.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        self.values.insert(name.to_string(), value.to_string());
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(file, serde_json::to_string_pretty(&self.values)?)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces each `{{name}}` of a known variable in the text, leaving anything else as it is.
+    pub fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after
+                .find("}}")
+                .and_then(|end| Some((self.get(after[..end].trim())?, end)));
+            match value {
+                Some((value, end)) => {
+                    result.push_str(value);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    result.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// The request with variables written into its URL, headers and, if it is text, its body.
+    pub fn apply(&self, mut request: HttpRequest) -> HttpRequest {
+        if self.values.is_empty() {
+            return request;
+        }
+        request.url = self.substitute(&request.url);
+        for (name, value) in &mut request.headers {
+            *name = self.substitute(name);
+            *value = self.substitute(value);
+        }
+        if let Ok(body) = std::str::from_utf8(&request.body) {
+            request.body = self.substitute(body).into_bytes();
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_substitute() -> Result<()> {
+        let mut variables = Variables::default();
+        variables.set("token", "abc")?;
+        variables.set("id", "42")?;
+        assert_eq!(
+            variables.substitute("/items/{{id}}?t={{ token }}&u={{user}}&{{"),
+            "/items/42?t=abc&u={{user}}&{{"
+        );
+
+        let request = HttpRequest {
+            url: "https://api.example.com/items/{{id}}".into(),
+            headers: vec![("Authorization".into(), "Bearer {{token}}".into())],
+            body: br#"{"id": "{{id}}"}"#.to_vec(),
+            ..HttpRequest::default()
+        };
+        let request = variables.apply(request);
+        assert_eq!(request.url, "https://api.example.com/items/42");
+        assert_eq!(request.headers[0].1, "Bearer abc");
+        assert_eq!(request.body, br#"{"id": "42"}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("papirus-variables-{}", std::process::id()));
+        let mut variables = Variables::load(dir.clone());
+        variables.set("token", "abc")?;
+        assert_eq!(Variables::load(dir.clone()).get("token"), Some("abc"));
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}